@@ -1,96 +1,284 @@
-﻿use eframe::egui;
+﻿mod api_server;
+mod batch;
+mod compatibility;
+mod engine_switch;
+mod job_list;
+mod matrix;
+mod output_settings;
+mod plugin_table;
+mod project_list;
+mod scan;
+
+use eframe::egui;
 use rfd::FileDialog;
-use std::sync::mpsc::Receiver;
+use std::path::PathBuf;
+use std::sync::Arc;
 
+use crate::platform::Platform;
 use crate::storage;
-use crate::commands::{create_build_command, create_package_command, ProgressUpdate};
+use crate::watcher::FileWatcher;
+use crate::commands::BuildMode;
+use crate::jobs::{JobKind, JobQueue, JobSpec};
+use crate::version_stamp::restore_leftover_stamp;
 
 /// Main application state.
 pub struct BuildApp {
-    projects: Vec<storage::Project>,
+    settings: storage::Settings,
     selected_mode: BuildMode,
-    engine_location: Option<storage::Engine>,
     selected_project: Option<usize>,
     selected_platform: Platform,
-    build_progress: Option<f32>,       // Progress value (0.0 to 1.0)
-    progress_message: String,          // Status message to display
-    progress_rx: Option<Receiver<ProgressUpdate>>, // Receiver for progress updates
-}
-
-#[derive(PartialEq)]
-enum BuildMode {
-    Debug,
-    Development,
-    Shipping,
+    selected_profile: Option<String>,              // Name of the build profile applied to new jobs
+    jobs: Arc<JobQueue>,                           // Queued, running and finished jobs
+    job_list: job_list::JobListState,              // Open job log window
+    matrix: matrix::MatrixState,                   // Build matrix selection and results
+    batch: batch::BatchState,                      // Batch build selection and summary
+    project_watcher: Option<FileWatcher>,          // Watches registered .uproject files
+    project_list: project_list::ProjectListState,  // Search, rename and undo state of the project table
+    scan: scan::ScanState,                         // Running scan and discovered projects
+    pending_job: Option<compatibility::PendingJob>, // Job waiting for its preflight checks to be fixed or overridden
+    plugin_editor: plugin_table::PluginEditorState, // Plugin editor input and last edit outcome
+    engine_switch: Option<engine_switch::EngineSwitchState>, // Open "Switch engine version" dialog
+    api_server: api_server::ApiServerState,       // Local HTTP/JSON API server
+    settings_error: Option<String>,                // Why the settings could not be loaded at startup
+    settings_read_only: bool,                      // Settings are not saved, to keep a file that could not be loaded
+    platform_availability: Option<PlatformAvailability>, // Availability of each platform for the current selection
 }
 
-#[derive(PartialEq)]
-enum Platform {
-    Win64,
-    Linux,
-    Mac,
-    Android,
-    IOS,
-    PS4,
-    PS5,
-    XBoxOne,
-    XBoxSeries,
-    Switch,
+/// Availability of every platform, computed once per engine and project
+/// selection rather than every frame, since it checks the engine folders.
+struct PlatformAvailability {
+    /// Engine location, .uproject location and `TargetPlatforms` it was computed for.
+    key: (Option<PathBuf>, Option<PathBuf>, Vec<String>),
+    platforms: Vec<(Platform, Result<(), String>)>,
 }
 
 impl Default for BuildApp {
     fn default() -> Self {
-        let projects = storage::load_project_locations().unwrap_or_default();
-        let engine_location = storage::load_engine_location().unwrap_or_default();
-        println!("Loaded projects: {:?}", projects);
-        println!("Loaded engine location: {:?}", engine_location);
-        Self {
-            projects,
+        let (mut settings, settings_error, settings_read_only) = match storage::load_settings() {
+            Ok(settings) => (settings, None, false),
+            Err(e) => {
+                eprintln!("Failed to load settings: {}", e);
+                // A newer version's settings stay where they are; anything else is moved aside.
+                let set_aside = if e.is::<storage::NewerSettingsError>() {
+                    Ok(None)
+                } else {
+                    storage::set_aside_settings()
+                };
+                let (message, read_only) = match set_aside {
+                    Ok(Some(path)) => (
+                        format!("Could not load the settings ({}). They were moved to {}.", e, path.display()),
+                        false,
+                    ),
+                    Ok(None) => (
+                        format!("Could not load the settings ({}). Changes are not saved in this session.", e),
+                        true,
+                    ),
+                    Err(move_error) => (
+                        format!(
+                            "Could not load the settings ({}) nor move them aside ({}). Changes are not saved in this session.",
+                            e, move_error
+                        ),
+                        true,
+                    ),
+                };
+                (storage::Settings::default(), Some(message), read_only)
+            }
+        };
+        for project in &mut settings.projects {
+            if let Err(e) = project.refresh() {
+                eprintln!("Failed to refresh project {}: {}", project.name, e);
+            }
+            match restore_leftover_stamp(&project.location) {
+                Ok(Some(path)) => println!("Restored {:?} left stamped by an interrupted package", path),
+                Ok(None) => {}
+                Err(e) => eprintln!("Failed to restore the config of {}: {}", project.name, e),
+            }
+        }
+        println!("Loaded projects: {:?}", settings.projects);
+        println!("Loaded engines: {:?}", settings.engines);
+        let jobs = JobQueue::new(settings.preferences.max_parallel_jobs, Some(storage::config_dir()));
+        let mut app = Self {
+            settings,
             selected_mode: BuildMode::Development,
-            engine_location,
             selected_project: None,
             selected_platform: Platform::Win64,
-            build_progress: None,
-            progress_message: "Idle".to_owned(),
-            progress_rx: None,
-        }
+            selected_profile: None,
+            jobs,
+            job_list: Default::default(),
+            matrix: Default::default(),
+            batch: Default::default(),
+            project_watcher: None,
+            project_list: Default::default(),
+            scan: Default::default(),
+            pending_job: None,
+            plugin_editor: Default::default(),
+            engine_switch: None,
+            api_server: Default::default(),
+            settings_error,
+            settings_read_only,
+            platform_availability: None,
+        };
+        app.update_project_watcher();
+        app.rescan_on_startup();
+        app.update_api_server();
+        app
     }
 }
 
-impl eframe::App for BuildApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Poll for progress updates from the running process.
-        {
-            if let Some(rx) = self.progress_rx.as_mut() {
-                let mut finished = false;
-                while let Ok(update) = rx.try_recv() {
-                    match update {
-                        ProgressUpdate::Progress(p) => {
-                            self.build_progress = Some(p);
-                            if (p - 1.0).abs() < 0.001 {
-                                self.progress_message = "finalizing...".to_owned();
-                            } else {
-                                self.progress_message = format!("{:.0}% complete", p * 100.0);
-                            }
-                        }
-                        ProgressUpdate::Stage(msg) => {
-                            self.progress_message = msg;
-                        }
-                        ProgressUpdate::Finished(msg) => {
-                            self.build_progress = None;
-                            self.progress_message = msg;
-                            finished = true;
-                        }
+impl BuildApp {
+    fn save_settings(&self) {
+        self.publish_api_settings();
+        if self.settings_read_only {
+            eprintln!("Settings not saved: they could not be loaded at startup");
+            return;
+        }
+        if let Err(e) = storage::save_settings(&self.settings) {
+            eprintln!("Failed to save settings: {}", e);
+        }
+    }
+
+    /// Describes a job for the selected project, engine, platform and mode.
+    fn job_spec(&self, kind: JobKind) -> Option<JobSpec> {
+        let Some(engine) = self.settings.selected_engine() else {
+            eprintln!("No engine location selected");
+            return None;
+        };
+        let Some(project) = self.selected_project.map(|index| &self.settings.projects[index]) else {
+            eprintln!("No project selected");
+            return None;
+        };
+        let mut spec = JobSpec::new(kind, project, engine, self.selected_platform, self.selected_mode);
+        if let Some(profile) = self.selected_profile() {
+            spec.apply_profile(profile, project);
+        }
+        Some(spec)
+    }
+
+    /// The build profile picked for new jobs, if any.
+    fn selected_profile(&self) -> Option<&storage::Profile> {
+        self.selected_profile.as_deref().and_then(|name| self.settings.find_profile(name))
+    }
+
+    /// Picks the build profile applied to new jobs, with its platform and
+    /// configuration when they are known.
+    fn profile_ui(&mut self, ui: &mut egui::Ui) {
+        if self.settings.profiles.is_empty() {
+            return;
+        }
+        let mut selected = self.selected_profile().map(|profile| profile.name.clone());
+        ui.horizontal(|ui| {
+            ui.label("Profile:");
+            egui::ComboBox::from_id_salt("profile_select")
+                .selected_text(selected.as_deref().unwrap_or("None"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut selected, None, "None");
+                    for profile in &self.settings.profiles {
+                        ui.selectable_value(&mut selected, Some(profile.name.clone()), &profile.name);
                     }
-                }
-                if finished {
-                    self.progress_rx = None;
-                }
+                });
+        });
+        if selected == self.selected_profile {
+            return;
+        }
+        self.selected_profile = selected;
+        if let Some(profile) = self.selected_profile() {
+            let platform = Platform::from_name(&profile.platform);
+            let mode = BuildMode::from_name(&profile.configuration);
+            if let Some(platform) = platform {
+                self.selected_platform = platform;
+            }
+            if let Some(mode) = mode {
+                self.selected_mode = mode;
+            }
+        }
+    }
+
+    /// Moves the platform selection to the first available platform when
+    /// the selected one cannot be built with the selected engine and project.
+    fn ensure_available_platform(&mut self) {
+        let availability = self.platform_availability();
+        if availability.iter().any(|(platform, available)| *platform == self.selected_platform && available.is_ok()) {
+            return;
+        }
+        if let Some((platform, _)) = availability.iter().find(|(_, available)| available.is_ok()) {
+            self.selected_platform = *platform;
+        }
+    }
+
+    /// Availability of every platform with the selected engine and project,
+    /// recomputed only when the selection changes.
+    fn platform_availability(&mut self) -> Vec<(Platform, Result<(), String>)> {
+        let engine = self.settings.selected_engine();
+        let project = self.selected_project.map(|index| &self.settings.projects[index]);
+        let key = (
+            engine.map(|engine| engine.location.clone()),
+            project.map(|project| project.location.clone()),
+            project.map(|project| project.target_platforms.clone()).unwrap_or_default(),
+        );
+        if self.platform_availability.as_ref().is_none_or(|cached| cached.key != key) {
+            let platforms = Platform::ALL
+                .into_iter()
+                .map(|platform| (platform, platform.availability(engine, project)))
+                .collect();
+            self.platform_availability = Some(PlatformAvailability { key, platforms });
+        }
+        self.platform_availability.as_ref().unwrap().platforms.clone()
+    }
+
+    /// Re-parses every registered .uproject file.
+    fn refresh_projects(&mut self) {
+        for project in &mut self.settings.projects {
+            if let Err(e) = project.refresh() {
+                eprintln!("Failed to refresh project {}: {}", project.name, e);
+            }
+        }
+        self.save_settings();
+    }
+
+    /// Starts, stops or retargets the .uproject watcher to match the preferences.
+    fn update_project_watcher(&mut self) {
+        if !self.settings.preferences.watch_projects {
+            self.project_watcher = None;
+            return;
+        }
+        let paths = self.settings.projects.iter().map(|p| p.location.clone()).collect();
+        match &self.project_watcher {
+            Some(watcher) => watcher.set_paths(paths),
+            None => self.project_watcher = Some(FileWatcher::new(paths)),
+        }
+    }
+
+    /// Refreshes the projects whose .uproject file changed on disk.
+    fn poll_project_watcher(&mut self) {
+        let Some(watcher) = &self.project_watcher else {
+            return;
+        };
+        let changed = watcher.changed_paths();
+        if changed.is_empty() {
+            return;
+        }
+        for project in self.settings.projects.iter_mut().filter(|p| changed.contains(&p.location)) {
+            println!("Project file changed: {:?}", project.location);
+            if let Err(e) = project.refresh() {
+                eprintln!("Failed to refresh project {}: {}", project.name, e);
             }
         }
+        self.save_settings();
+    }
+}
+
+impl eframe::App for BuildApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll_project_watcher();
+        self.refresh_matrix_cells();
+        self.refresh_batch();
 
         // The upper part of the UI: Engine, Project, Build Mode, and Platform selections.
         egui::CentralPanel::default().show(ctx, |ui| {
+            if let Some(error) = &self.settings_error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+
             // Engine Selection
             ui.horizontal(|ui| {
                 if ui.button("Open Engine").clicked() {
@@ -100,173 +288,105 @@ impl eframe::App for BuildApp {
                     {
                         println!("Selected engine file: {:?}", file);
                         if file.file_name().unwrap() == "UE5.sln" {
-                            if let Err(e) = storage::save_engine_location(file.clone()) {
-                                eprintln!("Failed to save engine location: {}", e);
-                            } else {
-                                self.engine_location = Some(storage::Engine { location: file });
-                                println!("Engine location saved: {:?}", self.engine_location);
+                            if !self.settings.engines.iter().any(|engine| engine.location == file) {
+                                self.settings.engines.push(storage::Engine { location: file.clone() });
                             }
+                            self.settings.preferences.selected_engine = Some(file);
+                            self.save_settings();
+                            println!("Engine location saved: {:?}", self.settings.preferences.selected_engine);
                         } else {
                             eprintln!("Selected file is not UE5.sln");
                         }
                     }
                 }
-                if let Some(engine) = &self.engine_location {
-                    ui.label(engine.location.to_string_lossy());
+                let selected_text = self
+                    .settings
+                    .selected_engine()
+                    .map(|engine| engine.location.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "No engine selected".to_owned());
+                let mut selected_engine = self.settings.preferences.selected_engine.clone();
+                egui::ComboBox::from_id_salt("engine_select")
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        for engine in &self.settings.engines {
+                            ui.selectable_value(
+                                &mut selected_engine,
+                                Some(engine.location.clone()),
+                                engine.location.to_string_lossy(),
+                            );
+                        }
+                    });
+                if selected_engine != self.settings.preferences.selected_engine {
+                    self.settings.preferences.selected_engine = selected_engine;
+                    self.save_settings();
                 }
             });
             ui.separator();
 
             // Project Selection
-            ui.horizontal_wrapped(|ui| {
-                if ui.button("Open Project").clicked() {
-                    if let Some(file) = FileDialog::new()
-                        .add_filter("Unreal Project", &["uproject"])
-                        .pick_file()
-                    {
-                        println!("Selected project file: {:?}", file);
-                        if let Some(existing_index) = self.projects.iter().position(|p| p.location == file) {
-                            self.selected_project = Some(existing_index);
-                            println!("Project already exists, selected project index: {:?}", self.selected_project);
-                        } else {
-                            let new_project = storage::Project::new(file);
-                            println!("New project added: {:?}", new_project);
-                            self.projects.push(new_project.clone());
-                            self.selected_project = Some(self.projects.len() - 1);
-                            println!("Selected project index: {:?}", self.selected_project);
-                            if let Err(e) = storage::save_project_locations(&self.projects) {
-                                eprintln!("Failed to save project locations: {}", e);
-                            }
-                        }
-                    }
-                }
-                for (index, project) in self.projects.iter().enumerate() {
-                    let project_info = format!(
-                        "{} (Engine: {}, Plugins: {})",
-                        project.name,
-                        project.engine_version,
-                        project.plugins.join(", ")
-                    );
-                    ui.radio_value(&mut self.selected_project, Some(index), project_info);
-                }
-            });
+            self.project_list_ui(ui);
+            self.plugin_table_ui(ui);
+            self.output_settings_ui(ui);
             ui.separator();
 
-            // Build Mode Selection
+            // Build profile, then Build Mode Selection
+            self.profile_ui(ui);
             ui.horizontal(|ui| {
-                ui.radio_value(&mut self.selected_mode, BuildMode::Debug, "Debug");
-                ui.radio_value(&mut self.selected_mode, BuildMode::Development, "Development");
-                ui.radio_value(&mut self.selected_mode, BuildMode::Shipping, "Shipping");
+                for mode in BuildMode::ALL {
+                    ui.radio_value(&mut self.selected_mode, mode, mode.as_str());
+                }
             });
             ui.separator();
 
-            // Platform Selection
+            // Platform Selection, limited to what the engine and project support.
+            self.ensure_available_platform();
+            let availability = self.platform_availability();
             ui.horizontal_wrapped(|ui| {
-                ui.radio_value(&mut self.selected_platform, Platform::Win64, "Win64");
-                ui.radio_value(&mut self.selected_platform, Platform::Linux, "Linux");
-                ui.radio_value(&mut self.selected_platform, Platform::Mac, "Mac");
-                ui.radio_value(&mut self.selected_platform, Platform::Android, "Android");
-                ui.radio_value(&mut self.selected_platform, Platform::IOS, "iOS");
-                ui.radio_value(&mut self.selected_platform, Platform::PS4, "PS4");
-                ui.radio_value(&mut self.selected_platform, Platform::PS5, "PS5");
-                ui.radio_value(&mut self.selected_platform, Platform::XBoxOne, "XBoxOne");
-                ui.radio_value(&mut self.selected_platform, Platform::XBoxSeries, "XBoxSeries");
-                ui.radio_value(&mut self.selected_platform, Platform::Switch, "Switch");
+                for (platform, available) in availability {
+                    match available {
+                        Ok(()) => {
+                            ui.radio_value(&mut self.selected_platform, platform, platform.name());
+                        }
+                        Err(reason) => {
+                            let selected = self.selected_platform == platform;
+                            ui.add_enabled(false, egui::RadioButton::new(selected, platform.name()))
+                                .on_disabled_hover_text(reason);
+                        }
+                    }
+                }
             });
         });
 
         // Compute flags for the bottom panel.
-        let running = self.build_progress.is_some();
         let package_condition = self.selected_project
-            .map(|index| self.projects[index].engine_version == "From Source")
+            .map(|index| self.settings.projects[index].engine_version == "From Source")
             .unwrap_or(false);
 
-        // Bottom panel for Build & Package buttons and the progress bar.
+        // Bottom panel for Build & Package buttons and the job queue.
         egui::TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
-                if ui.add_enabled(!running, egui::Button::new("Build")).clicked() {
-                    if let Some(engine) = &self.engine_location {
-                        if let Some(selected_project_index) = self.selected_project {
-                            let project = &self.projects[selected_project_index];
-                            let platform = match self.selected_platform {
-                                Platform::Win64 => "Win64",
-                                Platform::Linux => "Linux",
-                                Platform::Mac => "Mac",
-                                Platform::Android => "Android",
-                                Platform::IOS => "iOS",
-                                Platform::PS4 => "PS4",
-                                Platform::PS5 => "PS5",
-                                Platform::XBoxOne => "XBoxOne",
-                                Platform::XBoxSeries => "XBoxSeries",
-                                Platform::Switch => "Switch",
-                            };
-                            let optimization_type = match self.selected_mode {
-                                BuildMode::Debug => "Debug",
-                                BuildMode::Development => "Development",
-                                BuildMode::Shipping => "Shipping",
-                            };
-                            let rx = create_build_command(
-                                &engine.location,
-                                &project.name,
-                                platform,
-                                optimization_type,
-                                &project.location,
-                            );
-                            self.progress_rx = Some(rx);
-                            self.build_progress = Some(0.0);
-                            self.progress_message = "Build started...".to_owned();
-                        } else {
-                            eprintln!("No project selected");
-                        }
-                    } else {
-                        eprintln!("No engine location selected");
-                    }
+                if ui.button("Build").clicked() {
+                    self.request_job(JobKind::Build);
                 }
 
-                if ui.add_enabled(!running && package_condition, egui::Button::new("Package")).clicked() {
-                    if let Some(engine) = &self.engine_location {
-                        if let Some(selected_project_index) = self.selected_project {
-                            let project = &self.projects[selected_project_index];
-                            let platform = match self.selected_platform {
-                                Platform::Win64 => "Win64",
-                                Platform::Linux => "Linux",
-                                Platform::Mac => "Mac",
-                                Platform::Android => "Android",
-                                Platform::IOS => "iOS",
-                                Platform::PS4 => "PS4",
-                                Platform::PS5 => "PS5",
-                                Platform::XBoxOne => "XBoxOne",
-                                Platform::XBoxSeries => "XBoxSeries",
-                                Platform::Switch => "Switch",
-                            };
-                            let optimization_type = match self.selected_mode {
-                                BuildMode::Debug => "Debug",
-                                BuildMode::Development => "Development",
-                                BuildMode::Shipping => "Shipping",
-                            };
-                            let rx = create_package_command(
-                                &engine.location,
-                                platform,
-                                optimization_type,
-                                &project.location,
-                            );
-                            self.progress_rx = Some(rx);
-                            self.build_progress = Some(0.0);
-                            self.progress_message = "Packaging started...".to_owned();
-                        } else {
-                            eprintln!("No project selected");
-                        }
-                    } else {
-                        eprintln!("No engine location selected");
-                    }
+                if ui.add_enabled(package_condition, egui::Button::new("Package")).clicked() {
+                    self.request_job(JobKind::Package);
                 }
+
+                self.matrix_button_ui(ui);
+                self.batch_button_ui(ui);
             });
-            if let Some(progress) = self.build_progress {
-                ui.add(egui::ProgressBar::new(progress).text(&self.progress_message));
-            } else {
-                ui.label(&self.progress_message);
-            }
+            ui.separator();
+            self.job_list_ui(ui);
+            ui.separator();
+            self.api_server_ui(ui);
         });
+        self.compatibility_window_ui(ctx);
+        self.engine_switch_window_ui(ctx);
+        self.matrix_window_ui(ctx);
+        self.batch_window_ui(ctx);
+        self.job_log_window_ui(ctx);
+        self.job_size_window_ui(ctx);
         ctx.request_repaint();
     }
 }
@@ -0,0 +1,73 @@
+use eframe::egui;
+use std::sync::{Arc, RwLock};
+
+use super::BuildApp;
+use crate::server::{self, ApiServer};
+use crate::storage;
+
+/// The running API server, or why it could not start.
+#[derive(Default)]
+pub struct ApiServerState {
+    server: Option<ApiServer>,
+    error: Option<String>,
+    /// The settings requests are resolved against, replaced whenever the
+    /// GUI saves them.
+    settings: Arc<RwLock<storage::Settings>>,
+}
+
+impl BuildApp {
+    /// Hands the current settings to the API server.
+    pub(super) fn publish_api_settings(&self) {
+        *self.api_server.settings.write().unwrap() = self.settings.clone();
+    }
+
+    /// Starts or stops the API server to match the preferences.
+    pub(super) fn update_api_server(&mut self) {
+        let preferences = &self.settings.preferences;
+        if !preferences.api_server {
+            self.api_server.server = None;
+            self.api_server.error = None;
+            return;
+        }
+        if self.api_server.server.is_some() {
+            return;
+        }
+        let address = match preferences.api_address.trim() {
+            "" => server::DEFAULT_ADDRESS,
+            address => address,
+        };
+        self.publish_api_settings();
+        let snapshot = self.api_server.settings.clone();
+        let settings: server::SettingsSource = Arc::new(move || snapshot.read().unwrap().clone());
+        match ApiServer::start(address, preferences.api_token.clone(), settings, self.jobs.clone()) {
+            Ok(server) => {
+                self.api_server.server = Some(server);
+                self.api_server.error = None;
+            }
+            Err(e) => {
+                eprintln!("Failed to start the API server on {}: {}", address, e);
+                self.api_server.server = None;
+                self.api_server.error = Some(format!("Cannot listen on {}: {}", address, e));
+            }
+        }
+    }
+
+    /// Draws the API server toggle and its status.
+    pub(super) fn api_server_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut self.settings.preferences.api_server, "API server")
+                .on_hover_text("Serve projects, engines and the job queue over HTTP/JSON for scripts and dashboards")
+                .changed()
+            {
+                self.save_settings();
+                self.update_api_server();
+            }
+            if let Some(server) = &self.api_server.server {
+                ui.label(format!("http://{}", server.address()));
+            } else if let Some(error) = &self.api_server.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        });
+    }
+}
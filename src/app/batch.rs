@@ -0,0 +1,360 @@
+use eframe::egui;
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::BuildApp;
+use crate::commands::BuildMode;
+use crate::engine_association::engine_for;
+use crate::jobs::{BuildTarget, JobKind, JobSpec, JobStatus};
+use crate::platform::Platform;
+use crate::preflight::{spawn_preflight, Preflight};
+use crate::storage;
+
+/// State of the "Batch build" window.
+#[derive(Default)]
+pub struct BatchState {
+    open: bool,
+    target: BuildTarget,
+    /// Projects ticked for "Build selected", by .uproject location.
+    selected: Vec<PathBuf>,
+    /// The last batch that was queued.
+    run: Option<BatchRun>,
+}
+
+/// A queued batch: one build per project, each with the project's own engine.
+struct BatchRun {
+    target: BuildTarget,
+    platform: Platform,
+    mode: BuildMode,
+    description: String,
+    started: SystemTime,
+    entries: Vec<BatchEntry>,
+    /// Entries waiting for their preflight checks, with their jobs.
+    checking: Vec<(usize, JobSpec)>,
+    /// Receiver for the preflight of `checking`.
+    preflight: Option<Receiver<Vec<Preflight>>>,
+    /// Report written once every job finished.
+    report: Option<PathBuf>,
+    /// Why the report could not be written.
+    report_error: Option<String>,
+}
+
+struct BatchEntry {
+    project: String,
+    engine: Option<PathBuf>,
+    /// `None` when the project was skipped because no engine matched or a
+    /// preflight check failed.
+    job: Option<u64>,
+    status: Option<JobStatus>,
+    message: String,
+    duration: Option<Duration>,
+    log_file: Option<PathBuf>,
+}
+
+impl BatchEntry {
+    fn finished(&self) -> bool {
+        self.job.is_none() || matches!(self.status, Some(JobStatus::Succeeded | JobStatus::Failed))
+    }
+
+    fn result(&self) -> &'static str {
+        match self.status {
+            None => "skipped",
+            Some(JobStatus::Succeeded) => "succeeded",
+            Some(JobStatus::Failed) => "failed",
+            Some(JobStatus::Queued) => "queued",
+            Some(JobStatus::Running) => "running",
+        }
+    }
+}
+
+impl BuildApp {
+    /// Draws the "Batch build…" button.
+    pub(super) fn batch_button_ui(&mut self, ui: &mut egui::Ui) {
+        if ui
+            .button("Batch build…")
+            .on_hover_text("Build every or selected projects, each with its own engine")
+            .clicked()
+        {
+            self.batch.open = true;
+        }
+    }
+
+    /// Draws the project checklist, the batch options and the summary of
+    /// the last batch.
+    pub(super) fn batch_window_ui(&mut self, ctx: &egui::Context) {
+        if !self.batch.open {
+            return;
+        }
+
+        let mut open = true;
+        let mut queue = None;
+        let mut show_log = None;
+        egui::Window::new("Batch build").open(&mut open).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Target:");
+                for target in BuildTarget::ALL {
+                    ui.radio_value(&mut self.batch.target, target, target.as_str());
+                }
+            });
+            ui.label(format!(
+                "Platform and configuration: {} {} (from the main window)",
+                self.selected_platform, self.selected_mode
+            ));
+            egui::ScrollArea::vertical()
+                .id_salt("batch_projects_scroll")
+                .max_height(160.0)
+                .show(ui, |ui| {
+                    for project in &self.settings.projects {
+                        let mut checked = self.batch.selected.contains(&project.location);
+                        if ui.checkbox(&mut checked, project.display_name()).changed() {
+                            if checked {
+                                self.batch.selected.push(project.location.clone());
+                            } else {
+                                self.batch.selected.retain(|location| location != &project.location);
+                            }
+                        }
+                    }
+                });
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(!self.settings.projects.is_empty(), egui::Button::new("Build all"))
+                    .clicked()
+                {
+                    queue = Some(self.settings.projects.iter().map(|p| p.location.clone()).collect::<Vec<_>>());
+                }
+                if ui
+                    .add_enabled(!self.batch.selected.is_empty(), egui::Button::new("Build selected"))
+                    .clicked()
+                {
+                    queue = Some(self.batch.selected.clone());
+                }
+            });
+
+            if let Some(run) = &self.batch.run {
+                ui.separator();
+                batch_summary_ui(ui, run, &mut show_log);
+            }
+        });
+        self.batch.open = open;
+
+        if let Some(projects) = queue {
+            self.queue_batch(&projects);
+        }
+        if let Some(id) = show_log {
+            self.show_job_log(id);
+        }
+    }
+
+    /// Queues a build of each project in `locations` with the engine its
+    /// .uproject is associated with, once its preflight checks ran. Projects
+    /// without a matching engine, or that fail a preflight check, are
+    /// reported as skipped.
+    fn queue_batch(&mut self, locations: &[PathBuf]) {
+        let (target, platform, mode) = (self.batch.target, self.selected_platform, self.selected_mode);
+        let mut entries = Vec::new();
+        let mut checking = Vec::new();
+        let mut preflight_jobs = Vec::new();
+        for project in self.settings.projects.iter().filter(|p| locations.contains(&p.location)) {
+            let mut entry = BatchEntry {
+                project: project.display_name().to_owned(),
+                engine: None,
+                job: None,
+                status: None,
+                message: String::new(),
+                duration: None,
+                log_file: None,
+            };
+            match engine_for(project, &self.settings.engines) {
+                Ok(engine) => {
+                    entry.engine = Some(engine.location.clone());
+                    entry.message = "running preflight checks".to_owned();
+                    let mut spec = JobSpec {
+                        target,
+                        ..JobSpec::new(JobKind::Build, project, engine, platform, mode)
+                    };
+                    if let Some(profile) = self.selected_profile() {
+                        spec.apply_profile(profile, project);
+                    }
+                    preflight_jobs.push((spec.clone(), project.clone()));
+                    checking.push((entries.len(), spec));
+                }
+                Err(e) => {
+                    eprintln!("Skipping {} in batch build: {}", project.name, e);
+                    entry.message = e;
+                }
+            }
+            entries.push(entry);
+        }
+        self.batch.run = Some(BatchRun {
+            target,
+            platform,
+            mode,
+            description: format!("Build {} {} {}", target.as_str(), platform, mode),
+            started: SystemTime::now(),
+            entries,
+            preflight: (!checking.is_empty()).then(|| spawn_preflight(preflight_jobs)),
+            checking,
+            report: None,
+            report_error: None,
+        });
+        self.write_batch_report_if_done();
+    }
+
+    /// Queues the jobs of the batch whose preflight passed once the checks
+    /// are done, and skips the others.
+    fn poll_batch_preflight(&mut self) {
+        let Some(run) = &mut self.batch.run else {
+            return;
+        };
+        let Some(results) = run.preflight.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return;
+        };
+        run.preflight = None;
+        for ((index, spec), preflight) in std::mem::take(&mut run.checking).into_iter().zip(results) {
+            let entry = &mut run.entries[index];
+            let failures: Vec<String> = preflight
+                .failures()
+                .map(|check| format!("{}: {}", check.name, check.message))
+                .collect();
+            if failures.is_empty() {
+                entry.message.clear();
+                entry.job = Some(self.jobs.enqueue(spec));
+                entry.status = Some(JobStatus::Queued);
+            } else {
+                entry.message = format!("preflight failed: {}", failures.join("; "));
+                eprintln!("Skipping {} in batch build: {}", entry.project, entry.message);
+            }
+        }
+    }
+
+    /// Copies the status of the batch jobs and writes the report once they
+    /// have all finished. Jobs removed from the queue before they finished
+    /// count as skipped.
+    pub(super) fn refresh_batch(&mut self) {
+        self.poll_batch_preflight();
+        let Some(run) = &mut self.batch.run else {
+            return;
+        };
+        self.jobs.with_jobs(|jobs| {
+            for entry in &mut run.entries {
+                if entry.finished() {
+                    continue;
+                }
+                match jobs.iter().find(|job| Some(job.id) == entry.job) {
+                    Some(job) => {
+                        entry.status = Some(job.status);
+                        entry.message = job.message.clone();
+                        entry.duration = job.duration;
+                        entry.log_file = job.log_file.clone();
+                    }
+                    None => {
+                        entry.job = None;
+                        entry.status = None;
+                        entry.message = "removed from the queue".to_owned();
+                    }
+                }
+            }
+        });
+        self.write_batch_report_if_done();
+    }
+
+    /// Writes `reports/batch-<timestamp>.json` in the configuration
+    /// directory once every entry of the batch has finished.
+    fn write_batch_report_if_done(&mut self) {
+        let Some(run) = &mut self.batch.run else {
+            return;
+        };
+        if run.report.is_some()
+            || run.report_error.is_some()
+            || run.preflight.is_some()
+            || !run.entries.iter().all(BatchEntry::finished)
+        {
+            return;
+        }
+        let seconds = |time: SystemTime| time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let count = |result: &str| run.entries.iter().filter(|entry| entry.result() == result).count();
+        let report = json!({
+            "description": run.description,
+            "target": run.target,
+            "platform": run.platform,
+            "config": run.mode,
+            "started": seconds(run.started),
+            "finished": seconds(SystemTime::now()),
+            "summary": {
+                "succeeded": count("succeeded"),
+                "failed": count("failed"),
+                "skipped": count("skipped"),
+            },
+            "projects": run.entries.iter().map(|entry| json!({
+                "project": entry.project,
+                "engine": entry.engine,
+                "result": entry.result(),
+                "message": entry.message,
+                "duration_seconds": entry.duration.map(|duration| duration.as_secs()),
+                "log_file": entry.log_file,
+            })).collect::<Vec<_>>(),
+        });
+        let path = storage::config_dir()
+            .join("reports")
+            .join(format!("batch-{}.json", seconds(run.started)));
+        let result = std::fs::create_dir_all(path.parent().unwrap())
+            .map_err(|e| e.into())
+            .and_then(|_| storage::write_atomic(&path, serde_json::to_string_pretty(&report).unwrap().as_bytes()));
+        match result {
+            Ok(()) => {
+                println!("Batch report written to {:?}", path);
+                run.report = Some(path);
+            }
+            Err(e) => {
+                eprintln!("Failed to write batch report {:?}: {}", path, e);
+                run.report_error = Some(format!("Cannot write report {}: {}", path.display(), e));
+            }
+        }
+    }
+}
+
+fn batch_summary_ui(ui: &mut egui::Ui, run: &BatchRun, show_log: &mut Option<u64>) {
+    let count = |result: &str| run.entries.iter().filter(|entry| entry.result() == result).count();
+    let checking = |index: usize| run.checking.iter().any(|(checking, _)| *checking == index);
+    ui.strong(&run.description);
+    ui.label(format!(
+        "{} compiled, {} failed, {} skipped, {} pending",
+        count("succeeded"),
+        count("failed"),
+        count("skipped") - run.checking.len(),
+        count("queued") + count("running") + run.checking.len()
+    ));
+    egui::Grid::new("batch_grid").striped(true).num_columns(4).show(ui, |ui| {
+        for (index, entry) in run.entries.iter().enumerate() {
+            ui.label(&entry.project);
+            match entry.status {
+                None if checking(index) => ui.label("⏳ Checking"),
+                None => ui.colored_label(ui.visuals().warn_fg_color, "Skipped"),
+                Some(JobStatus::Succeeded) => ui.colored_label(egui::Color32::from_rgb(0x3c, 0xb3, 0x71), "✔ Compiled"),
+                Some(JobStatus::Failed) => ui.colored_label(ui.visuals().error_fg_color, "✖ Failed"),
+                Some(JobStatus::Running) => ui.label("▶ Running"),
+                Some(JobStatus::Queued) => ui.label("⏳ Queued"),
+            };
+            ui.label(&entry.message);
+            match entry.job {
+                Some(id) if entry.status != Some(JobStatus::Queued) => {
+                    if ui.small_button("Log").clicked() {
+                        *show_log = Some(id);
+                    }
+                }
+                _ => {
+                    ui.label("");
+                }
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(report) = &run.report {
+        ui.label(format!("Report: {}", report.to_string_lossy()));
+    }
+    if let Some(error) = &run.report_error {
+        ui.colored_label(ui.visuals().error_fg_color, error);
+    }
+}
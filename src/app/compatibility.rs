@@ -0,0 +1,125 @@
+use eframe::egui;
+use std::sync::mpsc::Receiver;
+
+use super::BuildApp;
+use crate::jobs::{JobKind, JobSpec};
+use crate::preflight::{spawn_preflight, CheckStatus, Preflight};
+
+/// A job waiting for its preflight checks, or held back until the user
+/// fixes or overrides its failed or flagged checks.
+pub struct PendingJob {
+    spec: JobSpec,
+    /// Receiver for the checks while they run.
+    rx: Option<Receiver<Vec<Preflight>>>,
+    preflight: Option<Preflight>,
+}
+
+impl BuildApp {
+    /// Runs the preflight checks before queueing `kind` for the selected
+    /// project, and asks for confirmation unless every check passed.
+    pub(super) fn request_job(&mut self, kind: JobKind) {
+        let Some(spec) = self.job_spec(kind) else {
+            return;
+        };
+        self.preflight_job(spec);
+    }
+
+    /// Starts the preflight of `spec` on a worker thread. The job is queued
+    /// once it passes, otherwise held back.
+    fn preflight_job(&mut self, spec: JobSpec) {
+        let Some(project) = self.settings.projects.iter().find(|project| project.location == spec.uproject) else {
+            return;
+        };
+        let rx = spawn_preflight(vec![(spec.clone(), project.clone())]);
+        self.pending_job = Some(PendingJob { spec, rx: Some(rx), preflight: None });
+    }
+
+    /// Queues the pending job once its preflight passed, or keeps it with
+    /// the checks to show.
+    fn poll_preflight(&mut self) {
+        let Some(pending) = &mut self.pending_job else {
+            return;
+        };
+        let Some(preflight) = pending.rx.as_ref().and_then(|rx| rx.try_recv().ok()).and_then(|mut results| results.pop())
+        else {
+            return;
+        };
+        pending.rx = None;
+        if preflight.passed() {
+            if let Some(pending) = self.pending_job.take() {
+                self.jobs.enqueue(pending.spec);
+            }
+            return;
+        }
+        for check in preflight.checks.iter().filter(|check| check.status != CheckStatus::Pass) {
+            println!("Preflight {:?}: {}: {}", check.status, check.name, check.message);
+        }
+        pending.preflight = Some(preflight);
+    }
+
+    /// Shows the preflight panel of the pending job, with every check and
+    /// its outcome.
+    pub(super) fn compatibility_window_ui(&mut self, ctx: &egui::Context) {
+        self.poll_preflight();
+        let Some(pending) = &self.pending_job else {
+            return;
+        };
+        let mut proceed = false;
+        let mut check_again = false;
+        let mut cancel = false;
+        egui::Window::new("Preflight")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(pending.spec.label());
+                ui.separator();
+                let Some(preflight) = &pending.preflight else {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Running preflight checks…");
+                    });
+                    cancel = ui.button("Cancel").clicked();
+                    return;
+                };
+                egui::Grid::new("preflight_checks").num_columns(2).striped(true).show(ui, |ui| {
+                    for check in &preflight.checks {
+                        let (symbol, color) = match check.status {
+                            CheckStatus::Pass => ("✔", ui.visuals().text_color()),
+                            CheckStatus::Warn => ("⚠", ui.visuals().warn_fg_color),
+                            CheckStatus::Fail => ("✖", ui.visuals().error_fg_color),
+                        };
+                        ui.colored_label(color, format!("{} {}", symbol, check.name));
+                        ui.label(&check.message);
+                        ui.end_row();
+                    }
+                });
+                ui.separator();
+                if preflight.has_failures() {
+                    ui.label("Fix the failed checks and check again, or override them.");
+                }
+                ui.horizontal(|ui| {
+                    let label = match pending.spec.kind {
+                        JobKind::Build => "Build anyway",
+                        JobKind::Package | JobKind::PackagePlugin(_) => "Package anyway",
+                        JobKind::Clean | JobKind::GenerateProjectFiles => "Continue anyway",
+                    };
+                    proceed = ui.button(label).clicked();
+                    check_again = ui.button("Check again").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        if proceed {
+            if let Some(pending) = self.pending_job.take() {
+                println!("Preflight overridden for {}", pending.spec.label());
+                self.jobs.enqueue(pending.spec);
+            }
+        } else if check_again {
+            if let Some(pending) = self.pending_job.take() {
+                self.preflight_job(pending.spec);
+            }
+        } else if cancel {
+            self.pending_job = None;
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use eframe::egui;
+use std::path::PathBuf;
+
+use super::BuildApp;
+use crate::engine_association::{association_for, switch_engine};
+use crate::jobs::{JobKind, JobSpec};
+
+/// State of the "Switch engine version" dialog.
+pub struct EngineSwitchState {
+    project: usize,
+    engine: Option<PathBuf>,
+    regenerate: bool,
+    error: Option<String>,
+    /// Association the selected engine resolves to, cached per engine since
+    /// resolving it may query the registry.
+    preview: Option<(PathBuf, Result<String, String>)>,
+}
+
+impl BuildApp {
+    /// Opens the "Switch engine version" dialog for a project.
+    pub(super) fn open_engine_switch(&mut self, project: usize) {
+        self.engine_switch = Some(EngineSwitchState {
+            project,
+            engine: self.settings.preferences.selected_engine.clone(),
+            regenerate: false,
+            error: None,
+            preview: None,
+        });
+    }
+
+    /// Draws the "Switch engine version" dialog and applies the switch.
+    pub(super) fn engine_switch_window_ui(&mut self, ctx: &egui::Context) {
+        let Some(state) = &mut self.engine_switch else {
+            return;
+        };
+        let project = &self.settings.projects[state.project];
+        let mut apply = false;
+        let mut open = true;
+        egui::Window::new(format!("Switch engine of {}", project.display_name()))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("Current engine: {}", project.engine_version));
+                let selected_text = state
+                    .engine
+                    .as_ref()
+                    .map(|engine| engine.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "Select an engine".to_owned());
+                egui::ComboBox::from_id_salt("engine_switch_select")
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        for engine in &self.settings.engines {
+                            ui.selectable_value(
+                                &mut state.engine,
+                                Some(engine.location.clone()),
+                                engine.location.to_string_lossy(),
+                            );
+                        }
+                    });
+                if let Some(engine) = self
+                    .settings
+                    .engines
+                    .iter()
+                    .find(|engine| Some(&engine.location) == state.engine.as_ref())
+                {
+                    if state.preview.as_ref().map(|(location, _)| location) != Some(&engine.location) {
+                        let association = association_for(engine).map_err(|e| e.to_string());
+                        state.preview = Some((engine.location.clone(), association));
+                    }
+                    match state.preview.as_ref().map(|(_, association)| association) {
+                        Some(Ok(association)) => {
+                            ui.label(format!("EngineAssociation: {}", association));
+                        }
+                        Some(Err(e)) => {
+                            ui.colored_label(ui.visuals().error_fg_color, e);
+                        }
+                        None => {}
+                    }
+                }
+                ui.checkbox(&mut state.regenerate, "Regenerate project files afterwards");
+                if let Some(error) = &state.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                apply = ui
+                    .add_enabled(state.engine.is_some(), egui::Button::new("Switch"))
+                    .clicked();
+            });
+
+        if !open {
+            self.engine_switch = None;
+            return;
+        }
+        if !apply {
+            return;
+        }
+        let Some(engine) = self
+            .settings
+            .engines
+            .iter()
+            .find(|engine| Some(&engine.location) == state.engine.as_ref())
+            .cloned()
+        else {
+            return;
+        };
+        let project = &mut self.settings.projects[state.project];
+        match switch_engine(project, &engine) {
+            Ok(_) => {
+                if let Err(e) = project.refresh() {
+                    eprintln!("Failed to refresh project {}: {}", project.name, e);
+                }
+                if state.regenerate {
+                    let spec = JobSpec::new(
+                        JobKind::GenerateProjectFiles,
+                        project,
+                        &engine,
+                        self.selected_platform,
+                        self.selected_mode,
+                    );
+                    self.jobs.enqueue(spec);
+                }
+                self.engine_switch = None;
+                self.save_settings();
+            }
+            Err(e) => {
+                eprintln!("Failed to switch engine of {}: {}", project.name, e);
+                state.error = Some(e.to_string());
+            }
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::BuildApp;
+use crate::commands::open_in_file_browser;
+use crate::disk::format_size;
+use crate::jobs::{Job, JobStatus};
+use crate::output::UtcDateTime;
+use crate::size_report::{format_change, load_report, SizeEntry, SizeReportFile};
+
+/// UI state of the job queue panel.
+#[derive(Default)]
+pub struct JobListState {
+    /// Job whose log window is open.
+    log_job: Option<u64>,
+    /// Title and size report of the package whose size window is open.
+    size_report: Option<(String, Result<SizeReportFile, String>)>,
+}
+
+/// An action requested from the job list, applied once the list is drawn.
+enum JobAction {
+    Remove(u64),
+    Move { id: u64, up: bool },
+    ShowLog(u64),
+    ShowSize(u64),
+    OpenLogFile(PathBuf),
+}
+
+/// Formats a duration as `m:ss`.
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+impl BuildApp {
+    /// Draws the job queue: one row per job with its status, progress and
+    /// actions, plus the parallelism setting.
+    pub(super) fn job_list_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.strong("Jobs");
+            ui.separator();
+            ui.label("Run in parallel:");
+            let mut max_parallel = self.settings.preferences.max_parallel_jobs.max(1);
+            if ui
+                .add(egui::DragValue::new(&mut max_parallel).range(1..=16))
+                .on_hover_text("Jobs on the same engine still run one at a time")
+                .changed()
+            {
+                self.settings.preferences.max_parallel_jobs = max_parallel;
+                self.jobs.set_max_parallel(max_parallel);
+                self.save_settings();
+            }
+            if ui.button("Clear finished").clicked() {
+                self.jobs.clear_finished();
+            }
+        });
+
+        let mut action = None;
+        self.jobs.with_jobs(|jobs| {
+            if jobs.is_empty() {
+                ui.label("No jobs. Build or Package adds one to the queue.");
+                return;
+            }
+            egui::ScrollArea::vertical()
+                .id_salt("job_list_scroll")
+                .max_height(180.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("job_grid").striped(true).num_columns(4).show(ui, |ui| {
+                        for job in jobs {
+                            if let Some(row_action) = job_row_ui(ui, job) {
+                                action = Some(row_action);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+        });
+
+        match action {
+            Some(JobAction::Remove(id)) => {
+                self.jobs.remove(id);
+            }
+            Some(JobAction::Move { id, up }) => self.jobs.move_job(id, up),
+            Some(JobAction::ShowLog(id)) => self.show_job_log(id),
+            Some(JobAction::ShowSize(id)) => self.show_job_size(id),
+            Some(JobAction::OpenLogFile(path)) => self.open_log_file(&path),
+            None => {}
+        }
+    }
+
+    /// Opens the log window of job `id`.
+    pub(super) fn show_job_log(&mut self, id: u64) {
+        self.job_list.log_job = Some(id);
+    }
+
+    /// Opens the size report window of package job `id`.
+    fn show_job_size(&mut self, id: u64) {
+        self.job_list.size_report = self.jobs.with_jobs(|jobs| {
+            let job = jobs.iter().find(|job| job.id == id)?;
+            let report = match &job.size_report {
+                Some(path) => load_report(path).map_err(|e| e.to_string()),
+                None => Err("The package did not write a size report.".to_owned()),
+            };
+            Some((format!("Size: {}", job.spec.label()), report))
+        });
+    }
+
+    /// Opens a job log file with the system's default application.
+    pub(super) fn open_log_file(&self, path: &Path) {
+        if let Err(e) = open_in_file_browser(path) {
+            eprintln!("Failed to open {:?}: {}", path, e);
+        }
+    }
+
+    /// Shows the output of the job picked with its "Log" button.
+    pub(super) fn job_log_window_ui(&mut self, ctx: &egui::Context) {
+        let Some(id) = self.job_list.log_job else {
+            return;
+        };
+        let mut open = true;
+        self.jobs.with_jobs(|jobs| {
+            let Some(job) = jobs.iter().find(|job| job.id == id) else {
+                open = false;
+                return;
+            };
+            egui::Window::new(format!("Log: {}", job.spec.label()))
+                .id(egui::Id::new("job_log_window"))
+                .open(&mut open)
+                .default_size([700.0, 400.0])
+                .show(ctx, |ui| {
+                    let lines: Vec<&str> = job.log_lines().collect();
+                    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                    egui::ScrollArea::both()
+                        .auto_shrink(false)
+                        .stick_to_bottom(true)
+                        .show_rows(ui, row_height, lines.len(), |ui, range| {
+                            for line in &lines[range] {
+                                ui.monospace(*line);
+                            }
+                        });
+                });
+        });
+        if !open {
+            self.job_list.log_job = None;
+        }
+    }
+
+    /// Shows the size report picked with a package's "Size" button, with the
+    /// changes since the previous package of the same platform and
+    /// configuration.
+    pub(super) fn job_size_window_ui(&mut self, ctx: &egui::Context) {
+        let Some((title, report)) = &self.job_list.size_report else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new(title.as_str())
+            .id(egui::Id::new("job_size_window"))
+            .open(&mut open)
+            .default_size([600.0, 450.0])
+            .show(ctx, |ui| match report {
+                Ok(file) => size_report_ui(ui, file),
+                Err(e) => {
+                    ui.colored_label(ui.visuals().error_fg_color, format!("No size report: {}", e));
+                }
+            });
+        if !open {
+            self.job_list.size_report = None;
+        }
+    }
+}
+
+/// The tables of a size report.
+fn size_report_ui(ui: &mut egui::Ui, file: &SizeReportFile) {
+    let report = &file.report;
+    ui.label(format!("{} in {} files", format_size(report.total_bytes), report.files));
+    match (&file.previous, file.previous_finished) {
+        (Some(previous), Some(finished)) => {
+            let date = UtcDateTime::from_system_time(std::time::UNIX_EPOCH + Duration::from_secs(finished)).date();
+            ui.label(format!(
+                "{} since the package of {}",
+                format_change(previous.total_bytes, report.total_bytes),
+                date
+            ));
+        }
+        _ => {
+            ui.weak("No earlier package to compare with");
+        }
+    }
+    let previous = file.previous.as_ref();
+    egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
+        ui.collapsing("By type", |ui| {
+            size_table_ui(ui, "size_by_type", &report.by_type, previous.map(|previous| &previous.by_type[..]));
+        });
+        ui.collapsing("Containers (pak, utoc, ucas)", |ui| {
+            size_table_ui(ui, "size_containers", &report.containers, previous.map(|previous| &previous.containers[..]));
+        });
+        ui.collapsing("Largest files", |ui| {
+            size_table_ui(ui, "size_largest", &report.largest, None);
+        });
+    });
+}
+
+/// One row per entry with its size, and its change since `previous` when given.
+fn size_table_ui(ui: &mut egui::Ui, id: &str, entries: &[SizeEntry], previous: Option<&[SizeEntry]>) {
+    egui::Grid::new(id).striped(true).num_columns(3).show(ui, |ui| {
+        for entry in entries {
+            ui.label(if entry.name.is_empty() { "(none)" } else { &entry.name });
+            ui.label(format_size(entry.bytes));
+            if let Some(previous) = previous {
+                let before = previous.iter().find(|old| old.name == entry.name).map_or(0, |old| old.bytes);
+                let change = format_change(before, entry.bytes);
+                if entry.bytes > before {
+                    ui.colored_label(ui.visuals().warn_fg_color, change);
+                } else {
+                    ui.label(change);
+                }
+            }
+            ui.end_row();
+        }
+        for old in previous.unwrap_or_default() {
+            if !entries.iter().any(|entry| entry.name == old.name) {
+                ui.weak(&old.name);
+                ui.weak(format_size(0));
+                ui.label("removed");
+                ui.end_row();
+            }
+        }
+    });
+}
+
+fn job_row_ui(ui: &mut egui::Ui, job: &Job) -> Option<JobAction> {
+    let mut action = None;
+    match job.status {
+        JobStatus::Queued => ui.label("⏳ Queued"),
+        JobStatus::Running => ui.label("▶ Running"),
+        JobStatus::Succeeded => ui.label("✔ Succeeded"),
+        JobStatus::Failed => ui.colored_label(ui.visuals().error_fg_color, "✖ Failed"),
+    };
+    ui.label(job.spec.label());
+
+    let elapsed = job.elapsed().map(format_duration).unwrap_or_default();
+    if job.status == JobStatus::Running {
+        ui.add(
+            egui::ProgressBar::new(job.progress)
+                .desired_width(260.0)
+                .text(format!("{} ({})", job.message, elapsed)),
+        );
+    } else if elapsed.is_empty() {
+        ui.label(&job.message);
+    } else {
+        ui.label(format!("{} ({})", job.message, elapsed));
+    }
+
+    ui.horizontal(|ui| {
+        if job.status == JobStatus::Queued {
+            if ui.small_button("⏶").on_hover_text("Run earlier").clicked() {
+                action = Some(JobAction::Move { id: job.id, up: true });
+            }
+            if ui.small_button("⏷").on_hover_text("Run later").clicked() {
+                action = Some(JobAction::Move { id: job.id, up: false });
+            }
+        } else if ui.small_button("Log").clicked() {
+            action = Some(JobAction::ShowLog(job.id));
+        }
+        if job.size_report.is_some()
+            && ui.small_button("Size").on_hover_text("Size breakdown of the staged build").clicked()
+        {
+            action = Some(JobAction::ShowSize(job.id));
+        }
+        if let Some(log_file) = &job.log_file {
+            if ui.small_button("Log file").on_hover_text(log_file.to_string_lossy()).clicked() {
+                action = Some(JobAction::OpenLogFile(log_file.clone()));
+            }
+        }
+        if job.status != JobStatus::Running && ui.small_button("✖").on_hover_text("Remove from the list").clicked() {
+            action = Some(JobAction::Remove(job.id));
+        }
+    });
+    action
+}
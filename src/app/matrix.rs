@@ -0,0 +1,313 @@
+use eframe::egui;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+
+use super::BuildApp;
+use crate::commands::BuildMode;
+use crate::jobs::{JobKind, JobSpec, JobStatus};
+use crate::platform::Platform;
+use crate::preflight::{spawn_preflight, Preflight};
+
+/// State of the "Build matrix" window.
+#[derive(Default)]
+pub struct MatrixState {
+    open: bool,
+    /// Run packages instead of builds.
+    package: bool,
+    /// The jobs of the last matrix that was queued.
+    run: Option<MatrixRun>,
+}
+
+/// A queued matrix: one job per platform and configuration.
+struct MatrixRun {
+    label: String,
+    platforms: Vec<Platform>,
+    modes: Vec<BuildMode>,
+    cells: Vec<MatrixCell>,
+    /// Jobs of the cells, until their preflight checks are done.
+    checking: Vec<JobSpec>,
+    /// Receiver for the preflight of `checking`, one result per cell.
+    preflight: Option<Receiver<Vec<Preflight>>>,
+}
+
+/// One platform × configuration job. The last known status is kept so the
+/// grid survives the job being cleared from the queue.
+struct MatrixCell {
+    platform: Platform,
+    mode: BuildMode,
+    /// `None` while the preflight runs, or when it failed.
+    job: Option<u64>,
+    status: JobStatus,
+    progress: f32,
+    log_file: Option<PathBuf>,
+    /// Why the cell was skipped.
+    skipped: Option<String>,
+}
+
+/// An action requested from a matrix cell.
+enum CellAction {
+    ShowLog(u64),
+    OpenLogFile(PathBuf),
+}
+
+impl BuildApp {
+    /// Draws the "Build matrix…" button.
+    pub(super) fn matrix_button_ui(&mut self, ui: &mut egui::Ui) {
+        if ui
+            .button("Build matrix…")
+            .on_hover_text("Queue one job per ticked platform and configuration")
+            .clicked()
+        {
+            self.matrix.open = true;
+        }
+    }
+
+    /// Draws the platform × configuration selection and the result grid.
+    pub(super) fn matrix_window_ui(&mut self, ctx: &egui::Context) {
+        if !self.matrix.open {
+            return;
+        }
+        let availability = self.platform_availability();
+
+        let mut open = true;
+        let mut queue = false;
+        let mut selection_changed = false;
+        let mut cell_action = None;
+        let project = self.selected_project.map(|index| &self.settings.projects[index]);
+        let can_package = project.is_some_and(|project| project.engine_version == "From Source");
+        let engine = self.settings.selected_engine();
+        let has_engine = engine.is_some();
+        let preferences = &mut self.settings.preferences;
+        egui::Window::new("Build matrix").open(&mut open).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.matrix.package, false, "Build");
+                ui.add_enabled_ui(can_package, |ui| ui.radio_value(&mut self.matrix.package, true, "Package"));
+            });
+            ui.label("Platforms:");
+            ui.horizontal_wrapped(|ui| {
+                for (platform, available) in &availability {
+                    let platform = *platform;
+                    match available {
+                        Ok(()) => {
+                            selection_changed |=
+                                toggle_ui(ui, &mut preferences.matrix_platforms, platform, platform.name());
+                        }
+                        Err(reason) => {
+                            let mut checked = preferences.matrix_platforms.contains(&platform);
+                            ui.add_enabled(false, egui::Checkbox::new(&mut checked, platform.name()))
+                                .on_disabled_hover_text(reason);
+                        }
+                    }
+                }
+            });
+            ui.label("Configurations:");
+            ui.horizontal(|ui| {
+                for mode in BuildMode::ALL {
+                    selection_changed |= toggle_ui(ui, &mut preferences.matrix_configurations, mode, mode.as_str());
+                }
+            });
+
+            let platforms = availability
+                .iter()
+                .filter(|(platform, available)| available.is_ok() && preferences.matrix_platforms.contains(platform))
+                .count();
+            let count = platforms * preferences.matrix_configurations.len();
+            let ready = count > 0 && project.is_some() && has_engine;
+            ui.horizontal(|ui| {
+                queue = ui
+                    .add_enabled(ready, egui::Button::new(format!("Queue {} job(s)", count)))
+                    .clicked();
+                match project {
+                    Some(project) => ui.label(format!("for {}", project.display_name())),
+                    None => ui.label("Select a project first"),
+                };
+            });
+
+            if let Some(run) = &self.matrix.run {
+                ui.separator();
+                ui.strong(&run.label);
+                egui::Grid::new("matrix_grid").striped(true).show(ui, |ui| {
+                    ui.label("");
+                    for mode in &run.modes {
+                        ui.strong(mode.as_str());
+                    }
+                    ui.end_row();
+                    for platform in &run.platforms {
+                        ui.strong(platform.name());
+                        for mode in &run.modes {
+                            let cell = run
+                                .cells
+                                .iter()
+                                .find(|cell| cell.platform == *platform && cell.mode == *mode);
+                            if let Some(action) = cell.and_then(|cell| matrix_cell_ui(ui, cell)) {
+                                cell_action = Some(action);
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+            }
+        });
+        self.matrix.open = open;
+
+        if selection_changed {
+            // Keep the ticked entries in display order.
+            let preferences = &mut self.settings.preferences;
+            preferences.matrix_platforms = Platform::ALL
+                .into_iter()
+                .filter(|platform| preferences.matrix_platforms.contains(platform))
+                .collect();
+            preferences.matrix_configurations = BuildMode::ALL
+                .into_iter()
+                .filter(|mode| preferences.matrix_configurations.contains(mode))
+                .collect();
+            self.save_settings();
+        }
+        if queue {
+            self.queue_matrix();
+        }
+        match cell_action {
+            Some(CellAction::ShowLog(id)) => self.show_job_log(id),
+            Some(CellAction::OpenLogFile(path)) => self.open_log_file(&path),
+            None => {}
+        }
+    }
+
+    /// Queues one job per ticked platform and configuration for the
+    /// selected project. Platforms the engine or project cannot build are left out.
+    fn queue_matrix(&mut self) {
+        let kind = if self.matrix.package { JobKind::Package } else { JobKind::Build };
+        let Some(engine) = self.settings.selected_engine() else {
+            eprintln!("No engine location selected");
+            return;
+        };
+        let Some(project) = self.selected_project.map(|index| &self.settings.projects[index]) else {
+            eprintln!("No project selected");
+            return;
+        };
+        let platforms: Vec<Platform> = self
+            .settings
+            .preferences
+            .matrix_platforms
+            .iter()
+            .copied()
+            .filter(|platform| platform.availability(Some(engine), Some(project)).is_ok())
+            .collect();
+        let modes = self.settings.preferences.matrix_configurations.clone();
+        let mut cells = Vec::new();
+        let mut checking = Vec::new();
+        for &platform in &platforms {
+            for &mode in &modes {
+                // Each cell gets its own staging and archive directories.
+                let mut spec = JobSpec::new(kind.clone(), project, engine, platform, mode);
+                if let Some(profile) = self.selected_profile() {
+                    spec.apply_profile(profile, project);
+                }
+                checking.push(spec);
+                cells.push(MatrixCell {
+                    platform,
+                    mode,
+                    job: None,
+                    status: JobStatus::Queued,
+                    progress: 0.0,
+                    log_file: None,
+                    skipped: None,
+                });
+            }
+        }
+        let preflight = spawn_preflight(checking.iter().map(|spec| (spec.clone(), project.clone())).collect());
+        let verb = if kind == JobKind::Package { "Package" } else { "Build" };
+        self.matrix.run = Some(MatrixRun {
+            label: format!("{} {}", verb, project.name),
+            platforms,
+            modes,
+            cells,
+            checking,
+            preflight: Some(preflight),
+        });
+    }
+
+    /// Queues the cells whose preflight passed once the checks are done;
+    /// the others are skipped with the failed checks.
+    fn poll_matrix_preflight(&mut self) {
+        let Some(run) = &mut self.matrix.run else {
+            return;
+        };
+        let Some(results) = run.preflight.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return;
+        };
+        run.preflight = None;
+        for ((cell, spec), preflight) in run.cells.iter_mut().zip(std::mem::take(&mut run.checking)).zip(results) {
+            let failures: Vec<String> = preflight
+                .failures()
+                .map(|check| format!("{}: {}", check.name, check.message))
+                .collect();
+            if failures.is_empty() {
+                cell.job = Some(self.jobs.enqueue(spec));
+            } else {
+                eprintln!("Skipping {} {} in build matrix: {}", cell.platform, cell.mode, failures.join("; "));
+                cell.skipped = Some(format!("preflight failed: {}", failures.join("; ")));
+            }
+        }
+    }
+
+    /// Copies the status of the matrix jobs still in the queue.
+    pub(super) fn refresh_matrix_cells(&mut self) {
+        self.poll_matrix_preflight();
+        let Some(run) = &mut self.matrix.run else {
+            return;
+        };
+        self.jobs.with_jobs(|jobs| {
+            for cell in &mut run.cells {
+                if let Some(job) = jobs.iter().find(|job| Some(job.id) == cell.job) {
+                    cell.status = job.status;
+                    cell.progress = job.progress;
+                    cell.log_file = job.log_file.clone();
+                }
+            }
+        });
+    }
+}
+
+/// A checkbox adding `value` to or removing it from `selected`.
+fn toggle_ui<T: PartialEq>(ui: &mut egui::Ui, selected: &mut Vec<T>, value: T, label: &str) -> bool {
+    let mut checked = selected.contains(&value);
+    let changed = ui.checkbox(&mut checked, label).changed();
+    if changed {
+        if checked {
+            selected.push(value);
+        } else {
+            selected.retain(|item| *item != value);
+        }
+    }
+    changed
+}
+
+fn matrix_cell_ui(ui: &mut egui::Ui, cell: &MatrixCell) -> Option<CellAction> {
+    let mut action = None;
+    ui.horizontal(|ui| {
+        if let Some(reason) = &cell.skipped {
+            ui.colored_label(ui.visuals().warn_fg_color, "Skipped").on_hover_text(reason);
+            return;
+        }
+        let Some(job) = cell.job else {
+            ui.spinner().on_hover_text("Running preflight checks");
+            return;
+        };
+        match cell.status {
+            JobStatus::Queued => ui.label("⏳"),
+            JobStatus::Running => ui.label(format!("▶ {:.0}%", cell.progress * 100.0)),
+            JobStatus::Succeeded => ui.colored_label(egui::Color32::from_rgb(0x3c, 0xb3, 0x71), "✔ Pass"),
+            JobStatus::Failed => ui.colored_label(ui.visuals().error_fg_color, "✖ Fail"),
+        };
+        if cell.status != JobStatus::Queued && ui.small_button("Log").clicked() {
+            action = Some(CellAction::ShowLog(job));
+        }
+        if let Some(log_file) = &cell.log_file {
+            if ui.small_button("📄").on_hover_text(log_file.to_string_lossy()).clicked() {
+                action = Some(CellAction::OpenLogFile(log_file.clone()));
+            }
+        }
+    });
+    action
+}
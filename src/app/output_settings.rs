@@ -0,0 +1,168 @@
+use eframe::egui;
+use rfd::FileDialog;
+use std::path::PathBuf;
+
+use super::BuildApp;
+use crate::archive::ArchiveFormat;
+use crate::output::PackageOutput;
+use crate::storage::PostAction;
+
+impl BuildApp {
+    /// Draws the staging and archive settings of the selected project, with
+    /// the directories a package for the selected platform and mode would
+    /// use, and what is done after a successful package.
+    pub(super) fn output_settings_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(index) = self.selected_project else {
+            return;
+        };
+        let mut save = false;
+        egui::CollapsingHeader::new("Output folders")
+            .id_salt("output_folders")
+            .show(ui, |ui| {
+                let output = &mut self.settings.projects[index].output;
+                egui::Grid::new("output_settings").num_columns(2).show(ui, |ui| {
+                    ui.label("Staging root");
+                    save |= path_field_ui(ui, &mut output.staging_root, "<project>/Builds");
+                    ui.end_row();
+
+                    ui.label("Staging subfolder");
+                    let staging = egui::TextEdit::singleline(&mut output.staging_template).hint_text("{platform}/{config}");
+                    save |= ui.add(staging).lost_focus();
+                    ui.end_row();
+
+                    ui.label("Archive root");
+                    save |= path_field_ui(ui, &mut output.archive_root, "Not archived");
+                    ui.end_row();
+
+                    ui.label("Archive subfolder");
+                    let archive = egui::TextEdit::singleline(&mut output.archive_template)
+                        .hint_text("{project}/{platform}/{config}/{date}-{changelist}");
+                    save |= ui.add(archive).lost_focus();
+                    ui.end_row();
+
+                    ui.label("Keep archives");
+                    save |= ui
+                        .add(egui::DragValue::new(&mut output.keep_archives).range(0..=100))
+                        .on_hover_text("Older archives next to a new one are removed; 0 keeps them all")
+                        .changed();
+                    ui.end_row();
+                });
+                ui.weak("Placeholders: {project} {platform} {config} {date} {time} (UTC) {changelist}");
+
+                let project = &self.settings.projects[index];
+                let preview =
+                    PackageOutput::preview(&project.output, project, self.selected_platform, self.selected_mode);
+                ui.label(format!("Staging: {}", preview.staging_dir.display()));
+                if let Some(archive_dir) = &preview.archive_dir {
+                    ui.label(format!("Archive: {}", archive_dir.display()));
+                    if preview.keep_archives > 0 && !preview.can_prune() {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            "Old archives are not removed: the last archive subfolder needs {date} or {time}",
+                        );
+                    }
+                }
+
+                ui.separator();
+                ui.label("After packaging");
+                let actions = &mut self.settings.projects[index].post_actions;
+                save |= post_actions_ui(ui, actions);
+            });
+        if save {
+            self.save_settings();
+        }
+    }
+}
+
+/// A folder path field with a "Browse…" button. An empty field is `None`.
+/// Returns whether the edit is complete and should be saved.
+fn path_field_ui(ui: &mut egui::Ui, path: &mut Option<PathBuf>, hint: &str) -> bool {
+    let mut text = path.as_ref().map(|path| path.to_string_lossy().to_string()).unwrap_or_default();
+    let mut done = false;
+    ui.horizontal(|ui| {
+        let response = ui.add(egui::TextEdit::singleline(&mut text).hint_text(hint));
+        if response.changed() {
+            *path = (!text.is_empty()).then(|| PathBuf::from(&text));
+        }
+        done |= response.lost_focus();
+        if ui.button("Browse…").clicked() {
+            if let Some(dir) = FileDialog::new().pick_folder() {
+                *path = Some(dir);
+                done = true;
+            }
+        }
+    });
+    done
+}
+
+/// The list of post actions, in the order they run, with a menu to add
+/// more. Returns whether the list changed and should be saved.
+fn post_actions_ui(ui: &mut egui::Ui, actions: &mut Vec<PostAction>) -> bool {
+    let mut save = false;
+    let mut remove = None;
+    for (index, action) in actions.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                remove = Some(index);
+            }
+            match action {
+                PostAction::CopyTo { destination } => {
+                    ui.label("Copy to");
+                    let mut path = (!destination.as_os_str().is_empty()).then(|| destination.clone());
+                    save |= path_field_ui(ui, &mut path, "Folder");
+                    *destination = path.unwrap_or_default();
+                }
+                PostAction::Archive { format } => {
+                    ui.label("Archive as");
+                    egui::ComboBox::from_id_salt(("archive_format", index))
+                        .selected_text(format!(".{}", format.extension()))
+                        .show_ui(ui, |ui| {
+                            for choice in ArchiveFormat::ALL {
+                                save |= ui
+                                    .selectable_value(format, choice, format!(".{}", choice.extension()))
+                                    .changed();
+                            }
+                        });
+                }
+                PostAction::Command { command } => {
+                    ui.label("Run");
+                    save |= ui
+                        .add(egui::TextEdit::singleline(command).hint_text("upload.bat"))
+                        .on_hover_text(
+                            "Runs from the staging folder with UBT_PROJECT, UBT_UPROJECT, UBT_PLATFORM, \
+                             UBT_CONFIG, UBT_STAGING_DIR and UBT_ARCHIVE_DIR set",
+                        )
+                        .lost_focus();
+                }
+                action => {
+                    ui.label(action.label());
+                }
+            }
+        });
+    }
+    if let Some(index) = remove {
+        actions.remove(index);
+        save = true;
+    }
+    if actions.is_empty() {
+        ui.weak("Nothing");
+    }
+    ui.menu_button("Add action", |ui| {
+        let choices = [
+            ("Open folder", PostAction::OpenFolder),
+            ("Copy to…", PostAction::CopyTo { destination: PathBuf::new() }),
+            ("Zip archive", PostAction::Archive { format: ArchiveFormat::Zip }),
+            ("tar.zst archive", PostAction::Archive { format: ArchiveFormat::TarZst }),
+            ("Run command", PostAction::Command { command: String::new() }),
+            ("Launch game", PostAction::Launch),
+        ];
+        for (name, action) in choices {
+            if ui.button(name).clicked() {
+                actions.push(action);
+                save = true;
+                ui.close_menu();
+            }
+        }
+    });
+    save
+}
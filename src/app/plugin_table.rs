@@ -0,0 +1,224 @@
+use eframe::egui;
+use std::path::PathBuf;
+
+use super::BuildApp;
+use crate::jobs::JobKind;
+use crate::plugins::{PluginDescriptor, PluginReference};
+use crate::uproject::UProjectFile;
+
+/// State of the plugin editor below the plugin table.
+#[derive(Default)]
+pub struct PluginEditorState {
+    /// Name typed in the "Add plugin" field.
+    new_plugin: String,
+    /// Outcome of the last edit.
+    status: Option<Result<String, String>>,
+}
+
+/// An edit of the selected project's `Plugins` array.
+enum PluginEdit {
+    SetEnabled(String, bool),
+    Remove(String),
+}
+
+/// An action triggered from a row of the plugin table.
+enum PluginAction {
+    Edit(PluginEdit),
+    /// Package the project-local plugin described by this .uplugin file.
+    Package(PathBuf),
+}
+
+/// One row of the plugin table: a .uproject entry, a project-local
+/// descriptor, or both.
+struct PluginRow<'a> {
+    name: &'a str,
+    reference: Option<&'a PluginReference>,
+    descriptor: Option<&'a PluginDescriptor>,
+}
+
+impl BuildApp {
+    /// Draws the plugin table of the selected project and applies the
+    /// edits made in it to the .uproject file.
+    pub(super) fn plugin_table_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(project_index) = self.selected_project else {
+            return;
+        };
+        let project = &self.settings.projects[project_index];
+        let can_package = self.settings.selected_engine().is_some();
+        let mut action = None;
+
+        let mut rows: Vec<PluginRow> = project
+            .plugins
+            .iter()
+            .map(|reference| PluginRow {
+                name: &reference.name,
+                reference: Some(reference),
+                descriptor: project
+                    .local_plugins
+                    .iter()
+                    .find(|descriptor| descriptor.name.eq_ignore_ascii_case(&reference.name)),
+            })
+            .collect();
+        for descriptor in &project.local_plugins {
+            if !rows.iter().any(|row| row.name.eq_ignore_ascii_case(&descriptor.name)) {
+                rows.push(PluginRow {
+                    name: &descriptor.name,
+                    reference: None,
+                    descriptor: Some(descriptor),
+                });
+            }
+        }
+
+        egui::CollapsingHeader::new(format!("Plugins of {} ({})", project.display_name(), rows.len()))
+            .id_salt("plugin_table")
+            .show(ui, |ui| {
+                if rows.is_empty() {
+                    ui.label("This project does not reference any plugin.");
+                }
+                egui::ScrollArea::both()
+                    .id_salt("plugin_table_scroll")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("plugin_grid").striped(true).num_columns(9).show(ui, |ui| {
+                            for header in
+                                ["Plugin", "Enabled", "Source", "Version", "Engine", "Modules", "Depends on", "Platforms", ""]
+                            {
+                                ui.strong(header);
+                            }
+                            ui.end_row();
+                            for row in &rows {
+                                if let Some(row_action) = plugin_row_ui(ui, row, can_package) {
+                                    action = Some(row_action);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+                ui.horizontal(|ui| {
+                    ui.label("Add plugin:");
+                    ui.text_edit_singleline(&mut self.plugin_editor.new_plugin);
+                    let name = self.plugin_editor.new_plugin.trim();
+                    if ui.add_enabled(!name.is_empty(), egui::Button::new("Add")).clicked() {
+                        action = Some(PluginAction::Edit(PluginEdit::SetEnabled(name.to_owned(), true)));
+                        self.plugin_editor.new_plugin.clear();
+                    }
+                });
+                match &self.plugin_editor.status {
+                    Some(Ok(message)) => {
+                        ui.label(message);
+                    }
+                    Some(Err(message)) => {
+                        ui.colored_label(ui.visuals().error_fg_color, message);
+                    }
+                    None => {}
+                }
+            });
+
+        match action {
+            Some(PluginAction::Edit(edit)) => self.apply_plugin_edit(project_index, edit),
+            Some(PluginAction::Package(uplugin)) => self.request_job(JobKind::PackagePlugin(uplugin)),
+            None => {}
+        }
+    }
+
+    /// Writes a plugin edit to the project's .uproject file (after backing
+    /// it up) and re-reads the project.
+    fn apply_plugin_edit(&mut self, project_index: usize, edit: PluginEdit) {
+        let project = &mut self.settings.projects[project_index];
+        let result = UProjectFile::load(&project.location).and_then(|mut uproject| {
+            let message = match &edit {
+                PluginEdit::SetEnabled(name, enabled) => {
+                    uproject.set_plugin_enabled(name, *enabled);
+                    format!("{} {}", if *enabled { "Enabled" } else { "Disabled" }, name)
+                }
+                PluginEdit::Remove(name) => {
+                    if !uproject.remove_plugin(name) {
+                        return Err(format!("{} is not listed in the project", name).into());
+                    }
+                    format!("Removed {}", name)
+                }
+            };
+            let backup = uproject.save()?;
+            Ok(format!("{} (backup: {})", message, backup.to_string_lossy()))
+        });
+        self.plugin_editor.status = Some(match result {
+            Ok(message) => Ok(message),
+            Err(e) => {
+                eprintln!("Failed to edit {:?}: {}", project.location, e);
+                Err(format!("Failed to edit {}: {}", project.location.to_string_lossy(), e))
+            }
+        });
+        if let Err(e) = project.refresh() {
+            eprintln!("Failed to refresh project {}: {}", project.name, e);
+        }
+        self.save_settings();
+    }
+}
+
+fn plugin_row_ui(ui: &mut egui::Ui, row: &PluginRow, can_package: bool) -> Option<PluginAction> {
+    let mut action = None;
+    let marketplace_url = row
+        .reference
+        .and_then(|reference| reference.marketplace_url.as_deref())
+        .or_else(|| row.descriptor.and_then(|descriptor| descriptor.marketplace_url.as_deref()));
+    match marketplace_url {
+        Some(url) if !url.is_empty() => {
+            ui.hyperlink_to(row.name, url);
+        }
+        _ => {
+            ui.label(row.name);
+        }
+    }
+
+    let mut enabled = row.reference.is_some_and(|reference| reference.enabled);
+    let hint = match (row.reference, row.descriptor) {
+        (Some(_), _) => "Listed in the .uproject",
+        (None, Some(descriptor)) if descriptor.enabled_by_default == Some(true) => "Not listed, enabled by default",
+        (None, _) => "Not listed in the .uproject",
+    };
+    if ui.checkbox(&mut enabled, "").on_hover_text(hint).changed() {
+        action = Some(PluginAction::Edit(PluginEdit::SetEnabled(row.name.to_owned(), enabled)));
+    }
+    ui.label(if row.descriptor.is_some() { "Project" } else { "Engine / Marketplace" });
+
+    let descriptor = row.descriptor;
+    ui.label(descriptor.map(|d| d.version_name.as_str()).unwrap_or("-"));
+    ui.label(descriptor.and_then(|d| d.engine_version.as_deref()).unwrap_or("-"));
+    ui.label(
+        descriptor
+            .map(|d| {
+                d.modules
+                    .iter()
+                    .map(|module| format!("{} ({})", module.name, module.module_type))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default(),
+    );
+    ui.label(
+        descriptor
+            .map(|d| d.dependencies.iter().map(|dep| dep.name.as_str()).collect::<Vec<_>>().join(", "))
+            .unwrap_or_default(),
+    );
+    ui.label(
+        row.reference
+            .map(|reference| reference.platform_allow_list.join(", "))
+            .filter(|platforms| !platforms.is_empty())
+            .unwrap_or_else(|| "All".to_owned()),
+    );
+    ui.horizontal(|ui| {
+        if let Some(descriptor) = row.descriptor {
+            if ui
+                .add_enabled(can_package, egui::Button::new("Package Plugin").small())
+                .on_hover_text("RunUAT BuildPlugin with the selected engine and platform")
+                .clicked()
+            {
+                action = Some(PluginAction::Package(descriptor.location.clone()));
+            }
+        }
+        if row.reference.is_some() && ui.small_button("✖").on_hover_text("Remove from the .uproject").clicked() {
+            action = Some(PluginAction::Edit(PluginEdit::Remove(row.name.to_owned())));
+        }
+    });
+    action
+}
@@ -0,0 +1,305 @@
+use eframe::egui;
+use rfd::FileDialog;
+use std::path::PathBuf;
+
+use super::BuildApp;
+use crate::commands::open_in_file_browser;
+use crate::storage;
+
+/// UI state of the project table.
+#[derive(Default)]
+pub struct ProjectListState {
+    filter: String,
+    /// Index of the project being renamed and the alias being edited.
+    renaming: Option<(usize, String)>,
+    /// Last removed project and its former index, kept so it can be restored.
+    removed: Option<(usize, storage::Project)>,
+}
+
+/// An action requested from the project table, applied once the table is drawn.
+enum ProjectAction {
+    Select(usize),
+    Remove(usize),
+    TogglePin(usize),
+    StartRename(usize),
+    OpenFolder(usize),
+    SwitchEngine(usize),
+    Move { from: usize, to: usize },
+}
+
+impl BuildApp {
+    /// Draws the project toolbar, the searchable project table and the
+    /// rename/undo helpers.
+    pub(super) fn project_list_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("Open Project").clicked() {
+                if let Some(file) = FileDialog::new()
+                    .add_filter("Unreal Project", &["uproject"])
+                    .pick_file()
+                {
+                    self.add_project(file);
+                }
+            }
+            self.scan_button_ui(ui);
+            if ui.button("Refresh").clicked() {
+                self.refresh_projects();
+            }
+            if ui
+                .checkbox(&mut self.settings.preferences.watch_projects, "Watch files")
+                .changed()
+            {
+                self.save_settings();
+                self.update_project_watcher();
+            }
+            ui.separator();
+            ui.label("Search:");
+            ui.text_edit_singleline(&mut self.project_list.filter);
+        });
+
+        if let Some((_, project)) = &self.project_list.removed {
+            let mut undo = false;
+            let mut dismiss = false;
+            ui.horizontal(|ui| {
+                ui.label(format!("Removed {}", project.display_name()));
+                undo = ui.button("Undo").clicked();
+                dismiss = ui.small_button("✖").clicked();
+            });
+            if undo {
+                self.undo_remove_project();
+            } else if dismiss {
+                self.project_list.removed = None;
+            }
+        }
+
+        let mut action = None;
+        egui::ScrollArea::vertical()
+            .id_salt("project_table")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                egui::Grid::new("project_grid")
+                    .striped(true)
+                    .num_columns(5)
+                    .show(ui, |ui| {
+                        ui.label("");
+                        ui.strong("Project");
+                        ui.strong("Engine");
+                        ui.strong("Plugins");
+                        ui.strong("Location");
+                        ui.end_row();
+
+                        for index in self.visible_project_indices() {
+                            if let Some(row_action) = self.project_row_ui(ui, index) {
+                                action = Some(row_action);
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        if let Some(action) = action {
+            self.apply_project_action(action);
+        }
+
+        self.rename_window_ui(ui.ctx());
+        self.scan_window_ui(ui.ctx());
+    }
+
+    /// Draws one row of the project table and returns the action it triggered.
+    fn project_row_ui(&mut self, ui: &mut egui::Ui, index: usize) -> Option<ProjectAction> {
+        let project = &self.settings.projects[index];
+        let mut action = None;
+
+        let handle = ui
+            .add(egui::Label::new(if project.pinned { "★" } else { "☰" }).sense(egui::Sense::drag()))
+            .on_hover_text(if project.pinned {
+                "Drag to reorder the pinned projects"
+            } else {
+                "Drag to reorder the unpinned projects"
+            });
+        handle.dnd_set_drag_payload(index);
+
+        let mut text = egui::RichText::new(project.display_name());
+        if project.missing {
+            text = egui::RichText::new(format!("{} [missing]", project.display_name()))
+                .color(ui.visuals().error_fg_color);
+        }
+        let name = ui.selectable_label(self.selected_project == Some(index), text);
+        if name.clicked() {
+            action = Some(ProjectAction::Select(index));
+        }
+        if let Some(from) = name.dnd_release_payload::<usize>().or_else(|| handle.dnd_release_payload::<usize>()) {
+            action = Some(ProjectAction::Move { from: *from, to: index });
+        }
+        name.context_menu(|ui| {
+            if ui.button("Rename…").clicked() {
+                action = Some(ProjectAction::StartRename(index));
+                ui.close_menu();
+            }
+            if ui.button(if project.pinned { "Unpin" } else { "Pin to top" }).clicked() {
+                action = Some(ProjectAction::TogglePin(index));
+                ui.close_menu();
+            }
+            if ui.button("Open folder").clicked() {
+                action = Some(ProjectAction::OpenFolder(index));
+                ui.close_menu();
+            }
+            if ui.button("Switch engine version…").clicked() {
+                action = Some(ProjectAction::SwitchEngine(index));
+                ui.close_menu();
+            }
+            ui.separator();
+            if ui.button("Remove").clicked() {
+                action = Some(ProjectAction::Remove(index));
+                ui.close_menu();
+            }
+        });
+
+        ui.label(&project.engine_version);
+        ui.label(project.enabled_plugin_names().join(", "));
+        ui.label(project.location.to_string_lossy());
+        action
+    }
+
+    /// Indices of the projects matching the search filter, pinned projects first.
+    fn visible_project_indices(&self) -> Vec<usize> {
+        let filter = self.project_list.filter.to_lowercase();
+        let mut indices: Vec<usize> = self
+            .settings
+            .projects
+            .iter()
+            .enumerate()
+            .filter(|(_, project)| {
+                filter.is_empty()
+                    || project.display_name().to_lowercase().contains(&filter)
+                    || project.name.to_lowercase().contains(&filter)
+                    || project.location.to_string_lossy().to_lowercase().contains(&filter)
+            })
+            .map(|(index, _)| index)
+            .collect();
+        indices.sort_by_key(|&index| !self.settings.projects[index].pinned);
+        indices
+    }
+
+    /// Registers a .uproject file, or selects it if it is already registered.
+    pub(super) fn add_project(&mut self, file: PathBuf) {
+        println!("Selected project file: {:?}", file);
+        if let Some(existing_index) = self.settings.projects.iter().position(|p| p.location == file) {
+            self.selected_project = Some(existing_index);
+            println!("Project already exists, selected project index: {:?}", self.selected_project);
+        } else {
+            let new_project = match storage::Project::new(file.clone()) {
+                Ok(project) => project,
+                Err(e) => {
+                    eprintln!("Failed to read project {:?}: {}", file, e);
+                    return;
+                }
+            };
+            println!("New project added: {:?}", new_project);
+            self.settings.projects.push(new_project);
+            self.selected_project = Some(self.settings.projects.len() - 1);
+            println!("Selected project index: {:?}", self.selected_project);
+            self.save_settings();
+            self.update_project_watcher();
+        }
+    }
+
+    fn apply_project_action(&mut self, action: ProjectAction) {
+        match action {
+            ProjectAction::Select(index) => self.selected_project = Some(index),
+            ProjectAction::StartRename(index) => {
+                let project = &self.settings.projects[index];
+                self.project_list.renaming = Some((index, project.alias.clone().unwrap_or_default()));
+            }
+            ProjectAction::TogglePin(index) => {
+                let project = &mut self.settings.projects[index];
+                project.pinned = !project.pinned;
+                self.save_settings();
+            }
+            ProjectAction::OpenFolder(index) => {
+                if let Some(folder) = self.settings.projects[index].location.parent() {
+                    if let Err(e) = open_in_file_browser(folder) {
+                        eprintln!("Failed to open folder {:?}: {}", folder, e);
+                    }
+                }
+            }
+            ProjectAction::SwitchEngine(index) => self.open_engine_switch(index),
+            ProjectAction::Remove(index) => {
+                let project = self.settings.projects.remove(index);
+                println!("Project removed: {:?}", project);
+                self.selected_project = match self.selected_project {
+                    Some(selected) if selected == index => None,
+                    Some(selected) if selected > index => Some(selected - 1),
+                    selected => selected,
+                };
+                self.project_list.removed = Some((index, project));
+                self.project_list.renaming = None;
+                self.engine_switch = None;
+                self.save_settings();
+                self.update_project_watcher();
+            }
+            ProjectAction::Move { from, to } => {
+                // Pinned projects are drawn above the others, so a row only
+                // moves within its own section. Both sections keep the
+                // relative order of `projects`, so moving one project to
+                // another's index in the list moves it there on screen too.
+                if from == to || self.settings.projects[from].pinned != self.settings.projects[to].pinned {
+                    return;
+                }
+                let selected = self.selected_project.map(|i| self.settings.projects[i].location.clone());
+                let project = self.settings.projects.remove(from);
+                self.settings.projects.insert(to, project);
+                self.selected_project =
+                    selected.and_then(|location| self.settings.projects.iter().position(|p| p.location == location));
+                self.project_list.renaming = None;
+                self.engine_switch = None;
+                self.save_settings();
+            }
+        }
+    }
+
+    /// Restores the last removed project at its former position.
+    fn undo_remove_project(&mut self) {
+        let Some((index, project)) = self.project_list.removed.take() else {
+            return;
+        };
+        let index = index.min(self.settings.projects.len());
+        self.settings.projects.insert(index, project);
+        if let Some(selected) = self.selected_project.filter(|&selected| selected >= index) {
+            self.selected_project = Some(selected + 1);
+        }
+        self.save_settings();
+        self.update_project_watcher();
+    }
+
+    /// Shows the alias editor while a project is being renamed.
+    fn rename_window_ui(&mut self, ctx: &egui::Context) {
+        let Some((index, alias)) = &mut self.project_list.renaming else {
+            return;
+        };
+        let index = *index;
+        let mut apply = false;
+        let mut cancel = false;
+        egui::Window::new("Rename project")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("Display name for {}:", self.settings.projects[index].name));
+                let response = ui.text_edit_singleline(alias);
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    apply = true;
+                }
+                ui.label("Leave empty to use the project name.");
+                ui.horizontal(|ui| {
+                    apply |= ui.button("Apply").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        if apply {
+            let alias = alias.trim().to_owned();
+            self.settings.projects[index].alias = (!alias.is_empty()).then_some(alias);
+            self.project_list.renaming = None;
+            self.save_settings();
+        } else if cancel {
+            self.project_list.renaming = None;
+        }
+    }
+}
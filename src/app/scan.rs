@@ -0,0 +1,137 @@
+use eframe::egui;
+use rfd::FileDialog;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+
+use super::BuildApp;
+use crate::scanner;
+
+/// State of the "Scan folder…" workflow.
+#[derive(Default)]
+pub struct ScanState {
+    /// Receiver for the results of a running scan.
+    rx: Option<Receiver<Vec<PathBuf>>>,
+    /// Discovered projects that are not registered yet, with their checkbox state.
+    discoveries: Vec<(PathBuf, bool)>,
+    /// Whether the discoveries window is shown.
+    open: bool,
+}
+
+impl BuildApp {
+    /// Starts scanning the remembered scan roots if rescanning at startup is enabled.
+    pub(super) fn rescan_on_startup(&mut self) {
+        let preferences = &self.settings.preferences;
+        if preferences.rescan_on_startup && !preferences.scan_roots.is_empty() {
+            self.scan.rx = Some(scanner::spawn_scan(preferences.scan_roots.clone()));
+        }
+    }
+
+    /// Draws the "Scan folder…" button.
+    pub(super) fn scan_button_ui(&mut self, ui: &mut egui::Ui) {
+        let scanning = self.scan.rx.is_some();
+        if ui
+            .add_enabled(!scanning, egui::Button::new(if scanning { "Scanning…" } else { "Scan folder…" }))
+            .clicked()
+        {
+            if let Some(root) = FileDialog::new().pick_folder() {
+                if !self.settings.preferences.scan_roots.contains(&root) {
+                    self.settings.preferences.scan_roots.push(root.clone());
+                    self.save_settings();
+                }
+                self.scan.rx = Some(scanner::spawn_scan(vec![root]));
+                self.scan.open = true;
+            }
+        }
+    }
+
+    /// Collects finished scan results and draws the discoveries checklist.
+    pub(super) fn scan_window_ui(&mut self, ctx: &egui::Context) {
+        if let Some(found) = self.scan.rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.scan.rx = None;
+            for path in found {
+                let registered = self.settings.projects.iter().any(|p| p.location == path);
+                if !registered && !self.scan.discoveries.iter().any(|(p, _)| p == &path) {
+                    self.scan.discoveries.push((path, true));
+                }
+            }
+            if !self.scan.discoveries.is_empty() {
+                self.scan.open = true;
+            }
+        }
+        if !self.scan.open {
+            return;
+        }
+
+        let mut open = true;
+        let mut add = false;
+        let mut remove_root = None;
+        egui::Window::new("Discovered projects")
+            .open(&mut open)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                if self.scan.rx.is_some() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Scanning…");
+                    });
+                } else if self.scan.discoveries.is_empty() {
+                    ui.label("No new projects found.");
+                }
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for (path, checked) in &mut self.scan.discoveries {
+                        ui.checkbox(checked, path.to_string_lossy());
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Select all").clicked() {
+                        self.scan.discoveries.iter_mut().for_each(|(_, checked)| *checked = true);
+                    }
+                    if ui.button("Select none").clicked() {
+                        self.scan.discoveries.iter_mut().for_each(|(_, checked)| *checked = false);
+                    }
+                    add = ui
+                        .add_enabled(
+                            self.scan.discoveries.iter().any(|(_, checked)| *checked),
+                            egui::Button::new("Add selected"),
+                        )
+                        .clicked();
+                });
+                ui.separator();
+                ui.collapsing("Scan roots", |ui| {
+                    for (index, root) in self.settings.preferences.scan_roots.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui.small_button("✖").clicked() {
+                                remove_root = Some(index);
+                            }
+                            ui.label(root.to_string_lossy());
+                        });
+                    }
+                    if ui
+                        .checkbox(&mut self.settings.preferences.rescan_on_startup, "Rescan at startup")
+                        .changed()
+                    {
+                        self.save_settings();
+                    }
+                });
+            });
+
+        if let Some(index) = remove_root {
+            self.settings.preferences.scan_roots.remove(index);
+            self.save_settings();
+        }
+        if add {
+            let (selected, remaining) = std::mem::take(&mut self.scan.discoveries)
+                .into_iter()
+                .partition::<Vec<_>, _>(|(_, checked)| *checked);
+            for (path, _) in selected {
+                self.add_project(path);
+            }
+            self.scan.discoveries = remaining;
+            open = !self.scan.discoveries.is_empty();
+        }
+        if !open {
+            self.scan.open = false;
+            self.scan.discoveries.clear();
+        }
+    }
+}
@@ -0,0 +1,488 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::output::UtcDateTime;
+
+/// Name of the manifest written at the root of every archive.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Files from this size on get Zip64 sizes, leaving room for deflate to
+/// grow incompressible data without overflowing 32 bits.
+const ZIP64_FILE_SIZE: u64 = 0xF000_0000;
+
+/// How packaged builds are compressed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    #[default]
+    Zip,
+    /// A tar archive compressed with zstd.
+    TarZst,
+}
+
+impl ArchiveFormat {
+    pub const ALL: [ArchiveFormat; 2] = [ArchiveFormat::Zip, ArchiveFormat::TarZst];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::TarZst => "tar.zst",
+        }
+    }
+}
+
+/// An archived file, as listed by the manifest.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Path in the archive, with `/` separators.
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// The files of an archive, written into it as `MANIFEST_FILE`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub files: Vec<ManifestEntry>,
+}
+
+/// A file under the archived folder.
+struct FileEntry {
+    /// Path relative to the folder, with `/` separators.
+    name: String,
+    path: PathBuf,
+    size: u64,
+    modified: Option<SystemTime>,
+    mode: u32,
+}
+
+/// Lists the files under `dir`, sorted by name. Symbolic links are not
+/// followed.
+fn list_files(dir: &Path) -> io::Result<Vec<FileEntry>> {
+    fn visit(root: &Path, dir: &Path, files: &mut Vec<FileEntry>) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let metadata = fs::symlink_metadata(&path)?;
+            if metadata.is_dir() {
+                visit(root, &path, files)?;
+            } else if metadata.is_file() {
+                let relative = path.strip_prefix(root).unwrap_or(&path);
+                let name: Vec<String> = relative.iter().map(|part| part.to_string_lossy().into_owned()).collect();
+                files.push(FileEntry {
+                    name: name.join("/"),
+                    size: metadata.len(),
+                    modified: metadata.modified().ok(),
+                    mode: file_mode(&metadata),
+                    path,
+                });
+            }
+        }
+        Ok(())
+    }
+    let mut files = Vec::new();
+    visit(dir, dir, &mut files)?;
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+/// Unix permissions of a file, so that launch scripts stay executable.
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode()
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &fs::Metadata) -> u32 {
+    0o100644
+}
+
+/// Zip timestamp of `time`; zip cannot store times before 1980.
+fn zip_time(time: Option<SystemTime>) -> zip::DateTime {
+    let time = UtcDateTime::from_system_time(time.unwrap_or(UNIX_EPOCH));
+    zip::DateTime::from_date_and_time(
+        time.year.clamp(1980, 2107) as u16,
+        time.month as u8,
+        time.day as u8,
+        time.hour as u8,
+        time.minute as u8,
+        time.second as u8,
+    )
+    .unwrap_or_default()
+}
+
+/// Writes the entries of an archive.
+trait EntryWriter {
+    /// Adds `file` with the contents of `input`. `read` is called with each
+    /// chunk once it was written.
+    fn add(&mut self, file: &FileEntry, input: &mut dyn Read, read: &mut dyn FnMut(&[u8])) -> io::Result<()>;
+}
+
+/// Writes a zip archive with deflated entries.
+struct ZipEntries<W: Write + Seek> {
+    zip: ZipWriter<W>,
+    /// Files from this size on get Zip64 sizes.
+    zip64_size: u64,
+}
+
+impl<W: Write + Seek> ZipEntries<W> {
+    fn new(out: W) -> Self {
+        Self { zip: ZipWriter::new(out), zip64_size: ZIP64_FILE_SIZE }
+    }
+
+    /// Writes the central directory and returns the output.
+    fn finish(self) -> io::Result<W> {
+        self.zip.finish().map_err(io::Error::other)
+    }
+}
+
+impl<W: Write + Seek> EntryWriter for ZipEntries<W> {
+    fn add(&mut self, file: &FileEntry, input: &mut dyn Read, read: &mut dyn FnMut(&[u8])) -> io::Result<()> {
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .last_modified_time(zip_time(file.modified))
+            .unix_permissions(file.mode)
+            .large_file(file.size >= self.zip64_size);
+        self.zip.start_file(file.name.as_str(), options).map_err(io::Error::other)?;
+        copy_chunks(input, &mut self.zip, read)?;
+        Ok(())
+    }
+}
+
+/// Copies `input` to `out` and returns the number of bytes copied. `read`
+/// is called with each chunk once it was written.
+fn copy_chunks(input: &mut dyn Read, out: &mut dyn Write, read: &mut dyn FnMut(&[u8])) -> io::Result<u64> {
+    let mut buffer = vec![0; 1 << 20];
+    let mut size = 0;
+    loop {
+        let count = input.read(&mut buffer)?;
+        if count == 0 {
+            return Ok(size);
+        }
+        out.write_all(&buffer[..count])?;
+        size += count as u64;
+        read(&buffer[..count]);
+    }
+}
+
+/// Writes a ustar archive, with pax headers for long names and large files.
+struct TarWriter<W: Write> {
+    out: W,
+}
+
+/// Largest size that fits the 11 octal digits of a ustar header.
+const MAX_TAR_SIZE: u64 = 0o77777777777;
+
+impl<W: Write> TarWriter<W> {
+    fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    fn write_padded(&mut self, data_len: u64) -> io::Result<()> {
+        let padding = (512 - data_len % 512) % 512;
+        self.out.write_all(&vec![0; padding as usize])
+    }
+
+    /// Writes the end-of-archive blocks and returns the output.
+    fn finish(mut self) -> io::Result<W> {
+        self.out.write_all(&[0; 1024])?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl<W: Write> EntryWriter for TarWriter<W> {
+    fn add(&mut self, file: &FileEntry, input: &mut dyn Read, read: &mut dyn FnMut(&[u8])) -> io::Result<()> {
+        let mtime = file
+            .modified
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let mut records = String::new();
+        if file.name.len() > 100 || !file.name.is_ascii() {
+            records.push_str(&pax_record("path", &file.name));
+        }
+        if file.size > MAX_TAR_SIZE {
+            records.push_str(&pax_record("size", &file.size.to_string()));
+        }
+        if !records.is_empty() {
+            self.out.write_all(&tar_header("././@PaxHeader", records.len() as u64, mtime, 0o644, b'x'))?;
+            self.out.write_all(records.as_bytes())?;
+            self.write_padded(records.len() as u64)?;
+        }
+        let header_size = if file.size > MAX_TAR_SIZE { 0 } else { file.size };
+        self.out.write_all(&tar_header(&file.name, header_size, mtime, file.mode & 0o7777, b'0'))?;
+
+        let size = copy_chunks(&mut input.take(file.size), &mut self.out, read)?;
+        if size != file.size {
+            return Err(io::Error::other(format!("{} changed while being archived", file.name)));
+        }
+        self.write_padded(size)
+    }
+}
+
+/// A ustar header block. Names longer than the field are cut; the full
+/// name goes in a pax record.
+fn tar_header(name: &str, size: u64, mtime: u64, mode: u32, kind: u8) -> [u8; 512] {
+    let mut header = [0; 512];
+    let name = name.as_bytes();
+    header[..name.len().min(100)].copy_from_slice(&name[..name.len().min(100)]);
+    put_octal(&mut header[100..108], u64::from(mode));
+    put_octal(&mut header[108..116], 0);
+    put_octal(&mut header[116..124], 0);
+    put_octal(&mut header[124..136], size);
+    put_octal(&mut header[136..148], mtime);
+    header[148..156].fill(b' ');
+    header[156] = kind;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    let checksum: u32 = header.iter().map(|&byte| u32::from(byte)).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    header
+}
+
+/// Writes `value` as zero-padded octal digits followed by a NUL.
+fn put_octal(field: &mut [u8], value: u64) {
+    let width = field.len() - 1;
+    field[..width].copy_from_slice(format!("{:0width$o}", value, width = width).as_bytes());
+    field[width] = 0;
+}
+
+/// A pax extended header record: `<length> <key>=<value>\n`, where the
+/// length counts the whole record, itself included.
+fn pax_record(key: &str, value: &str) -> String {
+    let body = format!(" {}={}\n", key, value);
+    let mut length = body.len();
+    loop {
+        let total = body.len() + length.to_string().len();
+        if total == length {
+            return format!("{}{}", length, body);
+        }
+        length = total;
+    }
+}
+
+/// Adds every file to `writer`, hashing them on the way, then the manifest.
+fn write_entries(
+    writer: &mut dyn EntryWriter,
+    files: &[FileEntry],
+    progress: &mut dyn FnMut(u64, u64),
+) -> io::Result<Manifest> {
+    let total = files.iter().map(|file| file.size).sum();
+    let mut done = 0;
+    let mut manifest = Manifest::default();
+    for file in files {
+        let mut hasher = Sha256::new();
+        writer.add(file, &mut File::open(&file.path)?, &mut |chunk| {
+            hasher.update(chunk);
+            done += chunk.len() as u64;
+            progress(done, total);
+        })?;
+        manifest.files.push(ManifestEntry {
+            path: file.name.clone(),
+            size: file.size,
+            sha256: format!("{:x}", hasher.finalize()),
+        });
+    }
+
+    let json = serde_json::to_vec_pretty(&manifest).map_err(io::Error::other)?;
+    let manifest_file = FileEntry {
+        name: MANIFEST_FILE.to_owned(),
+        path: PathBuf::new(),
+        size: json.len() as u64,
+        modified: Some(SystemTime::now()),
+        mode: 0o100644,
+    };
+    writer.add(&manifest_file, &mut json.as_slice(), &mut |_| {})?;
+    Ok(manifest)
+}
+
+/// Threads used for zstd compression.
+fn zstd_workers() -> u32 {
+    std::thread::available_parallelism().map_or(1, |count| count.get() as u32)
+}
+
+/// Compresses the files under `dir` into `destination`, with paths relative
+/// to `dir` and a `MANIFEST_FILE` listing their sizes and SHA-256 hashes,
+/// and returns the manifest. `progress` is called with the bytes archived
+/// so far and the total. A partly written archive is removed on failure.
+pub fn archive_dir(
+    dir: &Path,
+    destination: &Path,
+    format: ArchiveFormat,
+    progress: &mut dyn FnMut(u64, u64),
+) -> io::Result<Manifest> {
+    let files = list_files(dir)?;
+    let result = match format {
+        ArchiveFormat::Zip => File::create(destination).and_then(|file| {
+            let mut zip = ZipEntries::new(BufWriter::new(file));
+            let manifest = write_entries(&mut zip, &files, progress)?;
+            zip.finish()?;
+            Ok(manifest)
+        }),
+        ArchiveFormat::TarZst => File::create(destination).and_then(|file| {
+            let mut zstd = zstd::Encoder::new(BufWriter::new(file), 0)?;
+            zstd.multithread(zstd_workers())?;
+            let mut tar = TarWriter::new(zstd);
+            let manifest = write_entries(&mut tar, &files, progress)?;
+            tar.finish()?.finish()?.flush()?;
+            Ok(manifest)
+        }),
+    };
+    if result.is_err() {
+        let _ = fs::remove_file(destination);
+    }
+    result
+}
+
+/// SHA-256 of a file's contents, as lowercase hexadecimal.
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Writes the SHA-256 of `archive` to `<archive>.sha256`, in the format
+/// read by `sha256sum -c`, and returns it.
+pub fn write_checksum_file(archive: &Path) -> io::Result<String> {
+    let sha256 = sha256_file(archive)?;
+    let name = archive.file_name().unwrap_or_default().to_string_lossy();
+    let mut path = archive.as_os_str().to_owned();
+    path.push(".sha256");
+    fs::write(path, format!("{}  {}\n", sha256, name))?;
+    Ok(sha256)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn writes_zip_archives() {
+        let root = TempDir::new("archive");
+        let dir = root.join("Linux");
+        fs::create_dir_all(dir.join("Game").join("Content")).unwrap();
+        fs::write(dir.join("Game.sh"), "#!/bin/sh\n").unwrap();
+        fs::write(dir.join("Game").join("Content").join("Game.pak"), vec![7; 100_000]).unwrap();
+        let destination = dir.with_extension("zip");
+
+        let mut reported = (0, 0);
+        let manifest = archive_dir(&dir, &destination, ArchiveFormat::Zip, &mut |done, total| {
+            reported = (done, total)
+        })
+        .unwrap();
+        assert_eq!(reported, (100_010, 100_010));
+        let paths: Vec<&str> = manifest.files.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(paths, ["Game.sh", "Game/Content/Game.pak"]);
+        assert_eq!(manifest.files[0].sha256, "a8076d3d28d21e02012b20eaf7dbf75409a6277134439025f282e368e3305abf");
+
+        assert!(fs::metadata(&destination).unwrap().len() < 10_000);
+        let mut zip = zip::ZipArchive::new(File::open(&destination).unwrap()).unwrap();
+        let names: Vec<&str> = zip.file_names().collect();
+        assert_eq!(names, ["Game.sh", "Game/Content/Game.pak", MANIFEST_FILE]);
+        let mut script = zip.by_name("Game.sh").unwrap();
+        assert_eq!(script.unix_mode(), Some(file_mode(&fs::metadata(dir.join("Game.sh")).unwrap())));
+        let mut contents = String::new();
+        script.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "#!/bin/sh\n");
+        drop(script);
+        let mut pak = Vec::new();
+        zip.by_name("Game/Content/Game.pak").unwrap().read_to_end(&mut pak).unwrap();
+        assert_eq!(pak, vec![7; 100_000]);
+        let read: Manifest = serde_json::from_reader(zip.by_name(MANIFEST_FILE).unwrap()).unwrap();
+        assert_eq!(read.files.len(), 2);
+    }
+
+    #[test]
+    fn writes_zip64_entries() {
+        let file = FileEntry {
+            name: "Game.pak".to_string(),
+            path: PathBuf::new(),
+            size: 3,
+            modified: Some(UNIX_EPOCH),
+            mode: 0o100644,
+        };
+        let mut zip = ZipEntries { zip64_size: 0, ..ZipEntries::new(io::Cursor::new(Vec::new())) };
+        zip.add(&file, &mut &b"pak"[..], &mut |_| {}).unwrap();
+        let bytes = zip.finish().unwrap().into_inner();
+
+        // The local header carries a Zip64 extra field (id 0x0001).
+        let name_len = u16::from_le_bytes([bytes[26], bytes[27]]) as usize;
+        assert_eq!(&bytes[30 + name_len..32 + name_len], &[1, 0]);
+        let mut zip = zip::ZipArchive::new(io::Cursor::new(bytes)).unwrap();
+        let mut pak = String::new();
+        zip.by_name("Game.pak").unwrap().read_to_string(&mut pak).unwrap();
+        assert_eq!(pak, "pak");
+    }
+
+    #[test]
+    fn writes_tar_zst_archives() {
+        let root = TempDir::new("archive");
+        let dir = root.join("Linux");
+        let long_dir = dir.join("Game").join("Content").join("Sub".repeat(40));
+        fs::create_dir_all(&long_dir).unwrap();
+        fs::write(dir.join("Game.sh"), "#!/bin/sh\n").unwrap();
+        fs::write(long_dir.join("Game.pak"), vec![7; 100_000]).unwrap();
+        let destination = root.join("Linux.tar.zst");
+
+        let manifest = archive_dir(&dir, &destination, ArchiveFormat::TarZst, &mut |_, _| {}).unwrap();
+        assert!(fs::metadata(&destination).unwrap().len() < 10_000);
+
+        let decoder = zstd::Decoder::new(File::open(&destination).unwrap()).unwrap();
+        let mut tar = tar::Archive::new(decoder);
+        let mut entries = Vec::new();
+        for entry in tar.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let name = entry.path().unwrap().to_string_lossy().into_owned();
+            let mode = entry.header().mode().unwrap();
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents).unwrap();
+            entries.push((name, mode, contents));
+        }
+        let long_name = format!("Game/Content/{}/Game.pak", "Sub".repeat(40));
+        let names: Vec<&str> = entries.iter().map(|(name, _, _)| name.as_str()).collect();
+        assert_eq!(names, ["Game.sh", long_name.as_str(), MANIFEST_FILE]);
+        assert_eq!(entries[0].1, file_mode(&fs::metadata(dir.join("Game.sh")).unwrap()) & 0o7777);
+        assert_eq!(entries[0].2, b"#!/bin/sh\n");
+        assert_eq!(entries[1].2, vec![7; 100_000]);
+        let read: Manifest = serde_json::from_slice(&entries[2].2).unwrap();
+        assert_eq!(read.files.len(), manifest.files.len());
+        assert_eq!(read.files[1].sha256, manifest.files[1].sha256);
+        assert_eq!(sha256_file(&long_dir.join("Game.pak")).unwrap(), manifest.files[1].sha256);
+    }
+
+    #[test]
+    fn writes_tar_headers() {
+        let long_name = format!("Game/Content/{}/Game.pak", "Sub".repeat(40));
+        let file = FileEntry {
+            name: long_name.clone(),
+            path: PathBuf::new(),
+            size: 3,
+            modified: Some(UNIX_EPOCH),
+            mode: 0o100755,
+        };
+        let mut tar = TarWriter::new(Vec::new());
+        tar.add(&file, &mut &b"pak"[..], &mut |_| {}).unwrap();
+        let tar = tar.finish().unwrap();
+
+        // A pax header carrying the full name, then the file, then two empty blocks.
+        assert_eq!(tar.len(), 512 * 6);
+        assert_eq!(tar[156], b'x');
+        let record = pax_record("path", &long_name);
+        assert_eq!(&tar[512..512 + record.len()], record.as_bytes());
+        assert!(record.starts_with(&format!("{} ", record.len())));
+        let header = &tar[1024..1536];
+        assert_eq!(&header[100..108], b"0000755\0");
+        assert_eq!(&header[124..136], b"00000000003\0");
+        assert_eq!(&header[257..263], b"ustar\0");
+        let checksum: u32 = header.iter().enumerate().map(|(i, &b)| if (148..156).contains(&i) { 32 } else { u32::from(b) }).sum();
+        assert_eq!(&header[148..155], format!("{:06o}\0", checksum).as_bytes());
+        assert_eq!(&tar[1536..1539], b"pak");
+    }
+}
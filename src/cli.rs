@@ -0,0 +1,435 @@
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::SystemTime;
+
+use crate::commands::{BuildMode, ProgressUpdate};
+use crate::jobs::{JobKind, JobSpec};
+use crate::platform::Platform;
+use crate::post_actions::after_package;
+use crate::provenance::PackageRun;
+use crate::preflight::{run_preflight, CheckStatus, Preflight};
+use crate::storage::{self, Engine, Project, Settings};
+use crate::version_stamp::{stamp_package, StampedConfig};
+
+/// Exit code when the command ran but the build/package failed.
+const EXIT_JOB_FAILED: u8 = 1;
+/// Exit code for invalid command line usage.
+const EXIT_USAGE: u8 = 2;
+/// Exit code when the settings, project or engine cannot be resolved.
+const EXIT_CONFIG: u8 = 3;
+/// Exit code when a preflight check failed and `--force` was not given.
+const EXIT_PREFLIGHT: u8 = 4;
+
+const USAGE: &str = "\
+Usage: UnrealBuildTool [--config-dir <path>] <command> [options]
+
+Without a command, the graphical interface is started.
+
+Commands:
+  list-projects                  List registered projects
+  list-engines                   List registered engines
+  add-project <file.uproject>    Register a project
+  build                          Build a project
+  package                        Package a project with BuildCookRun
+  clean                          Clean a project's build products
+
+Options for build, package and clean:
+  --project <name|path>          Project name, alias or .uproject path (required)
+  --engine <UE5.sln>             Engine to use (defaults to the selected engine)
+  --profile <name>               Build profile supplying the platform, configuration,
+                                 output, post actions and version stamp
+  --platform <platform>          Target platform (default: the profile's, or Win64)
+  --config <configuration>       Debug, Development or Shipping (default: the
+                                 profile's, or Development)
+  --force                        Run even if preflight checks fail
+
+Global options:
+  --json                         Print machine-readable JSON lines
+  -h, --help                     Show this help";
+
+/// Subcommands understood by the CLI.
+const COMMANDS: [&str; 6] = ["list-projects", "list-engines", "add-project", "build", "package", "clean"];
+
+/// A CLI failure, carrying the message to print and the exit code.
+struct CliError {
+    code: u8,
+    message: String,
+}
+
+impl CliError {
+    fn usage(message: impl Into<String>) -> Self {
+        Self { code: EXIT_USAGE, message: message.into() }
+    }
+
+    fn config(message: impl Into<String>) -> Self {
+        Self { code: EXIT_CONFIG, message: message.into() }
+    }
+}
+
+/// Options shared by every subcommand.
+struct Options {
+    json: bool,
+    project: Option<String>,
+    engine: Option<PathBuf>,
+    profile: Option<String>,
+    platform: Option<Platform>,
+    mode: Option<BuildMode>,
+    force: bool,
+    positional: Vec<String>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, CliError> {
+        let mut options = Options {
+            json: false,
+            project: None,
+            engine: None,
+            profile: None,
+            platform: None,
+            mode: None,
+            force: false,
+            positional: Vec::new(),
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_owned())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next().cloned())
+                    .ok_or_else(|| CliError::usage(format!("{} requires a value", flag)))
+            };
+            match flag {
+                "--json" => options.json = true,
+                "--force" => options.force = true,
+                "--project" => options.project = Some(value()?),
+                "--engine" => options.engine = Some(PathBuf::from(value()?)),
+                "--profile" => options.profile = Some(value()?),
+                "--platform" => {
+                    let name = value()?;
+                    options.platform = Some(
+                        Platform::from_name(&name)
+                            .ok_or_else(|| CliError::usage(format!("unknown platform {:?}", name)))?,
+                    );
+                }
+                "--config" => {
+                    let name = value()?;
+                    options.mode = Some(
+                        BuildMode::from_name(&name)
+                            .ok_or_else(|| CliError::usage(format!("unknown configuration {:?}", name)))?,
+                    );
+                }
+                _ if flag.starts_with('-') => return Err(CliError::usage(format!("unknown option {:?}", flag))),
+                _ => options.positional.push(arg.clone()),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Runs the CLI if `args` start with a subcommand (or `--help`). Returns
+/// `None` when the GUI should be started instead.
+pub fn run(args: &[String]) -> Option<ExitCode> {
+    let command = args.first()?;
+    if command == "-h" || command == "--help" || command == "help" {
+        println!("{}", USAGE);
+        return Some(ExitCode::SUCCESS);
+    }
+    if !COMMANDS.contains(&command.as_str()) {
+        if command.starts_with('-') {
+            return None;
+        }
+        eprintln!("Unknown command {:?}\n\n{}", command, USAGE);
+        return Some(ExitCode::from(EXIT_USAGE));
+    }
+
+    let result = Options::parse(&args[1..]).and_then(|options| {
+        let json = options.json;
+        run_command(command, options).map_err(|e| report_error(e, json))
+    });
+    Some(match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {}", e.message);
+            if e.code == EXIT_USAGE {
+                eprintln!("\n{}", USAGE);
+            }
+            ExitCode::from(e.code)
+        }
+    })
+}
+
+/// Prints an error as JSON in `--json` mode so scripts always get a JSON line.
+fn report_error(error: CliError, json: bool) -> CliError {
+    if json {
+        println!("{}", json!({ "event": "error", "data": error.message, "code": error.code }));
+    }
+    error
+}
+
+fn run_command(command: &str, options: Options) -> Result<ExitCode, CliError> {
+    let mut settings =
+        storage::load_settings().map_err(|e| CliError::config(format!("failed to load settings: {}", e)))?;
+
+    match command {
+        "list-projects" => {
+            if options.json {
+                let projects: Vec<_> = settings
+                    .projects
+                    .iter_mut()
+                    .map(|project| {
+                        if let Err(e) = project.refresh() {
+                            eprintln!("Failed to refresh project {:?}: {}", project.location, e);
+                        }
+                        project.summary()
+                    })
+                    .collect();
+                println!("{}", json!(projects));
+            } else {
+                for project in &mut settings.projects {
+                    if let Err(e) = project.refresh() {
+                        eprintln!("Failed to refresh project {:?}: {}", project.location, e);
+                    }
+                    let missing = if project.missing { " [missing]" } else { "" };
+                    println!(
+                        "{}\t{}\t{}{}",
+                        project.display_name(),
+                        project.engine_version,
+                        project.location.to_string_lossy(),
+                        missing
+                    );
+                }
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        "list-engines" => {
+            let selected = settings.preferences.selected_engine.as_ref();
+            if options.json {
+                let engines: Vec<_> = settings
+                    .engines
+                    .iter()
+                    .map(|engine| engine.summary(Some(&engine.location) == selected))
+                    .collect();
+                println!("{}", json!(engines));
+            } else {
+                for engine in &settings.engines {
+                    let marker = if Some(&engine.location) == selected { "*" } else { " " };
+                    let version = engine.build_version().map(|v| v.major_minor()).unwrap_or_else(|_| "?".into());
+                    println!("{} {}\t{}", marker, version, engine.location.to_string_lossy());
+                }
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        "add-project" => {
+            let [location] = options.positional.as_slice() else {
+                return Err(CliError::usage("add-project expects exactly one .uproject path"));
+            };
+            let location = std::fs::canonicalize(location)
+                .map_err(|e| CliError::config(format!("cannot access {:?}: {}", location, e)))?;
+            let added = if settings.projects.iter().any(|p| p.location == location) {
+                false
+            } else {
+                let project = Project::new(location.clone())
+                    .map_err(|e| CliError::config(format!("cannot read {:?}: {}", location, e)))?;
+                settings.projects.push(project);
+                storage::save_settings(&settings)
+                    .map_err(|e| CliError::config(format!("failed to save settings: {}", e)))?;
+                true
+            };
+            if options.json {
+                println!("{}", json!({ "location": location, "added": added }));
+            } else if added {
+                println!("Added {}", location.to_string_lossy());
+            } else {
+                println!("{} is already registered", location.to_string_lossy());
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        "build" | "package" | "clean" => {
+            let project = find_project(&settings, &options)?;
+            let engine = find_engine(&settings, &options)?;
+            let kind = JobKind::from_name(command).expect("build, package and clean are job kinds");
+            let profile = match &options.profile {
+                Some(name) => Some(
+                    settings
+                        .find_profile(name)
+                        .ok_or_else(|| CliError::config(format!("no profile named {:?}", name)))?,
+                ),
+                None => None,
+            };
+            let platform = match (options.platform, profile) {
+                (Some(platform), _) => platform,
+                (None, Some(profile)) => Platform::from_name(&profile.platform).ok_or_else(|| {
+                    CliError::config(format!("profile {:?} has unknown platform {:?}", profile.name, profile.platform))
+                })?,
+                (None, None) => Platform::Win64,
+            };
+            let mode = match (options.mode, profile) {
+                (Some(mode), _) => mode,
+                (None, Some(profile)) => BuildMode::from_name(&profile.configuration).ok_or_else(|| {
+                    CliError::config(format!(
+                        "profile {:?} has unknown configuration {:?}",
+                        profile.name, profile.configuration
+                    ))
+                })?,
+                (None, None) => BuildMode::Development,
+            };
+            let mut spec = JobSpec::new(kind, &project, &engine, platform, mode);
+            if let Some(profile) = profile {
+                spec.apply_profile(profile, &project);
+            }
+            spec.resolve_output();
+            let preflight = run_preflight(&spec, &project);
+            report_preflight(&preflight, options.json);
+            if preflight.has_failures() && !options.force {
+                return Err(CliError {
+                    code: EXIT_PREFLIGHT,
+                    message: "preflight checks failed; fix them or pass --force to run anyway".to_owned(),
+                });
+            }
+            Ok(run_job(&spec, options.json))
+        }
+        _ => Err(CliError::usage(format!("unknown command {:?}", command))),
+    }
+}
+
+/// Resolves `--project` against the registered projects (name, alias or
+/// path), falling back to an unregistered .uproject path.
+fn find_project(settings: &Settings, options: &Options) -> Result<Project, CliError> {
+    let query = options
+        .project
+        .as_deref()
+        .ok_or_else(|| CliError::usage("--project is required"))?;
+    if let Some(project) = settings.find_project(query) {
+        return Ok(project.clone());
+    }
+    let path = Path::new(query);
+    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("uproject")) && path.is_file() {
+        return Project::new(std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()))
+            .map_err(|e| CliError::config(format!("cannot read {:?}: {}", path, e)));
+    }
+    Err(CliError::config(format!("no registered project matches {:?}", query)))
+}
+
+/// Resolves `--engine`, falling back to the engine selected in the GUI or
+/// the only registered engine.
+fn find_engine(settings: &Settings, options: &Options) -> Result<Engine, CliError> {
+    if let Some(location) = &options.engine {
+        if !location.is_file() {
+            return Err(CliError::config(format!("engine solution {:?} does not exist", location)));
+        }
+        return Ok(Engine { location: location.clone() });
+    }
+    settings
+        .default_engine()
+        .cloned()
+        .ok_or_else(|| CliError::config("no engine selected; pass --engine <UE5.sln>"))
+}
+
+/// Prints the preflight checks, as one JSON line in `--json` mode.
+fn report_preflight(preflight: &Preflight, json: bool) {
+    if json {
+        println!("{}", json!({ "event": "preflight", "data": preflight.checks }));
+        return;
+    }
+    for check in &preflight.checks {
+        let status = match check.status {
+            CheckStatus::Pass => "pass",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Fail => "FAIL",
+        };
+        let mut lines = check.message.lines();
+        println!("[{}] {}: {}", status, check.name, lines.next().unwrap_or_default());
+        for line in lines {
+            println!("       {}", line);
+        }
+    }
+}
+
+/// Stamps the version of a package, runs the job, then `after_package` for
+/// a successful package, printing every update, and maps the outcome to an
+/// exit code.
+fn run_job(spec: &JobSpec, json: bool) -> ExitCode {
+    let mut last_percent = None;
+    let mut succeeded = false;
+    let started = SystemTime::now();
+    let data_dir = storage::config_dir();
+    let mut report = |update| {
+        print_update(update, json, &mut last_percent);
+    };
+    let stamped = match stamp_package(spec, Some(&data_dir), &mut report) {
+        Ok(stamped) => stamped,
+        Err(e) => {
+            report(ProgressUpdate::Failed(format!("Failed to stamp the project version: {}", e)));
+            return ExitCode::from(EXIT_JOB_FAILED);
+        }
+    };
+    let mut commands = Vec::new();
+    for update in spec.launch() {
+        if let ProgressUpdate::Command(command) = &update {
+            commands.push(command.clone());
+        }
+        if let Some(outcome) = print_update(update, json, &mut last_percent) {
+            succeeded = outcome;
+        }
+    }
+    let mut report = |update| {
+        print_update(update, json, &mut last_percent);
+    };
+    let project_version = stamped.as_ref().and_then(StampedConfig::project_version);
+    if let Some(stamped) = stamped {
+        stamped.restore(&mut report);
+    }
+    if succeeded && spec.kind == JobKind::Package {
+        let run = PackageRun { started, commands, project_version };
+        succeeded = after_package(spec, Some(&data_dir), run, &mut report);
+    }
+    if succeeded {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(EXIT_JOB_FAILED)
+    }
+}
+
+/// Prints one update. Returns whether the job succeeded when the update
+/// tells.
+fn print_update(update: ProgressUpdate, json: bool, last_percent: &mut Option<u32>) -> Option<bool> {
+    if json {
+        println!("{}", serde_json::to_string(&update).unwrap_or_default());
+    }
+    match update {
+        ProgressUpdate::Progress(progress) => {
+            let percent = (progress * 100.0) as u32;
+            if !json && *last_percent != Some(percent) {
+                println!("[{:3}%]", percent);
+            }
+            *last_percent = Some(percent);
+            None
+        }
+        ProgressUpdate::Stage(message) if !json => {
+            println!("== {}", message);
+            None
+        }
+        ProgressUpdate::Log(line) if !json => {
+            println!("{}", line);
+            None
+        }
+        ProgressUpdate::Finished(message) => {
+            if !json {
+                println!("{}", message);
+            }
+            Some(true)
+        }
+        ProgressUpdate::Failed(message) => {
+            if !json {
+                eprintln!("{}", message);
+            }
+            Some(false)
+        }
+        _ => None,
+    }
+}
@@ -1,179 +1,446 @@
-﻿use std::path::{PathBuf};
-use std::process::{Command, Stdio};
+﻿use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
 use std::io::{BufReader, BufRead};
-use std::sync::mpsc::{self, Receiver};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::fmt;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 
 #[cfg(target_os = "windows")]
 const BUILD_SCRIPT: &str = "Build.bat";
 #[cfg(target_os = "windows")]
 const UAT_SCRIPT: &str = "RunUAT.bat";
+#[cfg(target_os = "windows")]
+const GENERATE_PROJECT_FILES_SCRIPT: &str = "GenerateProjectFiles.bat";
 #[cfg(target_os = "macos")]
 const BUILD_SCRIPT: &str = "Mac/Build.sh";
 #[cfg(target_os = "macos")]
 const UAT_SCRIPT: &str = "RunUAT.sh";
+#[cfg(target_os = "macos")]
+const GENERATE_PROJECT_FILES_SCRIPT: &str = "Mac/GenerateProjectFiles.sh";
+#[cfg(target_os = "linux")]
+const BUILD_SCRIPT: &str = "Linux/Build.sh";
+#[cfg(target_os = "linux")]
+const UAT_SCRIPT: &str = "RunUAT.sh";
+#[cfg(target_os = "linux")]
+const GENERATE_PROJECT_FILES_SCRIPT: &str = "Linux/GenerateProjectFiles.sh";
+
+/// Build configuration passed to UnrealBuildTool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BuildMode {
+    Debug,
+    Development,
+    Shipping,
+}
+
+impl BuildMode {
+    /// Every configuration, in display order.
+    pub const ALL: [BuildMode; 3] = [BuildMode::Debug, BuildMode::Development, BuildMode::Shipping];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BuildMode::Debug => "Debug",
+            BuildMode::Development => "Development",
+            BuildMode::Shipping => "Shipping",
+        }
+    }
+
+    /// Parses a configuration name, ignoring case.
+    pub fn from_name(name: &str) -> Option<BuildMode> {
+        BuildMode::ALL.into_iter().find(|mode| mode.as_str().eq_ignore_ascii_case(name))
+    }
+}
+
+impl fmt::Display for BuildMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
 
 /// Represents an update from the build/package process.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
 pub enum ProgressUpdate {
     /// A numeric progress update (value between 0.0 and 1.0)
     Progress(f32),
     /// A stage message update (e.g., "Build started", "Cooking...")
     Stage(String),
+    /// A line of process output, or the command line that started it.
+    Log(String),
+    /// The process was started with this id.
+    Process(u32),
+    /// A process was started with this program and arguments.
+    Command(Vec<String>),
+    /// The size report of a package was written to this file.
+    SizeReport(PathBuf),
     /// The process is finished with a final message.
     Finished(String),
+    /// The process could not be started or exited with an error.
+    Failed(String),
 }
 
-/// Launches the build process and returns a receiver for progress updates.
-/// Progress is parsed from lines matching the pattern "[current/total]".
-pub fn create_build_command(
-    engine_location: &PathBuf,
-    project_name: &str,
-    platform: &str,
-    optimization_type: &str,
-    uproject_location: &PathBuf,
-) -> Receiver<ProgressUpdate> {
-    let (tx, rx) = mpsc::channel::<ProgressUpdate>();
+/// Returns the path of a script under `Engine/Build/BatchFiles`.
+fn batch_file(engine_location: &Path, script: &str) -> PathBuf {
+    engine_location
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join("Engine")
+        .join("Build")
+        .join("BatchFiles")
+        .join(script)
+}
 
-    let engine_path = engine_location.parent().unwrap().to_string_lossy();
-    let build_bat = format!("{}\\Engine\\Build\\BatchFiles\\{}", engine_path, BUILD_SCRIPT);
+/// Script run by build and clean jobs.
+pub fn build_script(engine_location: &Path) -> PathBuf {
+    batch_file(engine_location, BUILD_SCRIPT)
+}
 
-    let args = [
-        project_name,
-        platform,
-        optimization_type,
-        &uproject_location.to_string_lossy(),
-        "-waitmutex",
-    ];
+/// Script run by package and plugin package jobs.
+pub fn uat_script(engine_location: &Path) -> PathBuf {
+    batch_file(engine_location, UAT_SCRIPT)
+}
 
-    let working_dir = uproject_location.parent().unwrap();
+/// Script run by project file generation jobs.
+pub fn generate_project_files_script(engine_location: &Path) -> PathBuf {
+    batch_file(engine_location, GENERATE_PROJECT_FILES_SCRIPT)
+}
 
-    println!("Build command: {} {:?}", build_bat, args);
+/// The program and arguments that run an engine script: batch files are
+/// run through `cmd /C` on Windows.
+fn script_command_line(script: &Path, args: &[String]) -> Vec<OsString> {
+    let mut command_line = Vec::new();
+    if cfg!(target_os = "windows") {
+        command_line.push(OsString::from("cmd"));
+        command_line.push(OsString::from("/C"));
+    }
+    command_line.push(script.as_os_str().to_owned());
+    command_line.extend(args.iter().map(OsString::from));
+    command_line
+}
 
-    let mut child = Command::new("cmd")
-        .args(&["/C", &build_bat])
-        .args(&args)
+/// Starts `command_line` with its stdout piped.
+fn spawn_script(command_line: &[OsString], working_dir: &Path) -> std::io::Result<Child> {
+    Command::new(&command_line[0])
+        .args(&command_line[1..])
         .current_dir(working_dir)
         .stdout(Stdio::piped())
         .spawn()
-        .expect("Failed to execute build command");
+}
 
+/// Runs a script and forwards its output to `parse_line` on a background
+/// thread. Updates returned by the parser are sent on the returned channel;
+/// once the process exits, a `Finished` or `Failed` update is sent if the
+/// parser did not already report completion.
+fn run_script<F>(label: &'static str, script: &Path, args: &[String], working_dir: &Path, parse_line: F) -> Receiver<ProgressUpdate>
+where
+    F: Fn(&str) -> Option<ProgressUpdate> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel::<ProgressUpdate>();
+    let _ = tx.send(ProgressUpdate::Log(format!("{} command: {:?} {:?}", label, script, args)));
+
+    let command_line = script_command_line(script, args);
+    let mut child = match spawn_script(&command_line, working_dir) {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("Failed to execute {} command: {}", label, e);
+            let _ = tx.send(ProgressUpdate::Failed(format!("{} could not start: {}", label, e)));
+            return rx;
+        }
+    };
     let stdout = child.stdout.take().expect("Failed to capture stdout");
-    let progress_regex = Regex::new(r"\[([0-9]+)/([0-9]+)\]").unwrap();
+    let _ = tx.send(ProgressUpdate::Process(child.id()));
+    let _ = tx.send(ProgressUpdate::Command(
+        command_line.iter().map(|arg| arg.to_string_lossy().into_owned()).collect(),
+    ));
 
     std::thread::spawn(move || {
         let reader = BufReader::new(stdout);
-        for line_result in reader.lines() {
-            if let Ok(line) = line_result {
-                println!("Build output: {}", line);
-                if line.contains("BUILD SUCCESSFUL") {
-                    let _ = tx.send(ProgressUpdate::Finished("Build finished".to_owned()));
-                } else if let Some(caps) = progress_regex.captures(&line) {
-                    if let (Some(curr_match), Some(total_match)) = (caps.get(1), caps.get(2)) {
-                        if let (Ok(current), Ok(total)) =
-                            (curr_match.as_str().parse::<f32>(), total_match.as_str().parse::<f32>())
-                        {
-                            if total > 0.0 {
-                                let progress = current / total;
-                                let _ = tx.send(ProgressUpdate::Progress(progress));
-                            }
-                        }
-                    }
-                }
+        let mut completed = false;
+        for line in reader.lines().map_while(Result::ok) {
+            let update = parse_line(&line);
+            let _ = tx.send(ProgressUpdate::Log(line));
+            if let Some(update) = update {
+                completed |= matches!(update, ProgressUpdate::Finished(_) | ProgressUpdate::Failed(_));
+                let _ = tx.send(update);
             }
         }
+        report_exit(label, &mut child, completed, &tx);
     });
 
     rx
 }
 
+/// Waits for the process and reports how it ended, unless its output already did.
+fn report_exit(label: &str, child: &mut Child, completed: bool, tx: &Sender<ProgressUpdate>) {
+    let update = match child.wait() {
+        Ok(status) if status.success() => ProgressUpdate::Finished(format!("{} finished", label)),
+        Ok(status) => ProgressUpdate::Failed(match status.code() {
+            Some(code) => format!("{} failed (exit code {})", label, code),
+            None => format!("{} was terminated", label),
+        }),
+        Err(e) => ProgressUpdate::Failed(format!("{} failed: {}", label, e)),
+    };
+    if !completed || matches!(update, ProgressUpdate::Failed(_)) {
+        let _ = tx.send(update);
+    }
+}
+
+/// Returns a parser turning UnrealBuildTool's "[current/total]" action
+/// lines into progress updates.
+fn action_progress_parser() -> impl Fn(&str) -> Option<ProgressUpdate> {
+    let progress_regex = Regex::new(r"\[([0-9]+)/([0-9]+)\]").unwrap();
+    move |line| {
+        let caps = progress_regex.captures(line)?;
+        let current = caps.get(1)?.as_str().parse::<f32>().ok()?;
+        let total = caps.get(2)?.as_str().parse::<f32>().ok()?;
+        (total > 0.0).then(|| ProgressUpdate::Progress(current / total))
+    }
+}
+
+/// Launches the build process and returns a receiver for progress updates.
+/// Progress is parsed from lines matching the pattern "[current/total]".
+pub fn create_build_command(
+    engine_location: &Path,
+    project_name: &str,
+    platform: &str,
+    optimization_type: &str,
+    uproject_location: &Path,
+) -> Receiver<ProgressUpdate> {
+    let build_bat = batch_file(engine_location, BUILD_SCRIPT);
+
+    let args = [
+        project_name.to_owned(),
+        platform.to_owned(),
+        optimization_type.to_owned(),
+        uproject_location.to_string_lossy().to_string(),
+        "-waitmutex".to_owned(),
+    ];
+
+    let working_dir = uproject_location.parent().unwrap();
+    let parse_progress = action_progress_parser();
+
+    run_script("Build", &build_bat, &args, working_dir, move |line| {
+        if line.contains("BUILD SUCCESSFUL") {
+            Some(ProgressUpdate::Finished("Build finished".to_owned()))
+        } else {
+            parse_progress(line)
+        }
+    })
+}
+
+/// Cleans the build products of a project target (UnrealBuildTool `-clean`).
+pub fn create_clean_command(
+    engine_location: &Path,
+    project_name: &str,
+    platform: &str,
+    optimization_type: &str,
+    uproject_location: &Path,
+) -> Receiver<ProgressUpdate> {
+    let build_bat = batch_file(engine_location, BUILD_SCRIPT);
+
+    let args = [
+        project_name.to_owned(),
+        platform.to_owned(),
+        optimization_type.to_owned(),
+        uproject_location.to_string_lossy().to_string(),
+        "-clean".to_owned(),
+        "-waitmutex".to_owned(),
+    ];
+
+    let working_dir = uproject_location.parent().unwrap();
+
+    run_script("Clean", &build_bat, &args, working_dir, |_| None)
+}
+
+/// BuildCookRun arguments of a package staged into `staging_directory` and,
+/// if given, archived into `archive_directory`.
+fn package_args(
+    platform: &str,
+    optimization_type: &str,
+    uproject_location: &Path,
+    staging_directory: &Path,
+    archive_directory: Option<&Path>,
+) -> Vec<String> {
+    let mut args = vec![
+        "BuildCookRun".to_owned(),
+        format!("-project={}", uproject_location.to_string_lossy()),
+        "-noP4".to_owned(),
+        format!("-platform={}", platform),
+        format!("-clientconfig={}", optimization_type),
+        format!("-serverconfig={}", optimization_type),
+        "-nocompileeditor".to_owned(),
+        "-cook".to_owned(),
+        "-allmaps".to_owned(),
+        "-build".to_owned(),
+        "-CookCultures=en".to_owned(),
+        "-unversionedcookedcontent".to_owned(),
+        "-stage".to_owned(),
+        "-package".to_owned(),
+        format!("-stagingdirectory={}", staging_directory.to_string_lossy()),
+    ];
+    if let Some(archive_directory) = archive_directory {
+        args.push("-archive".to_owned());
+        args.push(format!("-archivedirectory={}", archive_directory.to_string_lossy()));
+    }
+    args
+}
+
 /// Launches the package process and returns a receiver for progress updates.
+/// The build is staged into `staging_directory` and, if given, archived into
+/// `archive_directory`.
 pub fn create_package_command(
-    engine_location: &PathBuf,
+    engine_location: &Path,
     platform: &str,
     optimization_type: &str,
-    uproject_location: &PathBuf,
+    uproject_location: &Path,
+    staging_directory: &Path,
+    archive_directory: Option<&Path>,
 ) -> Receiver<ProgressUpdate> {
-    let (tx, rx) = mpsc::channel::<ProgressUpdate>();
+    let uat_bat = batch_file(engine_location, UAT_SCRIPT);
+    let args = package_args(platform, optimization_type, uproject_location, staging_directory, archive_directory);
+
+    let working_dir = uproject_location.parent().unwrap();
 
-    let engine_path = engine_location.parent().unwrap().to_string_lossy().to_string();
-    let uat_bat = format!("{}\\Engine\\Build\\BatchFiles\\{}", engine_path, UAT_SCRIPT);
-    let staging_directory = format!(
-        "{}\\Builds",
-        uproject_location.parent().unwrap().to_string_lossy()
-    );
+    let percentage_regex = Regex::new(r"(\d+)%").unwrap();
+
+    run_script("Package", &uat_bat, &args, working_dir, move |line| {
+        if line.contains("********** BUILD COMMAND STARTED **********") {
+            Some(ProgressUpdate::Stage("Build started".into()))
+        } else if line.contains("********** BUILD COMMAND COMPLETED **********") {
+            Some(ProgressUpdate::Stage("Build completed".into()))
+        } else if line.contains("********** COOK COMMAND STARTED **********") {
+            Some(ProgressUpdate::Stage("Cooking...".into()))
+        } else if line.contains("********** COOK COMMAND COMPLETED **********") {
+            Some(ProgressUpdate::Stage("Cook completed".into()))
+        } else if line.contains("********** STAGE COMMAND STARTED **********") {
+            Some(ProgressUpdate::Stage("Staging...".into()))
+        } else if line.contains("********** PACKAGE COMMAND STARTED **********") {
+            Some(ProgressUpdate::Stage("Packaging...".into()))
+        } else if line.contains("********** PACKAGE COMMAND COMPLETED **********") {
+            Some(ProgressUpdate::Stage("Package completed".into()))
+        } else if line.contains("BUILD SUCCESSFUL") {
+            Some(ProgressUpdate::Finished("Package finished".into()))
+        } else {
+            let caps = percentage_regex.captures(line)?;
+            let percent = caps.get(1)?.as_str().parse::<f32>().ok()?;
+            Some(ProgressUpdate::Progress(percent / 100.0))
+        }
+    })
+}
+
+/// Launches `RunUAT BuildPlugin` for a .uplugin file and returns a receiver
+/// for progress updates. The packaged plugin is written to `package_directory`.
+pub fn create_plugin_package_command(
+    engine_location: &Path,
+    uplugin_location: &Path,
+    package_directory: &Path,
+    target_platforms: &[&str],
+) -> Receiver<ProgressUpdate> {
+    let uat_bat = batch_file(engine_location, UAT_SCRIPT);
 
     let args = [
-        "BuildCookRun",
-        &format!("-project={}", uproject_location.to_string_lossy()),
-        "-noP4",
-        &format!("-platform={}", platform),
-        &format!("-clientconfig={}", optimization_type),
-        &format!("-serverconfig={}", optimization_type),
-        "-nocompileeditor",
-        "-cook",
-        "-allmaps",
-        "-build",
-        "-CookCultures=en",
-        "-unversionedcookedcontent",
-        "-stage",
-        "-package",
-        &format!("-stagingdirectory={}", staging_directory),
+        "BuildPlugin".to_owned(),
+        format!("-Plugin={}", uplugin_location.to_string_lossy()),
+        format!("-Package={}", package_directory.to_string_lossy()),
+        format!("-TargetPlatforms={}", target_platforms.join("+")),
     ];
 
-    println!("Package command: {} {:?}", uat_bat, args);
+    let working_dir = uplugin_location.parent().unwrap();
+    let parse_progress = action_progress_parser();
 
+    run_script("Plugin package", &uat_bat, &args, working_dir, move |line| {
+        if line.contains("Building plugin for host platforms") {
+            Some(ProgressUpdate::Stage("Building for host platforms...".into()))
+        } else if line.contains("Building plugin for target platforms") {
+            Some(ProgressUpdate::Stage("Building for target platforms...".into()))
+        } else if line.contains("BUILD SUCCESSFUL") {
+            Some(ProgressUpdate::Finished("Plugin package finished".into()))
+        } else {
+            parse_progress(line)
+        }
+    })
+}
+
+/// Regenerates the IDE project files of a project with the given engine.
+pub fn create_generate_project_files_command(engine_location: &Path, uproject_location: &Path) -> Receiver<ProgressUpdate> {
+    let script = batch_file(engine_location, GENERATE_PROJECT_FILES_SCRIPT);
+    let args = [
+        format!("-project={}", uproject_location.to_string_lossy()),
+        "-game".to_owned(),
+    ];
     let working_dir = uproject_location.parent().unwrap();
+    run_script("Generate project files", &script, &args, working_dir, |_| None)
+}
 
-    let mut child = Command::new("cmd")
-        .args(&["/C", &uat_bat])
-        .args(&args)
-        .current_dir(working_dir)
-        .stdout(Stdio::piped())
-        .spawn()
-        .expect("Failed to execute package command");
+/// Stops a process started by a job together with the processes it started
+/// (UnrealBuildTool, the compilers, the cooker).
+pub fn kill_process_tree(pid: u32) -> std::io::Result<()> {
+    if cfg!(target_os = "windows") {
+        let status = Command::new("taskkill").args(["/PID", &pid.to_string(), "/T", "/F"]).status()?;
+        if !status.success() {
+            return Err(std::io::Error::other(format!("could not stop process {} ({})", pid, status)));
+        }
+    } else {
+        let listing = Command::new("ps").args(["-A", "-o", "pid=,ppid="]).output()?;
+        let mut pids = descendants(pid, &String::from_utf8_lossy(&listing.stdout));
+        pids.push(pid);
+        // Some of the processes may have exited since the listing.
+        Command::new("kill")
+            .arg("-TERM")
+            .args(pids.iter().map(u32::to_string))
+            .stderr(Stdio::null())
+            .status()?;
+    }
+    Ok(())
+}
 
-    let stdout = child.stdout.take().expect("Failed to capture stdout");
+/// The ids of every descendant of `root` in a `ps -o pid=,ppid=` listing.
+fn descendants(root: u32, listing: &str) -> Vec<u32> {
+    let parents: Vec<(u32, u32)> = listing
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().map(|field| field.parse::<u32>());
+            Some((fields.next()?.ok()?, fields.next()?.ok()?))
+        })
+        .collect();
+    let mut found = Vec::new();
+    let mut pending = vec![root];
+    while let Some(parent) = pending.pop() {
+        let children: Vec<u32> = parents
+            .iter()
+            .filter(|&&(pid, ppid)| ppid == parent && !found.contains(&pid))
+            .map(|&(pid, _)| pid)
+            .collect();
+        found.extend(&children);
+        pending.extend(children);
+    }
+    found
+}
 
-    let percentage_regex = Regex::new(r"(\d+)%").unwrap();
+/// Opens `path` in the platform's file browser.
+pub fn open_in_file_browser(path: &Path) -> std::io::Result<()> {
+    let program = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    Command::new(program).arg(path).spawn()?;
+    Ok(())
+}
 
-    std::thread::spawn(move || {
-        let reader = BufReader::new(stdout);
-        for line_result in reader.lines() {
-            if let Ok(line) = line_result {
-                println!("Package output: {}", line);
-                if line.contains("********** BUILD COMMAND STARTED **********") {
-                    let _ = tx.send(ProgressUpdate::Stage("Build started".into()));
-                } else if line.contains("********** BUILD COMMAND COMPLETED **********") {
-                    let _ = tx.send(ProgressUpdate::Stage("Build completed".into()));
-                } else if line.contains("********** COOK COMMAND STARTED **********") {
-                    let _ = tx.send(ProgressUpdate::Stage("Cooking...".into()));
-                } else if line.contains("********** COOK COMMAND COMPLETED **********") {
-                    let _ = tx.send(ProgressUpdate::Stage("Cook completed".into()));
-                } else if line.contains("********** STAGE COMMAND STARTED **********") {
-                    let _ = tx.send(ProgressUpdate::Stage("Staging...".into()));
-                } else if line.contains("********** PACKAGE COMMAND STARTED **********") {
-                    let _ = tx.send(ProgressUpdate::Stage("Packaging...".into()));
-                } else if line.contains("********** PACKAGE COMMAND COMPLETED **********") {
-                    let _ = tx.send(ProgressUpdate::Stage("Package completed".into()));
-                } else if line.contains("BUILD SUCCESSFUL") {
-                    // Open the staging directory in the file explorer.
-                    if cfg!(target_os = "windows") {
-                        let _ = Command::new("explorer").arg(&staging_directory).spawn();
-                    } else if cfg!(target_os = "macos") {
-                        let _ = Command::new("open").arg(&staging_directory).spawn();
-                    }
-                    let _ = tx.send(ProgressUpdate::Finished("Package finished".into()));
-                } else if let Some(caps) = percentage_regex.captures(&line) {
-                    if let Some(num_str) = caps.get(1) {
-                        if let Ok(percent) = num_str.as_str().parse::<f32>() {
-                            let progress = percent / 100.0;
-                            let _ = tx.send(ProgressUpdate::Progress(progress));
-                        }
-                    }
-                }
-            }
-        }
-    });
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    rx
-}
\ No newline at end of file
+    #[test]
+    fn finds_descendant_processes() {
+        let listing = "    1     0\n  100     1\n  101   100\n  102   101\n  200     1\n  103   100\n";
+        let mut pids = descendants(100, listing);
+        pids.sort();
+        assert_eq!(pids, [101, 102, 103]);
+        assert!(descendants(102, listing).is_empty());
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::platform::Platform;
+use crate::plugins::{self, PluginDescriptor};
+use crate::storage::{Engine, Project};
+
+/// A problem with an enabled plugin that is likely to make a build fail.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PluginIssue {
+    /// The plugin declares an `EngineVersion` other than the engine's.
+    EngineVersionMismatch {
+        plugin: String,
+        plugin_engine_version: String,
+        engine_version: String,
+    },
+    /// Neither the plugin nor any of its modules supports the platform.
+    UnsupportedPlatform { plugin: String, platform: Platform },
+    /// The plugin is not in the project, the engine or the marketplace folders.
+    NotFound { plugin: String },
+}
+
+impl fmt::Display for PluginIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginIssue::EngineVersionMismatch {
+                plugin,
+                plugin_engine_version,
+                engine_version,
+            } => write!(
+                f,
+                "{} targets engine {} but the selected engine is {}",
+                plugin, plugin_engine_version, engine_version
+            ),
+            PluginIssue::UnsupportedPlatform { plugin, platform } => {
+                write!(f, "{} does not support {}", plugin, platform)
+            }
+            PluginIssue::NotFound { plugin } => write!(
+                f,
+                "{} was not found in the project, the engine or the marketplace plugins",
+                plugin
+            ),
+        }
+    }
+}
+
+/// Checks the plugins enabled in `project` against `engine` and `platform`.
+/// Project-local plugins shadow engine plugins of the same name, matching
+/// UnrealBuildTool's lookup order.
+pub fn check_plugins(project: &Project, engine: &Engine, platform: Platform) -> Vec<PluginIssue> {
+    let engine_version = engine.build_version().ok().map(|version| version.major_minor());
+    let engine_plugins: HashMap<String, PathBuf> =
+        plugins::find_uplugins(&engine.root().join("Engine").join("Plugins"))
+            .into_iter()
+            .filter_map(|path| {
+                let name = path.file_stem()?.to_string_lossy().to_lowercase();
+                Some((name, path))
+            })
+            .collect();
+
+    let mut issues = Vec::new();
+    for reference in project.plugins.iter().filter(|plugin| plugin.enabled) {
+        let local = project
+            .local_plugins
+            .iter()
+            .find(|descriptor| descriptor.name.eq_ignore_ascii_case(&reference.name))
+            .cloned();
+        let descriptor = local.or_else(|| {
+            let path = engine_plugins.get(&reference.name.to_lowercase())?;
+            PluginDescriptor::load(path).ok()
+        });
+        let Some(descriptor) = descriptor else {
+            issues.push(PluginIssue::NotFound {
+                plugin: reference.name.clone(),
+            });
+            continue;
+        };
+
+        if let (Some(plugin_engine_version), Some(engine_version)) =
+            (descriptor.engine_version.as_deref(), engine_version.as_deref())
+        {
+            if !same_major_minor(plugin_engine_version, engine_version) {
+                issues.push(PluginIssue::EngineVersionMismatch {
+                    plugin: reference.name.clone(),
+                    plugin_engine_version: plugin_engine_version.to_owned(),
+                    engine_version: engine_version.to_owned(),
+                });
+            }
+        }
+
+        // A plugin the project itself excludes from this platform is never built for it.
+        let excluded_by_project =
+            !reference.platform_allow_list.is_empty() && !contains_platform(&reference.platform_allow_list, platform);
+        if !excluded_by_project && !supports_platform(&descriptor, platform) {
+            issues.push(PluginIssue::UnsupportedPlatform {
+                plugin: reference.name.clone(),
+                platform,
+            });
+        }
+    }
+    issues
+}
+
+/// Whether the plugin's `SupportedTargetPlatforms` and module allow lists
+/// permit `platform`. An empty list means "every platform".
+fn supports_platform(descriptor: &PluginDescriptor, platform: Platform) -> bool {
+    if !descriptor.supported_target_platforms.is_empty()
+        && !contains_platform(&descriptor.supported_target_platforms, platform)
+    {
+        return false;
+    }
+    descriptor.modules.is_empty()
+        || descriptor
+            .modules
+            .iter()
+            .any(|module| module.platform_allow_list.is_empty() || contains_platform(&module.platform_allow_list, platform))
+}
+
+fn contains_platform(list: &[String], platform: Platform) -> bool {
+    list.iter().any(|name| name.eq_ignore_ascii_case(platform.name()))
+}
+
+/// Compares two version strings such as "5.3.0" and "5.3" on major.minor.
+fn same_major_minor(a: &str, b: &str) -> bool {
+    let major_minor = |version: &str| version.split('.').take(2).map(str::to_owned).collect::<Vec<_>>();
+    major_minor(a) == major_minor(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fixture;
+    use std::fs;
+    use std::path::Path;
+
+    fn write_plugin(dir: &Path, name: &str, descriptor: &str) {
+        let dir = dir.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(format!("{}.uplugin", name)), descriptor).unwrap();
+    }
+
+    #[test]
+    fn compares_major_minor_versions() {
+        assert!(same_major_minor("5.3", "5.3.2"));
+        assert!(same_major_minor("5.3.0", "5.3"));
+        assert!(!same_major_minor("5.3", "5.4"));
+        assert!(!same_major_minor("5.30", "5.3"));
+        assert!(!same_major_minor("4.27.2", "5.3"));
+    }
+
+    #[test]
+    fn checks_enabled_plugins() {
+        let (root, project, engine) = fixture("compatibility");
+        let project_plugins = root.join("Game").join("Plugins");
+        let engine_plugins = root.join("Engine").join("Plugins");
+        write_plugin(&project_plugins, "Local", r#"{ "EngineVersion": "5.4.0" }"#);
+        write_plugin(&project_plugins, "OldLocal", r#"{ "EngineVersion": "5.3.0" }"#);
+        // Project plugins shadow engine plugins of the same name.
+        write_plugin(&engine_plugins.join("Runtime"), "Local", r#"{ "EngineVersion": "5.1.0" }"#);
+        write_plugin(
+            &engine_plugins.join("Runtime"),
+            "WindowsOnly",
+            r#"{ "Modules": [{ "Name": "WindowsOnly", "PlatformAllowList": ["Win64"] }] }"#,
+        );
+        write_plugin(
+            &engine_plugins.join("Runtime"),
+            "AnyModule",
+            r#"{ "Modules": [{ "Name": "A", "PlatformAllowList": ["Win64"] }, { "Name": "B" }] }"#,
+        );
+        write_plugin(&engine_plugins.join("Marketplace"), "Store", r#"{ "SupportedTargetPlatforms": ["Mac"] }"#);
+        fs::write(
+            &project.location,
+            r#"{ "EngineAssociation": "5.4", "Plugins": [
+                { "Name": "Local", "Enabled": true },
+                { "Name": "OldLocal", "Enabled": true },
+                { "Name": "WindowsOnly", "Enabled": true },
+                { "Name": "windowsonly", "Enabled": true, "PlatformAllowList": ["Win64"] },
+                { "Name": "AnyModule", "Enabled": true },
+                { "Name": "Store", "Enabled": true },
+                { "Name": "Missing", "Enabled": true },
+                { "Name": "MissingButDisabled", "Enabled": false }
+            ] }"#,
+        )
+        .unwrap();
+        let project = Project::new(project.location.clone()).unwrap();
+
+        assert_eq!(
+            check_plugins(&project, &engine, Platform::Linux),
+            [
+                PluginIssue::EngineVersionMismatch {
+                    plugin: "OldLocal".to_owned(),
+                    plugin_engine_version: "5.3.0".to_owned(),
+                    engine_version: "5.4".to_owned(),
+                },
+                PluginIssue::UnsupportedPlatform { plugin: "WindowsOnly".to_owned(), platform: Platform::Linux },
+                PluginIssue::UnsupportedPlatform { plugin: "Store".to_owned(), platform: Platform::Linux },
+                PluginIssue::NotFound { plugin: "Missing".to_owned() },
+            ]
+        );
+        let unsupported_on_mac: Vec<String> = check_plugins(&project, &engine, Platform::Mac)
+            .into_iter()
+            .filter_map(|issue| match issue {
+                PluginIssue::UnsupportedPlatform { plugin, .. } => Some(plugin),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(unsupported_on_mac, ["WindowsOnly"]);
+    }
+}
@@ -0,0 +1,141 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::SystemTime;
+
+/// Free space below which a job is not started, and a running job is aborted.
+pub const MIN_FREE_SPACE: u64 = 2 << 30;
+
+/// The closest existing ancestor of `path` (or `path` itself).
+pub fn existing_ancestor(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|ancestor| ancestor.exists())
+}
+
+/// Bytes available to the user on the volume that holds (or will hold) `path`.
+pub fn free_space(path: &Path) -> Option<u64> {
+    let dir = existing_ancestor(path)?;
+    if cfg!(target_os = "windows") {
+        let script = format!(
+            "(Get-Item -LiteralPath '{}').PSDrive.Free",
+            dir.to_string_lossy().replace('\'', "''")
+        );
+        let output = Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+            .output()
+            .ok()?;
+        String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+    } else {
+        let output = Command::new("df").arg("-Pk").arg(dir).output().ok()?;
+        parse_df_output(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+/// Reads the available bytes from `df -Pk` output.
+fn parse_df_output(output: &str) -> Option<u64> {
+    let line = output.lines().nth(1)?;
+    let available_kib: u64 = line.split_whitespace().nth(3)?.parse().ok()?;
+    Some(available_kib * 1024)
+}
+
+/// Human-readable size, e.g. "1.5 GiB".
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Calls `visit` with every file under `dir`, its size and modification
+/// time. Symbolic links are not followed.
+pub fn walk_files(dir: &Path, visit: &mut impl FnMut(&Path, u64, Option<SystemTime>)) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(metadata) = fs::symlink_metadata(entry.path()) else {
+            continue;
+        };
+        if metadata.is_dir() {
+            walk_files(&entry.path(), visit);
+        } else if metadata.is_file() {
+            visit(&entry.path(), metadata.len(), metadata.modified().ok());
+        }
+    }
+}
+
+/// Total size of the files under `dir`.
+pub fn dir_size(dir: &Path) -> u64 {
+    let mut total = 0;
+    walk_files(dir, &mut |_, size, _| total += size);
+    total
+}
+
+/// Total size of the entries of `dir` that were written to since `since`:
+/// a folder counts in full if any file in it was modified after `since`.
+/// Measures what a job staged into a shared output directory, even when
+/// the copied files keep their original modification times.
+pub fn written_size(dir: &Path, since: SystemTime) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut total = 0;
+    for entry in entries.flatten() {
+        let Ok(metadata) = fs::symlink_metadata(entry.path()) else {
+            continue;
+        };
+        let touched = |modified: Option<SystemTime>| modified.is_some_and(|modified| modified >= since);
+        if metadata.is_dir() {
+            let (mut size, mut written) = (0, touched(metadata.modified().ok()));
+            walk_files(&entry.path(), &mut |_, file_size, modified| {
+                size += file_size;
+                written |= touched(modified);
+            });
+            if written {
+                total += size;
+            }
+        } else if metadata.is_file() && touched(metadata.modified().ok()) {
+            total += metadata.len();
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use std::time::Duration;
+
+    #[test]
+    fn parses_df_output() {
+        let df = "Filesystem 1024-blocks Used Available Capacity Mounted on\n\
+                  /dev/sda1 102400 51200 51200 50% /\n";
+        assert_eq!(parse_df_output(df), Some(51200 * 1024));
+        assert_eq!(parse_df_output(""), None);
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536 << 20), "1.5 GiB");
+    }
+
+    #[test]
+    fn measures_written_folders() {
+        let dir = TempDir::new("disk");
+        fs::create_dir_all(dir.join("Windows").join("Game")).unwrap();
+        fs::create_dir_all(dir.join("Linux")).unwrap();
+        fs::write(dir.join("Windows").join("Game").join("Game.pak"), vec![0; 300]).unwrap();
+        fs::write(dir.join("Linux").join("Game.pak"), vec![0; 200]).unwrap();
+        assert_eq!(dir_size(&dir), 500);
+
+        let past = SystemTime::now() - Duration::from_secs(3600);
+        assert_eq!(written_size(&dir, past), 500);
+        let future = SystemTime::now() + Duration::from_secs(3600);
+        assert_eq!(written_size(&dir, future), 0);
+    }
+}
@@ -0,0 +1,239 @@
+use regex::Regex;
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::storage::{self, Engine, Project};
+use crate::uproject::UProjectFile;
+
+/// Returns the source builds registered with UnrealVersionSelector as
+/// `(GUID, engine root)` pairs. They live in the registry on Windows and in
+/// `Epic/UnrealEngine/Install.ini` elsewhere.
+pub fn registered_builds() -> Vec<(String, PathBuf)> {
+    if cfg!(target_os = "windows") {
+        registry_builds()
+    } else {
+        install_ini_builds()
+    }
+}
+
+fn registry_builds() -> Vec<(String, PathBuf)> {
+    let output = match Command::new("reg")
+        .args(["query", r"HKCU\SOFTWARE\Epic Games\Unreal Engine\Builds"])
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Failed to query registered engines: {}", e);
+            return Vec::new();
+        }
+    };
+    parse_registry_builds(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Reads the builds listed by `reg query`.
+fn parse_registry_builds(output: &str) -> Vec<(String, PathBuf)> {
+    // Lines look like: "    {GUID}    REG_SZ    D:/UnrealEngine"
+    let line_regex = Regex::new(r"^\s*(\S+)\s+REG_SZ\s+(.+?)\s*$").unwrap();
+    output
+        .lines()
+        .filter_map(|line| {
+            let caps = line_regex.captures(line)?;
+            Some((caps[1].to_owned(), PathBuf::from(&caps[2])))
+        })
+        .collect()
+}
+
+fn install_ini_builds() -> Vec<(String, PathBuf)> {
+    let Some(home) = std::env::var_os("HOME").map(PathBuf::from) else {
+        return Vec::new();
+    };
+    let ini = if cfg!(target_os = "macos") {
+        home.join("Library").join("Application Support").join("Epic").join("UnrealEngine").join("Install.ini")
+    } else {
+        home.join(".config").join("Epic").join("UnrealEngine").join("Install.ini")
+    };
+    let Ok(content) = fs::read_to_string(ini) else {
+        return Vec::new();
+    };
+    parse_install_ini(&content)
+}
+
+/// Reads the `[Installations]` section of `Install.ini`.
+fn parse_install_ini(content: &str) -> Vec<(String, PathBuf)> {
+    let mut in_installations = false;
+    let mut builds = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_installations = line.eq_ignore_ascii_case("[Installations]");
+        } else if let (true, Some((guid, path))) = (in_installations, line.split_once('=')) {
+            builds.push((guid.trim().to_owned(), PathBuf::from(path.trim())));
+        }
+    }
+    builds
+}
+
+/// Normalizes a path for comparison: forward slashes, no trailing slash and,
+/// on Windows, case-insensitive.
+fn normalize(path: &Path) -> String {
+    let normalized = path.to_string_lossy().replace('\\', "/");
+    let normalized = normalized.trim_end_matches('/');
+    if cfg!(target_os = "windows") {
+        normalized.to_lowercase()
+    } else {
+        normalized.to_owned()
+    }
+}
+
+/// Whether `association` is a version string ("5.4") or a registered build GUID.
+pub fn is_valid_association(association: &str) -> bool {
+    let version = Regex::new(r"^\d+\.\d+$").unwrap();
+    let guid = Regex::new(r"^\{[0-9A-Fa-f]{8}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{12}\}$").unwrap();
+    version.is_match(association) || guid.is_match(association)
+}
+
+/// Returns the `EngineAssociation` value that makes a project use `engine`:
+/// the GUID it is registered under for source builds, or the "major.minor"
+/// version for installed builds.
+pub fn association_for(engine: &Engine) -> Result<String, Box<dyn std::error::Error>> {
+    let root = engine.root();
+    if !root.join("Engine").is_dir() {
+        return Err(format!("{:?} is not an engine directory", root).into());
+    }
+    let engine_root = normalize(&root);
+    if let Some((guid, _)) = registered_builds()
+        .into_iter()
+        .find(|(_, path)| normalize(path) == engine_root)
+    {
+        return Ok(guid);
+    }
+    if root.join("Engine").join("Build").join("InstalledBuild.txt").is_file() {
+        return Ok(engine.build_version()?.major_minor());
+    }
+    Err(format!(
+        "{:?} is a source build that is not registered; register it with UnrealVersionSelector first",
+        root
+    )
+    .into())
+}
+
+/// Rewrites the project's `EngineAssociation` so it uses `engine`. The
+/// .uproject is backed up first; the backup location is returned.
+pub fn switch_engine(project: &Project, engine: &Engine) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let association = association_for(engine)?;
+    if !is_valid_association(&association) {
+        return Err(format!("{:?} is not a valid engine association", association).into());
+    }
+    let mut uproject = UProjectFile::load(&project.location)?;
+    uproject.set("EngineAssociation", json!(association));
+    let backup = uproject.save()?;
+    eprintln!("Switched {} to engine {} ({:?})", project.name, association, engine.location);
+    Ok(backup)
+}
+
+/// Finds the registered engine a project is associated with: the source
+/// build registered under its GUID, the engine whose `Build.version`
+/// matches its "major.minor" association, or, without an association, the
+/// engine tree the project lives in.
+pub fn engine_for<'a>(project: &Project, engines: &'a [Engine]) -> Result<&'a Engine, String> {
+    let uproject = storage::read_uproject(&project.location).map_err(|e| e.to_string())?;
+    let association = uproject.get("EngineAssociation").and_then(|value| value.as_str()).unwrap_or_default();
+    let engine = if association.is_empty() {
+        engines.iter().find(|engine| project.location.starts_with(engine.root()))
+    } else if association.starts_with('{') {
+        let root = registered_builds()
+            .into_iter()
+            .find(|(guid, _)| guid.eq_ignore_ascii_case(association))
+            .map(|(_, root)| normalize(&root))
+            .ok_or_else(|| format!("engine {} is not registered on this machine", association))?;
+        engines.iter().find(|engine| normalize(&engine.root()) == root)
+    } else {
+        engines.iter().find(|engine| {
+            engine
+                .build_version()
+                .is_ok_and(|version| version.major_minor() == association)
+        })
+    };
+    engine.ok_or_else(|| match association {
+        "" => "the project has no engine association and is not inside a registered engine".to_owned(),
+        association => format!("no registered engine matches {}", association),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fixture;
+
+    #[test]
+    fn validates_associations() {
+        for valid in ["5.4", "4.27", "{2C4A3E8B-1F2D-4E5A-9B7C-0D1E2F3A4B5C}", "{2c4a3e8b-1f2d-4e5a-9b7c-0d1e2f3a4b5c}"] {
+            assert!(is_valid_association(valid), "{}", valid);
+        }
+        for invalid in [
+            "",
+            "5",
+            "5.4.1",
+            "v5.4",
+            " 5.4",
+            "2C4A3E8B-1F2D-4E5A-9B7C-0D1E2F3A4B5C",
+            "{2C4A3E8B-1F2D-4E5A-9B7C-0D1E2F3A4B5}",
+            "{2C4A3E8B-1F2D-4E5A-9B7C-0D1E2F3A4B5G}",
+        ] {
+            assert!(!is_valid_association(invalid), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn parses_registered_builds() {
+        let output = "\r\nHKEY_CURRENT_USER\\SOFTWARE\\Epic Games\\Unreal Engine\\Builds\r\n    \
+                      {2C4A3E8B-1F2D-4E5A-9B7C-0D1E2F3A4B5C}    REG_SZ    D:/Source Builds/UE5  \r\n\r\n";
+        assert_eq!(
+            parse_registry_builds(output),
+            [("{2C4A3E8B-1F2D-4E5A-9B7C-0D1E2F3A4B5C}".to_owned(), PathBuf::from("D:/Source Builds/UE5"))]
+        );
+
+        let ini = "[Settings]\nKey=Value\n\n[Installations]\n{A1}=/home/dev/UnrealEngine\n {B2} = /opt/UE5 \n[Other]\n{C3}=/x\n";
+        assert_eq!(
+            parse_install_ini(ini),
+            [
+                ("{A1}".to_owned(), PathBuf::from("/home/dev/UnrealEngine")),
+                ("{B2}".to_owned(), PathBuf::from("/opt/UE5")),
+            ]
+        );
+    }
+
+    #[test]
+    fn normalizes_paths() {
+        let expected = if cfg!(target_os = "windows") { "d:/engines/ue5" } else { "D:/Engines/UE5" };
+        assert_eq!(normalize(Path::new("D:\\Engines\\UE5\\")), expected);
+        assert_eq!(normalize(Path::new("D:/Engines/UE5/")), expected);
+    }
+
+    #[test]
+    fn switches_installed_engines_by_version() {
+        let (root, project, engine) = fixture("association");
+        fs::write(root.join("Engine").join("Build").join("InstalledBuild.txt"), "").unwrap();
+        let original = "{\r\n\t\"FileVersion\": 3,\r\n\t\"EngineAssociation\": \"5.3\",\r\n\t\"Category\": \"\"\r\n}\r\n";
+        fs::write(&project.location, original).unwrap();
+
+        assert_eq!(association_for(&engine).unwrap(), "5.4");
+        let backup = switch_engine(&project, &engine).unwrap();
+        assert_eq!(fs::read_to_string(&backup).unwrap(), original);
+        assert_eq!(
+            fs::read_to_string(&project.location).unwrap(),
+            "{\r\n\t\"FileVersion\": 3,\r\n\t\"EngineAssociation\": \"5.4\",\r\n\t\"Category\": \"\"\r\n}\r\n"
+        );
+        assert_eq!(engine_for(&project, std::slice::from_ref(&engine)).unwrap().location, engine.location);
+    }
+
+    #[test]
+    fn refuses_unregistered_source_builds() {
+        let (root, project, engine) = fixture("association");
+        assert!(association_for(&engine).is_err());
+        assert!(switch_engine(&project, &engine).is_err());
+        assert!(!root.join("Game").join("Game.uproject.bak").exists());
+        assert_eq!(engine_for(&project, &[]).unwrap_err(), "no registered engine matches 5.4");
+    }
+}
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::commands::BuildMode;
+use crate::platform::Platform;
+use crate::size_report::SizeReport;
+use crate::storage::write_atomic;
+
+/// File in the configuration directory holding the package history.
+pub const HISTORY_FILE: &str = "history.json";
+/// Number of packages remembered.
+const MAX_ENTRIES: usize = 500;
+
+/// A successful package, remembered to estimate and compare later ones.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// The project's .uproject file.
+    pub project: PathBuf,
+    pub platform: Platform,
+    pub config: BuildMode,
+    /// Unix time the job finished.
+    pub finished: u64,
+    pub output_dir: PathBuf,
+    /// Bytes written to the output directory by the job.
+    pub staged_bytes: u64,
+    /// Breakdown of the staged platform folder.
+    #[serde(default)]
+    pub size: Option<SizeReport>,
+}
+
+/// Reads the history in `dir`, oldest first. A missing or unreadable file
+/// is an empty history.
+pub fn load_history(dir: &Path) -> Vec<HistoryEntry> {
+    fs::read_to_string(dir.join(HISTORY_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Appends `entry` to the history in `dir`, dropping the oldest entries
+/// past `MAX_ENTRIES`.
+pub fn record(dir: &Path, entry: HistoryEntry) -> Result<(), Box<dyn std::error::Error>> {
+    let mut history = load_history(dir);
+    history.push(entry);
+    let excess = history.len().saturating_sub(MAX_ENTRIES);
+    history.drain(..excess);
+    fs::create_dir_all(dir)?;
+    write_atomic(&dir.join(HISTORY_FILE), serde_json::to_string_pretty(&history)?.as_bytes())
+}
+
+/// The most recent package of `project`, for `platform` and `config` when
+/// given.
+pub fn latest<'a>(
+    history: &'a [HistoryEntry],
+    project: &Path,
+    platform: Option<Platform>,
+    config: Option<BuildMode>,
+) -> Option<&'a HistoryEntry> {
+    history.iter().rev().find(|entry| {
+        entry.project == project
+            && platform.is_none_or(|platform| entry.platform == platform)
+            && config.is_none_or(|config| entry.config == config)
+    })
+}
@@ -0,0 +1,134 @@
+const BOM: char = '\u{feff}';
+
+/// The section name of a `[Section]` header line.
+fn section_name(line: &str) -> Option<&str> {
+    line.trim().strip_prefix('[')?.strip_suffix(']')
+}
+
+/// The key of a plain `Key=Value` line. Comments and array operations
+/// (`+Key=`, `-Key=`, `.Key=`, `!Key=`) have none.
+fn key_of(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if line.starts_with([';', '#', '+', '-', '.', '!']) {
+        return None;
+    }
+    let (key, _) = line.split_once('=')?;
+    Some(key.trim())
+}
+
+/// Value of `key` in `[section]`, without surrounding quotes. Section and
+/// key names are case-insensitive, as in Unreal.
+pub fn get_value(content: &str, section: &str, key: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in content.trim_start_matches(BOM).lines() {
+        if let Some(name) = section_name(line) {
+            in_section = name.eq_ignore_ascii_case(section);
+        } else if in_section && key_of(line).is_some_and(|name| name.eq_ignore_ascii_case(key)) {
+            let (_, value) = line.split_once('=')?;
+            let value = value.trim();
+            let unquoted = value.strip_prefix('"').and_then(|value| value.strip_suffix('"'));
+            return Some(unquoted.unwrap_or(value).to_owned());
+        }
+    }
+    None
+}
+
+/// `content` with `key=value` in `[section]`, leaving every other line
+/// untouched: the first assignment of `key` is replaced, otherwise the key
+/// is added at the end of the section, or a new section is appended. Line
+/// endings and a byte order mark are kept.
+pub fn set_value(content: &str, section: &str, key: &str, value: &str) -> String {
+    let (bom, body) = match content.strip_prefix(BOM) {
+        Some(body) => (BOM.to_string(), body),
+        None => (String::new(), content),
+    };
+    let newline = if body.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut lines: Vec<String> = body.lines().map(str::to_owned).collect();
+
+    let mut in_section = false;
+    let mut insert_at = None;
+    let mut replaced = false;
+    for (index, line) in lines.iter_mut().enumerate() {
+        if let Some(name) = section_name(line) {
+            if in_section {
+                break;
+            }
+            in_section = name.eq_ignore_ascii_case(section);
+            if in_section {
+                insert_at = Some(index + 1);
+            }
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+        if let Some(name) = key_of(line).filter(|name| name.eq_ignore_ascii_case(key)) {
+            *line = format!("{}={}", name, value);
+            replaced = true;
+            break;
+        }
+        if !line.trim().is_empty() {
+            insert_at = Some(index + 1);
+        }
+    }
+
+    if !replaced {
+        match insert_at {
+            Some(index) => lines.insert(index, format!("{}={}", key, value)),
+            None => {
+                if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                    lines.push(String::new());
+                }
+                lines.push(format!("[{}]", section));
+                lines.push(format!("{}={}", key, value));
+            }
+        }
+    }
+
+    let mut result = bom + &lines.join(newline);
+    if body.is_empty() || body.ends_with('\n') {
+        result.push_str(newline);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS: &str = "/Script/EngineSettings.GeneralProjectSettings";
+
+    #[test]
+    fn edits_values_in_place() {
+        let content = "\u{feff}[/Script/EngineSettings.GeneralProjectSettings]\r\n\
+            ProjectID=ABC\r\n\
+            ; ProjectVersion=0.0.1\r\n\
+            +ProjectVersion=ignored\r\n\
+            projectversion = \"1.0.0\"\r\n\
+            \r\n\
+            [/Script/UnrealEd.ProjectPackagingSettings]\r\n\
+            BuildConfiguration=PPBC_Development\r\n";
+        assert_eq!(get_value(content, SETTINGS, "ProjectVersion").as_deref(), Some("1.0.0"));
+
+        let stamped = set_value(content, SETTINGS, "ProjectVersion", "1.2.3");
+        assert_eq!(stamped, content.replace("projectversion = \"1.0.0\"", "projectversion=1.2.3"));
+
+        let added = set_value(&stamped, SETTINGS, "BuildVersion", "42");
+        assert!(added.contains("projectversion=1.2.3\r\nBuildVersion=42\r\n\r\n[/Script/UnrealEd"));
+        assert!(added.starts_with('\u{feff}'));
+        assert_eq!(get_value(&added, "/script/enginesettings.generalprojectsettings", "buildversion").as_deref(), Some("42"));
+    }
+
+    #[test]
+    fn appends_missing_sections() {
+        let content = "[/Script/Engine.Engine]\nGameViewportClientClassName=Foo\n";
+        let stamped = set_value(content, SETTINGS, "ProjectVersion", "2.0");
+        assert_eq!(
+            stamped,
+            "[/Script/Engine.Engine]\nGameViewportClientClassName=Foo\n\n\
+             [/Script/EngineSettings.GeneralProjectSettings]\nProjectVersion=2.0\n"
+        );
+        assert_eq!(get_value(content, SETTINGS, "ProjectVersion"), None);
+        assert_eq!(set_value("", SETTINGS, "ProjectVersion", "2.0"), format!("[{}]\nProjectVersion=2.0\n", SETTINGS));
+    }
+}
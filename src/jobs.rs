@@ -0,0 +1,814 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::commands::{
+    build_script, create_build_command, create_clean_command, create_generate_project_files_command,
+    create_package_command, create_plugin_package_command, generate_project_files_script, kill_process_tree,
+    uat_script, BuildMode, ProgressUpdate,
+};
+use crate::disk::{existing_ancestor, format_size, free_space, MIN_FREE_SPACE};
+use crate::output::PackageOutput;
+use crate::platform::Platform;
+use crate::post_actions::after_package;
+use crate::provenance::PackageRun;
+use crate::storage::{Engine, OutputSettings, PostAction, Profile, Project, VersionStamp};
+use crate::version_stamp::{stamp_package, StampedConfig};
+
+/// How often the free space is checked while a job runs.
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+/// Updates kept in memory per job; older ones are only in its log file.
+const MAX_EVENTS: usize = 10_000;
+
+/// What a job runs.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JobKind {
+    Build,
+    Package,
+    Clean,
+    /// Packages the project-local plugin described by this .uplugin file.
+    PackagePlugin(PathBuf),
+    GenerateProjectFiles,
+}
+
+impl JobKind {
+    /// Name used by the command line and the API.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::Build => "build",
+            JobKind::Package => "package",
+            JobKind::Clean => "clean",
+            JobKind::PackagePlugin(_) => "package_plugin",
+            JobKind::GenerateProjectFiles => "generate_project_files",
+        }
+    }
+
+    /// Parses the kinds that can be requested by name.
+    pub fn from_name(name: &str) -> Option<JobKind> {
+        match name {
+            "build" => Some(JobKind::Build),
+            "package" => Some(JobKind::Package),
+            "clean" => Some(JobKind::Clean),
+            _ => None,
+        }
+    }
+}
+
+/// Which target of the project is built.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub enum BuildTarget {
+    #[default]
+    Game,
+    Editor,
+    Client,
+    Server,
+}
+
+impl BuildTarget {
+    pub const ALL: [BuildTarget; 4] = [BuildTarget::Game, BuildTarget::Editor, BuildTarget::Client, BuildTarget::Server];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BuildTarget::Game => "Game",
+            BuildTarget::Editor => "Editor",
+            BuildTarget::Client => "Client",
+            BuildTarget::Server => "Server",
+        }
+    }
+
+    /// UnrealBuildTool target name for `project`, e.g. "MyGameEditor".
+    pub fn target_name(self, project: &str) -> String {
+        match self {
+            BuildTarget::Game => project.to_owned(),
+            target => format!("{}{}", project, target.as_str()),
+        }
+    }
+}
+
+/// Everything needed to launch a job.
+#[derive(Clone, Debug)]
+pub struct JobSpec {
+    pub kind: JobKind,
+    pub project: String,
+    /// Target built by `Build` and `Clean` jobs.
+    pub target: BuildTarget,
+    pub uproject: PathBuf,
+    /// Engine solution (`UE5.sln`).
+    pub engine: PathBuf,
+    pub platform: Platform,
+    pub mode: BuildMode,
+    /// Output settings of `Package` jobs, expanded into `output` when the
+    /// job starts.
+    pub output_settings: Option<OutputSettings>,
+    /// Staging and archive directories of `Package` jobs. Until the job
+    /// starts, a preview with `{changelist}` unexpanded and the date and
+    /// time of when it was queued.
+    pub output: Option<PackageOutput>,
+    /// Run after a successful `Package` job.
+    pub post_actions: Vec<PostAction>,
+    /// Written to `DefaultGame.ini` before a `Package` job runs.
+    pub version_stamp: Option<VersionStamp>,
+}
+
+impl JobSpec {
+    pub fn new(kind: JobKind, project: &Project, engine: &Engine, platform: Platform, mode: BuildMode) -> Self {
+        let mut spec = Self {
+            kind,
+            project: project.name.clone(),
+            target: BuildTarget::Game,
+            uproject: project.location.clone(),
+            engine: engine.location.clone(),
+            platform,
+            mode,
+            output_settings: None,
+            output: None,
+            post_actions: Vec::new(),
+            version_stamp: None,
+        };
+        spec.set_output(&project.output, project);
+        spec.set_post_actions(&project.post_actions);
+        spec
+    }
+
+    /// Sets the staging and archive settings of a package (the project's,
+    /// or a profile's) and previews its directories.
+    pub fn set_output(&mut self, settings: &OutputSettings, project: &Project) {
+        self.output_settings = (self.kind == JobKind::Package).then(|| settings.clone());
+        self.output = self
+            .output_settings
+            .as_ref()
+            .map(|settings| PackageOutput::preview(settings, project, self.platform, self.mode));
+    }
+
+    /// Expands the output settings of a package that is starting: its
+    /// `{date}`, `{time}` and `{changelist}`.
+    pub fn resolve_output(&mut self) {
+        if let Some(settings) = &self.output_settings {
+            let output = PackageOutput::resolve(
+                settings,
+                &self.project,
+                &self.uproject,
+                self.platform,
+                self.mode,
+                SystemTime::now(),
+            );
+            self.output = Some(output);
+        }
+    }
+
+    /// Sets what is done after a successful package (the project's, or a
+    /// profile's). Other jobs have no post actions.
+    pub fn set_post_actions(&mut self, actions: &[PostAction]) {
+        self.post_actions = if self.kind == JobKind::Package { actions.to_vec() } else { Vec::new() };
+    }
+
+    /// Applies what `profile` sets: its output settings, post actions and
+    /// version stamp (packages only).
+    pub fn apply_profile(&mut self, profile: &Profile, project: &Project) {
+        if let Some(output) = &profile.output {
+            self.set_output(output, project);
+        }
+        if let Some(actions) = &profile.post_actions {
+            self.set_post_actions(actions);
+        }
+        self.version_stamp = profile.version.clone().filter(|_| self.kind == JobKind::Package);
+    }
+
+    /// Short description shown in the job list.
+    pub fn label(&self) -> String {
+        match &self.kind {
+            JobKind::Build => format!("Build {} {} {}", self.target_name(), self.platform, self.mode),
+            JobKind::Package => format!("Package {} {} {}", self.project, self.platform, self.mode),
+            JobKind::Clean => format!("Clean {} {} {}", self.target_name(), self.platform, self.mode),
+            JobKind::PackagePlugin(uplugin) => format!(
+                "Package plugin {} {}",
+                uplugin.file_stem().unwrap_or_default().to_string_lossy(),
+                self.platform
+            ),
+            JobKind::GenerateProjectFiles => format!("Generate project files of {}", self.project),
+        }
+    }
+
+    /// UnrealBuildTool target name, e.g. "MyGameEditor".
+    pub fn target_name(&self) -> String {
+        self.target.target_name(&self.project)
+    }
+
+    /// Engine script the job runs.
+    pub fn script(&self) -> PathBuf {
+        match self.kind {
+            JobKind::Build | JobKind::Clean => build_script(&self.engine),
+            JobKind::Package | JobKind::PackagePlugin(_) => uat_script(&self.engine),
+            JobKind::GenerateProjectFiles => generate_project_files_script(&self.engine),
+        }
+    }
+
+    /// Directory the job writes its output to: the staging directory of
+    /// packages, `Builds/Plugins/<name>/UE_<version>` for packaged plugins and
+    /// the project folder (Binaries, Intermediate) otherwise.
+    pub fn output_dir(&self) -> PathBuf {
+        let project_dir = self.uproject.parent().map(Path::to_path_buf).unwrap_or_default();
+        match &self.kind {
+            JobKind::Package => match &self.output {
+                Some(output) => output.staging_dir.clone(),
+                None => project_dir.join("Builds"),
+            },
+            JobKind::PackagePlugin(uplugin) => {
+                let engine_version = Engine { location: self.engine.clone() }
+                    .build_version()
+                    .map(|version| version.major_minor())
+                    .unwrap_or_else(|_| "Unknown".to_owned());
+                let plugin_name = uplugin.file_stem().unwrap_or_default().to_string_lossy();
+                project_dir
+                    .join("Builds")
+                    .join("Plugins")
+                    .join(plugin_name.as_ref())
+                    .join(format!("UE_{}", engine_version))
+            }
+            JobKind::Build | JobKind::Clean | JobKind::GenerateProjectFiles => project_dir,
+        }
+    }
+
+    /// Starts the process and returns its progress updates.
+    pub fn launch(&self) -> Receiver<ProgressUpdate> {
+        let platform = self.platform.name();
+        let mode = self.mode.as_str();
+        let target = self.target_name();
+        match &self.kind {
+            JobKind::Build => create_build_command(&self.engine, &target, platform, mode, &self.uproject),
+            JobKind::Package => create_package_command(
+                &self.engine,
+                platform,
+                mode,
+                &self.uproject,
+                &self.output_dir(),
+                self.output.as_ref().and_then(|output| output.archive_dir.as_deref()),
+            ),
+            JobKind::Clean => create_clean_command(&self.engine, &target, platform, mode, &self.uproject),
+            JobKind::PackagePlugin(uplugin) => {
+                create_plugin_package_command(&self.engine, uplugin, &self.output_dir(), &[platform])
+            }
+            JobKind::GenerateProjectFiles => create_generate_project_files_command(&self.engine, &self.uproject),
+        }
+    }
+}
+
+/// Where a job is in its lifecycle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// A queued, running or finished job.
+pub struct Job {
+    pub id: u64,
+    pub spec: JobSpec,
+    pub status: JobStatus,
+    /// Last reported progress (0.0 to 1.0).
+    pub progress: f32,
+    /// Last stage or result message.
+    pub message: String,
+    /// The last `MAX_EVENTS` updates received from the process.
+    pub events: VecDeque<ProgressUpdate>,
+    /// Updates received in all, including those no longer kept.
+    event_count: usize,
+    /// File the output is written to, once the job started.
+    pub log_file: Option<PathBuf>,
+    /// Program and arguments of every process the job started, in order.
+    pub commands: Vec<Vec<String>>,
+    /// Size report written by a finished package.
+    pub size_report: Option<PathBuf>,
+    started: Option<Instant>,
+    /// Run time of a finished job.
+    pub duration: Option<Duration>,
+    /// Id of the job's process, once started.
+    pid: Option<u32>,
+    /// Why the job was stopped, if it was.
+    abort_reason: Option<String>,
+}
+
+impl Job {
+    /// The last lines of process output received so far.
+    pub fn log_lines(&self) -> impl Iterator<Item = &str> {
+        self.events.iter().filter_map(|update| match update {
+            ProgressUpdate::Log(line) => Some(line.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Time spent running so far, or in total once finished.
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.duration.or_else(|| self.started.map(|started| started.elapsed()))
+    }
+
+    pub fn summary(&self) -> Value {
+        json!({
+            "id": self.id,
+            "kind": self.spec.kind.as_str(),
+            "label": self.spec.label(),
+            "project": self.spec.project,
+            "target": self.spec.target_name(),
+            "platform": self.spec.platform,
+            "config": self.spec.mode,
+            "status": self.status,
+            "progress": self.progress,
+            "message": self.message,
+            "events": self.event_count,
+            "log_file": self.log_file,
+            "output_dir": self.spec.output_dir(),
+            "archive_dir": self.spec.output.as_ref().and_then(|output| output.archive_dir.clone()),
+            "size_report": self.size_report,
+        })
+    }
+
+    fn apply(&mut self, update: ProgressUpdate) {
+        match &update {
+            ProgressUpdate::Progress(progress) => self.progress = *progress,
+            ProgressUpdate::Stage(message) | ProgressUpdate::Finished(message) | ProgressUpdate::Failed(message) => {
+                self.message = message.clone()
+            }
+            ProgressUpdate::Process(pid) => self.pid = Some(*pid),
+            ProgressUpdate::Command(command) => self.commands.push(command.clone()),
+            ProgressUpdate::SizeReport(path) => self.size_report = Some(path.clone()),
+            ProgressUpdate::Log(_) => {}
+        }
+        if self.events.len() == MAX_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(update);
+        self.event_count += 1;
+    }
+
+    /// Whether the last `Finished` or `Failed` update is a success and the
+    /// job was not aborted.
+    fn succeeded(&self) -> bool {
+        let outcome = self
+            .events
+            .iter()
+            .rev()
+            .find(|update| matches!(update, ProgressUpdate::Finished(_) | ProgressUpdate::Failed(_)));
+        matches!(outcome, Some(ProgressUpdate::Finished(_))) && self.abort_reason.is_none()
+    }
+
+    /// Marks the job finished once its process exited and its post actions
+    /// ran.
+    fn finish(&mut self) {
+        self.status = if self.succeeded() { JobStatus::Succeeded } else { JobStatus::Failed };
+        if let Some(reason) = &self.abort_reason {
+            self.message = reason.clone();
+        }
+        if self.status == JobStatus::Succeeded {
+            self.progress = 1.0;
+        }
+        self.duration = self.elapsed();
+    }
+}
+
+struct QueueState {
+    jobs: Vec<Job>,
+    next_id: u64,
+    max_parallel: usize,
+}
+
+/// Runs jobs in order, up to `max_parallel` at once. Two jobs never run
+/// at the same time on the same engine, since UnrealBuildTool holds a
+/// per-engine mutex. Running jobs are aborted if the free space where they
+/// write drops below `MIN_FREE_SPACE`. Shared by the GUI and the API server.
+pub struct JobQueue {
+    state: Mutex<QueueState>,
+    /// Notified whenever a job changes.
+    changed: Condvar,
+    /// Directory receiving one log file per job (in `logs/`) and the package
+    /// history. Nothing is written when `None`.
+    data_dir: Option<PathBuf>,
+}
+
+impl JobQueue {
+    pub fn new(max_parallel: usize, data_dir: Option<PathBuf>) -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(QueueState {
+                jobs: Vec::new(),
+                next_id: 1,
+                max_parallel: max_parallel.max(1),
+            }),
+            changed: Condvar::new(),
+            data_dir,
+        })
+    }
+
+    /// Adds a job at the end of the queue and starts it if a slot is free.
+    pub fn enqueue(self: &Arc<Self>, spec: JobSpec) -> u64 {
+        let id = {
+            let mut state = self.state.lock().unwrap();
+            let id = state.next_id;
+            state.next_id += 1;
+            println!("Queued job {}: {}", id, spec.label());
+            state.jobs.push(Job {
+                id,
+                spec,
+                status: JobStatus::Queued,
+                progress: 0.0,
+                message: "Queued".to_owned(),
+                events: VecDeque::new(),
+                event_count: 0,
+                log_file: None,
+                commands: Vec::new(),
+                size_report: None,
+                started: None,
+                duration: None,
+                pid: None,
+                abort_reason: None,
+            });
+            id
+        };
+        self.schedule();
+        id
+    }
+
+    /// Removes a job that is not running.
+    pub fn remove(&self, id: u64) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let Some(index) = state.jobs.iter().position(|job| job.id == id && job.status != JobStatus::Running) else {
+            return false;
+        };
+        state.jobs.remove(index);
+        self.changed.notify_all();
+        true
+    }
+
+    /// Swaps a queued job with the previous (`up`) or next queued job.
+    pub fn move_job(&self, id: u64, up: bool) {
+        let mut state = self.state.lock().unwrap();
+        let queued: Vec<usize> = (0..state.jobs.len())
+            .filter(|&index| state.jobs[index].status == JobStatus::Queued)
+            .collect();
+        let Some(position) = queued.iter().position(|&index| state.jobs[index].id == id) else {
+            return;
+        };
+        let other = if up { position.checked_sub(1) } else { Some(position + 1) };
+        if let Some(&other) = other.and_then(|other| queued.get(other)) {
+            state.jobs.swap(queued[position], other);
+            self.changed.notify_all();
+        }
+    }
+
+    /// Removes every finished job.
+    pub fn clear_finished(&self) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .jobs
+            .retain(|job| matches!(job.status, JobStatus::Queued | JobStatus::Running));
+        self.changed.notify_all();
+    }
+
+    pub fn set_max_parallel(self: &Arc<Self>, max_parallel: usize) {
+        self.state.lock().unwrap().max_parallel = max_parallel.max(1);
+        self.schedule();
+    }
+
+    /// Calls `f` with the jobs, in queue order.
+    pub fn with_jobs<R>(&self, f: impl FnOnce(&[Job]) -> R) -> R {
+        f(&self.state.lock().unwrap().jobs)
+    }
+
+    pub fn summary(&self, id: u64) -> Option<Value> {
+        self.with_jobs(|jobs| jobs.iter().find(|job| job.id == id).map(Job::summary))
+    }
+
+    /// Waits up to `timeout` for job `id` to produce updates past the first
+    /// `seen` ones, or to finish. Returns the new updates still kept, the
+    /// number of updates seen after them and whether the job has finished,
+    /// or `None` if there is no such job.
+    pub fn wait_for_events(
+        &self,
+        id: u64,
+        seen: usize,
+        timeout: Duration,
+    ) -> Option<(Vec<ProgressUpdate>, usize, bool)> {
+        let state = self.state.lock().unwrap();
+        let (state, _) = self
+            .changed
+            .wait_timeout_while(state, timeout, |state| {
+                state.jobs.iter().any(|job| {
+                    job.id == id
+                        && job.event_count <= seen
+                        && matches!(job.status, JobStatus::Queued | JobStatus::Running)
+                })
+            })
+            .unwrap();
+        let job = state.jobs.iter().find(|job| job.id == id)?;
+        let finished = matches!(job.status, JobStatus::Succeeded | JobStatus::Failed);
+        let first_kept = job.event_count - job.events.len();
+        let events = job.events.iter().skip(seen.saturating_sub(first_kept)).cloned().collect();
+        Some((events, job.event_count, finished))
+    }
+
+    /// Starts queued jobs while slots are free, skipping jobs whose engine
+    /// is busy.
+    fn schedule(self: &Arc<Self>) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            let busy_engines: Vec<PathBuf> = state
+                .jobs
+                .iter()
+                .filter(|job| job.status == JobStatus::Running)
+                .map(|job| job.spec.engine.clone())
+                .collect();
+            if busy_engines.len() >= state.max_parallel {
+                break;
+            }
+            let Some(job) = state
+                .jobs
+                .iter_mut()
+                .find(|job| job.status == JobStatus::Queued && !busy_engines.contains(&job.spec.engine))
+            else {
+                break;
+            };
+            job.status = JobStatus::Running;
+            job.message = "Started".to_owned();
+            job.started = Some(Instant::now());
+            let log = self
+                .data_dir
+                .as_deref()
+                .and_then(|dir| open_log_file(&dir.join("logs"), job));
+            job.log_file = log.as_ref().map(|(path, _)| path.clone());
+            println!("Starting job {}: {}", job.id, job.spec.label());
+
+            let queue = Arc::clone(self);
+            let id = job.id;
+            let spec = job.spec.clone();
+            std::thread::spawn(move || queue.pump(id, spec, log.map(|(_, file)| file)));
+        }
+        self.changed.notify_all();
+    }
+
+    /// Resolves the output directories of a package, stamps its version,
+    /// launches the job and records its updates, restores the stamped
+    /// config and, for a successful package, records those of
+    /// `after_package`, then frees the slot. A failing post action fails
+    /// the job.
+    fn pump(self: Arc<Self>, id: u64, mut spec: JobSpec, mut log: Option<File>) {
+        let started = SystemTime::now();
+        spec.resolve_output();
+        self.update(id, |job| job.spec.output = spec.output.clone());
+        let queue = Arc::clone(&self);
+        let output_dir = spec.output_dir();
+        std::thread::spawn(move || queue.watch_disk_space(id, output_dir));
+
+        let mut record = |update: ProgressUpdate| {
+            if let Some(file) = log.as_mut() {
+                let line = match &update {
+                    ProgressUpdate::Progress(_)
+                    | ProgressUpdate::Process(_)
+                    | ProgressUpdate::Command(_)
+                    | ProgressUpdate::SizeReport(_) => None,
+                    ProgressUpdate::Log(line) => Some(line),
+                    ProgressUpdate::Stage(message) | ProgressUpdate::Finished(message) | ProgressUpdate::Failed(message) => {
+                        Some(message)
+                    }
+                };
+                if let Some(line) = line {
+                    let _ = writeln!(file, "{}", line);
+                }
+            }
+            self.update(id, |job| job.apply(update));
+        };
+        match stamp_package(&spec, self.data_dir.as_deref(), &mut record) {
+            Ok(stamped) => {
+                for update in spec.launch() {
+                    record(update);
+                }
+                // The process has exited; only post actions may start new ones.
+                self.update(id, |job| job.pid = None);
+                let project_version = stamped.as_ref().and_then(StampedConfig::project_version);
+                if let Some(stamped) = stamped {
+                    stamped.restore(&mut record);
+                }
+                let succeeded = self.with_jobs(|jobs| jobs.iter().any(|job| job.id == id && job.succeeded()));
+                if spec.kind == JobKind::Package && succeeded {
+                    let run = PackageRun {
+                        started,
+                        commands: self.with_jobs(|jobs| {
+                            jobs.iter().find(|job| job.id == id).map(|job| job.commands.clone()).unwrap_or_default()
+                        }),
+                        project_version,
+                    };
+                    after_package(&spec, self.data_dir.as_deref(), run, &mut record);
+                }
+            }
+            Err(e) => record(ProgressUpdate::Failed(format!("Failed to stamp the project version: {}", e))),
+        }
+        self.update(id, Job::finish);
+        self.with_jobs(|jobs| {
+            if let Some(job) = jobs.iter().find(|job| job.id == id) {
+                println!("Job {} {:?}: {}", id, job.status, job.message);
+            }
+        });
+        self.schedule();
+    }
+
+    /// Stops a running job, reporting `reason` as its result.
+    pub fn abort(&self, id: u64, reason: &str) {
+        let pid = {
+            let mut state = self.state.lock().unwrap();
+            let Some(job) = state.jobs.iter_mut().find(|job| job.id == id && job.status == JobStatus::Running) else {
+                return;
+            };
+            eprintln!("Aborting job {}: {}", id, reason);
+            job.abort_reason = Some(reason.to_owned());
+            job.apply(ProgressUpdate::Failed(reason.to_owned()));
+            self.changed.notify_all();
+            job.pid
+        };
+        if let Some(pid) = pid {
+            if let Err(e) = kill_process_tree(pid) {
+                eprintln!("Failed to stop job {}: {}", id, e);
+            }
+        }
+    }
+
+    /// Checks the free space where job `id` writes every
+    /// `DISK_CHECK_INTERVAL` while it runs, and aborts the job if it drops
+    /// below `MIN_FREE_SPACE`.
+    fn watch_disk_space(&self, id: u64, output_dir: PathBuf) {
+        let is_running = |state: &mut QueueState| {
+            state.jobs.iter().any(|job| job.id == id && job.status == JobStatus::Running)
+        };
+        loop {
+            let state = self.state.lock().unwrap();
+            let (mut state, _) = self
+                .changed
+                .wait_timeout_while(state, DISK_CHECK_INTERVAL, |state| is_running(state))
+                .unwrap();
+            if !is_running(&mut state) {
+                return;
+            }
+            drop(state);
+            let Some(free) = free_space(&output_dir) else {
+                continue;
+            };
+            if free < MIN_FREE_SPACE {
+                let volume = existing_ancestor(&output_dir).unwrap_or(&output_dir);
+                let reason = format!("Aborted: only {} left in {}", format_size(free), volume.display());
+                self.abort(id, &reason);
+                return;
+            }
+        }
+    }
+
+    fn update(&self, id: u64, change: impl FnOnce(&mut Job)) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(job) = state.jobs.iter_mut().find(|job| job.id == id) {
+            change(job);
+        }
+        self.changed.notify_all();
+    }
+}
+
+/// Creates `<log_dir>/<timestamp>-<id>.log` for a starting job.
+fn open_log_file(log_dir: &Path, job: &Job) -> Option<(PathBuf, File)> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let path = log_dir.join(format!("{}-{}.log", timestamp, job.id));
+    let result = fs::create_dir_all(log_dir).and_then(|_| File::create(&path));
+    match result {
+        Ok(mut file) => {
+            let _ = writeln!(file, "{}", job.spec.label());
+            Some((path, file))
+        }
+        Err(e) => {
+            eprintln!("Failed to create log file {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::test_support::fixture;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// A build that runs until `release` exists in the project folder.
+    const WAITING_SCRIPT: &str = "#!/bin/sh\nwhile [ ! -e release ]; do sleep 0.02; done\necho \"BUILD SUCCESSFUL\"\n";
+
+    /// Makes `engine`'s build script run until `release` exists in the
+    /// project folder. Scripts are written before any job starts, so no
+    /// child process inherits a script still open for writing.
+    fn install_waiting_script(project: &Project, engine: &Engine) {
+        let script = build_job(project, engine).script();
+        fs::create_dir_all(script.parent().unwrap()).unwrap();
+        fs::write(&script, WAITING_SCRIPT).unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    fn build_job(project: &Project, engine: &Engine) -> JobSpec {
+        JobSpec::new(JobKind::Build, project, engine, Platform::Linux, BuildMode::Development)
+    }
+
+    fn statuses(queue: &JobQueue) -> Vec<(u64, JobStatus)> {
+        queue.with_jobs(|jobs| jobs.iter().map(|job| (job.id, job.status)).collect())
+    }
+
+    /// Waits until every job is finished.
+    fn wait_until_finished(queue: &JobQueue) {
+        let deadline = Instant::now() + Duration::from_secs(20);
+        while statuses(queue).iter().any(|(_, status)| matches!(status, JobStatus::Queued | JobStatus::Running)) {
+            assert!(Instant::now() < deadline, "jobs did not finish: {:?}", statuses(queue));
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn runs_one_job_per_engine_up_to_the_limit() {
+        let (root, project, engine) = fixture("queue");
+        let other_engine = Engine { location: root.join("Other").join("UE5.sln") };
+        let third_engine = Engine { location: root.join("Third").join("UE5.sln") };
+        for engine in [&engine, &other_engine, &third_engine] {
+            install_waiting_script(&project, engine);
+        }
+        let queue = JobQueue::new(2, None);
+
+        let first = queue.enqueue(build_job(&project, &engine));
+        let same_engine = queue.enqueue(build_job(&project, &engine));
+        let other = queue.enqueue(build_job(&project, &other_engine));
+        let over_limit = queue.enqueue(build_job(&project, &third_engine));
+        assert_eq!(
+            statuses(&queue),
+            [
+                (first, JobStatus::Running),
+                (same_engine, JobStatus::Queued),
+                (other, JobStatus::Running),
+                (over_limit, JobStatus::Queued),
+            ]
+        );
+
+        fs::write(root.join("Game").join("release"), "").unwrap();
+        wait_until_finished(&queue);
+        assert!(statuses(&queue).iter().all(|(_, status)| *status == JobStatus::Succeeded), "{:?}", statuses(&queue));
+    }
+
+    #[test]
+    fn reorders_and_removes_only_pending_jobs() {
+        let (root, project, engine) = fixture("queue");
+        install_waiting_script(&project, &engine);
+        let queue = JobQueue::new(1, None);
+
+        let running = queue.enqueue(build_job(&project, &engine));
+        let second = queue.enqueue(build_job(&project, &engine));
+        let third = queue.enqueue(build_job(&project, &engine));
+        let order = |queue: &JobQueue| statuses(queue).into_iter().map(|(id, _)| id).collect::<Vec<_>>();
+
+        queue.move_job(third, true);
+        assert_eq!(order(&queue), [running, third, second]);
+        // The running job keeps its place, and the first pending job cannot move up past it.
+        queue.move_job(third, true);
+        queue.move_job(running, false);
+        assert_eq!(order(&queue), [running, third, second]);
+
+        assert!(!queue.remove(running));
+        assert!(queue.remove(second));
+        assert_eq!(order(&queue), [running, third]);
+
+        fs::write(root.join("Game").join("release"), "").unwrap();
+        wait_until_finished(&queue);
+        assert_eq!(statuses(&queue), [(running, JobStatus::Succeeded), (third, JobStatus::Succeeded)]);
+    }
+
+    #[test]
+    fn keeps_the_latest_events() {
+        let (_root, project, engine) = fixture("queue");
+        let mut job = Job {
+            id: 1,
+            spec: build_job(&project, &engine),
+            status: JobStatus::Running,
+            progress: 0.0,
+            message: String::new(),
+            events: VecDeque::new(),
+            event_count: 0,
+            log_file: None,
+            commands: Vec::new(),
+            size_report: None,
+            started: None,
+            duration: None,
+            pid: None,
+            abort_reason: None,
+        };
+        for line in 0..MAX_EVENTS + 5 {
+            job.apply(ProgressUpdate::Log(line.to_string()));
+        }
+        assert_eq!(job.events.len(), MAX_EVENTS);
+        assert_eq!(job.event_count, MAX_EVENTS + 5);
+        assert_eq!(job.log_lines().next(), Some("5"));
+        assert_eq!(job.summary()["events"], MAX_EVENTS + 5);
+    }
+}
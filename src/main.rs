@@ -1,8 +1,58 @@
 mod app;
+mod cli;
 mod storage;
+mod uproject;
+mod archive;
 mod commands;
+mod compatibility;
+mod disk;
+mod engine_association;
+mod history;
+mod ini;
+mod jobs;
+mod output;
+mod platform;
+mod post_actions;
+mod preflight;
+mod provenance;
+mod plugins;
+mod scanner;
+mod sdk;
+mod size_report;
+mod server;
+mod source_control;
+#[cfg(test)]
+mod test_support;
+mod version_stamp;
+mod watcher;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Removes `--config-dir <path>` or `--config-dir=<path>` from `args` and
+/// returns its value.
+fn take_config_dir_arg(args: &mut Vec<String>) -> Option<PathBuf> {
+    let index = args
+        .iter()
+        .position(|arg| arg == "--config-dir" || arg.starts_with("--config-dir="))?;
+    let arg = args.remove(index);
+    match arg.strip_prefix("--config-dir=") {
+        Some(value) => Some(PathBuf::from(value)),
+        None if index < args.len() => Some(PathBuf::from(args.remove(index))),
+        None => None,
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = storage::init_config_dir(take_config_dir_arg(&mut args)) {
+        eprintln!("Failed to initialize configuration directory: {}", e);
+    }
+
+    if let Some(code) = cli::run(&args) {
+        return code;
+    }
 
-fn main() {
     let options = eframe::NativeOptions::default();
     if let Err(e) = eframe::run_native(
         "Build Tool GUI",
@@ -10,5 +60,7 @@ fn main() {
         Box::new(|_cc| Ok(Box::new(app::BuildApp::default()))),
     ) {
         eprintln!("Error running the application: {}", e);
+        return ExitCode::FAILURE;
     }
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,397 @@
+use regex::{Captures, Regex};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::commands::BuildMode;
+use crate::platform::Platform;
+use crate::source_control::detect_revision;
+use crate::storage::{OutputSettings, Project};
+
+/// Value of `{changelist}` when no revision can be detected.
+pub const UNKNOWN_REVISION: &str = "unknown";
+
+/// A calendar date and time in UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UtcDateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl UtcDateTime {
+    pub fn from_system_time(time: SystemTime) -> Self {
+        let seconds = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+        let (days, time_of_day) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+        // Days since 1970-01-01 to a civil date (Howard Hinnant's algorithm).
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 } as u32;
+        Self {
+            year: year_of_era + era * 400 + i64::from(month <= 2),
+            month,
+            day,
+            hour: (time_of_day / 3600) as u32,
+            minute: (time_of_day % 3600 / 60) as u32,
+            second: (time_of_day % 60) as u32,
+        }
+    }
+
+    /// `2026-10-18`
+    pub fn date(&self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    /// `143005`
+    pub fn time(&self) -> String {
+        format!("{:02}{:02}{:02}", self.hour, self.minute, self.second)
+    }
+
+    /// `2026-10-18T14:30:05Z`
+    pub fn iso8601(&self) -> String {
+        format!("{}T{:02}:{:02}:{:02}Z", self.date(), self.hour, self.minute, self.second)
+    }
+}
+
+/// Values substituted into output folder templates.
+pub struct TemplateValues {
+    pub project: String,
+    pub platform: String,
+    pub config: String,
+    pub date: String,
+    pub time: String,
+    pub changelist: String,
+}
+
+impl TemplateValues {
+    fn get(&self, name: &str) -> Option<&str> {
+        match name {
+            "project" => Some(&self.project),
+            "platform" => Some(&self.platform),
+            "config" => Some(&self.config),
+            "date" => Some(&self.date),
+            "time" => Some(&self.time),
+            "changelist" => Some(&self.changelist),
+            _ => None,
+        }
+    }
+}
+
+fn placeholder_regex() -> Regex {
+    Regex::new(r"\{(\w+)\}").unwrap()
+}
+
+/// Replaces the `{name}` placeholders of `template`. Unknown placeholders
+/// are kept as they are.
+pub fn expand_template(template: &str, values: &TemplateValues) -> String {
+    placeholder_regex()
+        .replace_all(template, |caps: &Captures| match values.get(&caps[1]) {
+            Some(value) => folder_name(value),
+            None => caps[0].to_owned(),
+        })
+        .into_owned()
+}
+
+/// `value` with the characters that are not allowed in folder names replaced.
+fn folder_name(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '_' } else { c })
+        .collect()
+}
+
+/// `root` followed by the folders of the expanded `template`.
+fn join_template(root: &Path, template: &str, values: &TemplateValues) -> PathBuf {
+    let mut dir = root.to_path_buf();
+    for part in expand_template(template, values)
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+    {
+        dir.push(part);
+    }
+    dir
+}
+
+/// Written into each archive folder once the package is archived. Only
+/// folders holding it are pruned, so nothing the tool did not create is
+/// removed.
+pub const ARCHIVE_MARKER: &str = ".ubt-archive";
+
+/// Regex matching the names the last folder of `template` takes across
+/// runs: the project, platform and configuration stay fixed while the
+/// date, time and changelist vary. `None` unless the folder contains
+/// `{date}` or `{time}`, since archives could not be told apart otherwise.
+fn archive_name_pattern(template: &str, values: &TemplateValues) -> Option<String> {
+    let leaf = template.trim_end_matches(['/', '\\']).rsplit(['/', '\\']).next()?;
+    if !leaf.contains("{date}") && !leaf.contains("{time}") {
+        return None;
+    }
+    let mut pattern = String::from("^");
+    let mut last = 0;
+    for caps in placeholder_regex().captures_iter(leaf) {
+        let placeholder = caps.get(0).unwrap();
+        pattern.push_str(&regex::escape(&leaf[last..placeholder.start()]));
+        match &caps[1] {
+            "date" => pattern.push_str(r"\d{4}-\d{2}-\d{2}"),
+            "time" => pattern.push_str(r"\d{6}"),
+            "changelist" => pattern.push_str(r"\w+"),
+            name => match values.get(name) {
+                Some(value) => pattern.push_str(&regex::escape(&folder_name(value))),
+                None => pattern.push_str(&regex::escape(placeholder.as_str())),
+            },
+        }
+        last = placeholder.end();
+    }
+    pattern.push_str(&regex::escape(&leaf[last..]));
+    pattern.push('$');
+    Some(pattern)
+}
+
+/// Staging and archive directories of one package job.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PackageOutput {
+    pub staging_dir: PathBuf,
+    /// Passed as `-archivedirectory`; the package is not archived when `None`.
+    pub archive_dir: Option<PathBuf>,
+    /// Archives kept next to `archive_dir`; 0 keeps them all.
+    pub keep_archives: usize,
+    /// Matches the names of the earlier archives counted by `keep_archives`.
+    archive_pattern: Option<String>,
+}
+
+impl PackageOutput {
+    /// Expands `settings` for a package of project `name` (`uproject`)
+    /// started at `now`. Asks source control for `{changelist}`, so this is
+    /// done when the package starts rather than when it is queued.
+    pub fn resolve(
+        settings: &OutputSettings,
+        name: &str,
+        uproject: &Path,
+        platform: Platform,
+        mode: BuildMode,
+        now: SystemTime,
+    ) -> Self {
+        Self::resolve_with(settings, name, uproject, platform, mode, now, detect_revision)
+    }
+
+    /// Like `resolve`, but leaves `{changelist}` unexpanded rather than
+    /// asking source control, for previews.
+    pub fn preview(settings: &OutputSettings, project: &Project, platform: Platform, mode: BuildMode) -> Self {
+        Self::resolve_with(
+            settings,
+            &project.name,
+            &project.location,
+            platform,
+            mode,
+            SystemTime::now(),
+            |_| Some("{changelist}".to_owned()),
+        )
+    }
+
+    fn resolve_with(
+        settings: &OutputSettings,
+        name: &str,
+        uproject: &Path,
+        platform: Platform,
+        mode: BuildMode,
+        now: SystemTime,
+        revision: impl FnOnce(&Path) -> Option<String>,
+    ) -> Self {
+        let project_dir = uproject.parent().map(Path::to_path_buf).unwrap_or_default();
+        let archive_template = settings.archive_root.as_ref().map(|_| settings.archive_template.as_str());
+        let wants_changelist = settings.staging_template.contains("{changelist}")
+            || archive_template.is_some_and(|template| template.contains("{changelist}"));
+        let changelist = wants_changelist.then(|| revision(&project_dir)).flatten();
+        let time = UtcDateTime::from_system_time(now);
+        let values = TemplateValues {
+            project: name.to_owned(),
+            platform: platform.name().to_owned(),
+            config: mode.as_str().to_owned(),
+            date: time.date(),
+            time: time.time(),
+            changelist: changelist.unwrap_or_else(|| UNKNOWN_REVISION.to_owned()),
+        };
+
+        let staging_root = match &settings.staging_root {
+            Some(root) => project_dir.join(root),
+            None => project_dir.join("Builds"),
+        };
+        Self {
+            staging_dir: join_template(&staging_root, &settings.staging_template, &values),
+            archive_dir: settings
+                .archive_root
+                .as_ref()
+                .map(|root| join_template(&project_dir.join(root), &settings.archive_template, &values)),
+            keep_archives: settings.keep_archives,
+            archive_pattern: archive_template.and_then(|template| archive_name_pattern(template, &values)),
+        }
+    }
+
+    /// Whether `prune_archives` can tell the archives apart.
+    pub fn can_prune(&self) -> bool {
+        self.archive_pattern.is_some()
+    }
+
+    /// Marks `archive_dir`, once the package was archived into it, as an
+    /// archive that `prune_archives` may remove later.
+    pub fn mark_archive(&self) -> std::io::Result<()> {
+        match &self.archive_dir {
+            Some(dir) if dir.is_dir() => fs::write(dir.join(ARCHIVE_MARKER), b""),
+            _ => Ok(()),
+        }
+    }
+
+    /// Removes the oldest archives next to `archive_dir` so that only the
+    /// newest `keep_archives` remain, and returns what was removed. Only
+    /// folders named after the archive template and marked by
+    /// `mark_archive` are considered. Fails if the last folder of the
+    /// archive template has neither `{date}` nor `{time}`.
+    pub fn prune_archives(&self) -> std::io::Result<Vec<PathBuf>> {
+        let Some(archive_dir) = self.archive_dir.as_ref().filter(|_| self.keep_archives > 0) else {
+            return Ok(Vec::new());
+        };
+        let Some(pattern) = &self.archive_pattern else {
+            return Err(std::io::Error::other(
+                "the last folder of the archive template needs {date} or {time} to tell archives apart",
+            ));
+        };
+        let Some(parent) = archive_dir.parent() else {
+            return Ok(Vec::new());
+        };
+        let pattern = Regex::new(pattern).map_err(std::io::Error::other)?;
+        let mut archives: Vec<(SystemTime, PathBuf)> = fs::read_dir(parent)?
+            .flatten()
+            .filter(|entry| entry.path().is_dir() && entry.path() != *archive_dir)
+            .filter(|entry| entry.path().join(ARCHIVE_MARKER).is_file())
+            .filter(|entry| pattern.is_match(&entry.file_name().to_string_lossy()))
+            .map(|entry| {
+                let modified = entry.metadata().and_then(|metadata| metadata.modified());
+                (modified.unwrap_or(UNIX_EPOCH), entry.path())
+            })
+            .collect();
+        archives.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+        // The new archive is one of the kept ones.
+        let mut removed = Vec::new();
+        for (_, path) in archives.into_iter().skip(self.keep_archives - 1) {
+            fs::remove_dir_all(&path)?;
+            removed.push(path);
+        }
+        Ok(removed)
+    }
+}
+
+/// The folder BuildCookRun staged `platform` into under `staging_dir`,
+/// e.g. `Windows`, `LinuxNoEditor` or `Android_ASTC`, or `staging_dir`
+/// itself when there is none. The most recently written one wins.
+pub fn staged_platform_dir(staging_dir: &Path, platform: Platform) -> PathBuf {
+    fs::read_dir(staging_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir() && platform.matches_target_name(&entry.file_name().to_string_lossy()))
+        .max_by_key(|entry| entry.metadata().and_then(|metadata| metadata.modified()).ok())
+        .map_or_else(|| staging_dir.to_path_buf(), |entry| entry.path())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use std::time::Duration;
+
+    fn values() -> TemplateValues {
+        TemplateValues {
+            project: "Game".to_owned(),
+            platform: "Win64".to_owned(),
+            config: "Shipping".to_owned(),
+            date: "2026-10-18".to_owned(),
+            time: "143005".to_owned(),
+            changelist: "12345".to_owned(),
+        }
+    }
+
+    #[test]
+    fn converts_unix_time_to_utc() {
+        let time = UtcDateTime::from_system_time(UNIX_EPOCH + Duration::from_secs(1_792_333_805));
+        assert_eq!((time.date(), time.time()), ("2026-10-18".to_owned(), "143005".to_owned()));
+        assert_eq!(time.iso8601(), "2026-10-18T14:30:05Z");
+        let leap_day = UtcDateTime::from_system_time(UNIX_EPOCH + Duration::from_secs(951_782_400));
+        assert_eq!(leap_day.date(), "2000-02-29");
+    }
+
+    #[test]
+    fn expands_templates() {
+        let template = "{project}/{platform}/{config}/{date}-{changelist}";
+        assert_eq!(expand_template(template, &values()), "Game/Win64/Shipping/2026-10-18-12345");
+        assert_eq!(expand_template("{branch}-{time}", &values()), "{branch}-143005");
+
+        let dir = join_template(Path::new("/builds"), "../{project}//{config}/", &values());
+        assert_eq!(dir, Path::new("/builds").join("Game").join("Shipping"));
+    }
+
+    #[test]
+    fn prunes_only_matching_archives() {
+        let root = TempDir::new("output");
+        let template = "{platform}/{config}-{date}-{changelist}";
+        for name in ["Shipping-2026-10-01-100", "Shipping-2026-10-02-101", "Debug-2026-10-03-102", "Notes"] {
+            fs::create_dir_all(root.join("Win64").join(name)).unwrap();
+            fs::write(root.join("Win64").join(name).join(ARCHIVE_MARKER), "").unwrap();
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        // Matches the template, but was not archived by the tool.
+        fs::create_dir_all(root.join("Win64").join("Shipping-2026-09-30-099")).unwrap();
+        let output = PackageOutput {
+            staging_dir: root.join("Staging"),
+            archive_dir: Some(join_template(&root, template, &values())),
+            keep_archives: 2,
+            archive_pattern: archive_name_pattern(template, &values()),
+        };
+        fs::create_dir_all(output.archive_dir.as_ref().unwrap()).unwrap();
+        output.mark_archive().unwrap();
+
+        let removed = output.prune_archives().unwrap();
+        assert_eq!(removed, [root.join("Win64").join("Shipping-2026-10-01-100")]);
+        let mut left: Vec<String> = fs::read_dir(root.join("Win64"))
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            [
+                "Debug-2026-10-03-102",
+                "Notes",
+                "Shipping-2026-09-30-099",
+                "Shipping-2026-10-02-101",
+                "Shipping-2026-10-18-12345"
+            ]
+        );
+        assert!(output.archive_dir.unwrap().join(ARCHIVE_MARKER).is_file());
+    }
+
+    #[test]
+    fn refuses_to_prune_without_date_or_time() {
+        let root = TempDir::new("output-no-date");
+        for template in ["{platform}/{config}-{changelist}", "{date}/{config}", "{project}"] {
+            assert_eq!(archive_name_pattern(template, &values()), None, "{}", template);
+            let output = PackageOutput {
+                staging_dir: root.join("Staging"),
+                archive_dir: Some(join_template(&root, template, &values())),
+                keep_archives: 2,
+                archive_pattern: archive_name_pattern(template, &values()),
+            };
+            assert!(output.prune_archives().is_err());
+        }
+        assert!(archive_name_pattern("{project}/{time}", &values()).is_some());
+    }
+}
@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::storage::{Engine, Project};
+
+/// A target platform, as understood by UnrealBuildTool.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Platform {
+    Win64,
+    Linux,
+    Mac,
+    Android,
+    IOS,
+    PS4,
+    PS5,
+    XBoxOne,
+    XBoxSeries,
+    Switch,
+}
+
+impl Platform {
+    /// Every platform, in display order.
+    pub const ALL: [Platform; 10] = [
+        Platform::Win64,
+        Platform::Linux,
+        Platform::Mac,
+        Platform::Android,
+        Platform::IOS,
+        Platform::PS4,
+        Platform::PS5,
+        Platform::XBoxOne,
+        Platform::XBoxSeries,
+        Platform::Switch,
+    ];
+
+    /// Platform name passed to UnrealBuildTool and used in plugin allow lists.
+    pub fn name(self) -> &'static str {
+        match self {
+            Platform::Win64 => "Win64",
+            Platform::Linux => "Linux",
+            Platform::Mac => "Mac",
+            Platform::Android => "Android",
+            Platform::IOS => "iOS",
+            Platform::PS4 => "PS4",
+            Platform::PS5 => "PS5",
+            Platform::XBoxOne => "XBoxOne",
+            Platform::XBoxSeries => "XBoxSeries",
+            Platform::Switch => "Switch",
+        }
+    }
+
+    /// Parses a platform name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Platform> {
+        Platform::ALL
+            .into_iter()
+            .find(|platform| platform.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Names the platform goes by in .uproject `TargetPlatforms` and ini files.
+    fn aliases(self) -> &'static [&'static str] {
+        match self {
+            Platform::Win64 => &["Win64", "Windows"],
+            Platform::Linux => &["Linux"],
+            Platform::Mac => &["Mac"],
+            Platform::Android => &["Android"],
+            Platform::IOS => &["IOS"],
+            Platform::PS4 => &["PS4"],
+            Platform::PS5 => &["PS5"],
+            Platform::XBoxOne => &["XBoxOne", "XB1", "XboxOneGDK"],
+            Platform::XBoxSeries => &["XBoxSeries", "XSX"],
+            Platform::Switch => &["Switch"],
+        }
+    }
+
+    /// Folder names of the platform extension under `Engine/Platforms`.
+    /// Empty for platforms built into the engine.
+    fn extension_dirs(self) -> &'static [&'static str] {
+        match self {
+            Platform::Win64 | Platform::Linux | Platform::Mac | Platform::Android | Platform::IOS => &[],
+            Platform::PS4 => &["PS4"],
+            Platform::PS5 => &["PS5"],
+            Platform::XBoxOne => &["XB1", "XboxOne", "XboxOneGDK"],
+            Platform::XBoxSeries => &["XSX", "XboxSeries"],
+            Platform::Switch => &["Switch"],
+        }
+    }
+
+    /// Host operating systems (`std::env::consts::OS`) that can build the
+    /// platform, with a description for the UI.
+    fn build_hosts(self) -> (&'static [&'static str], &'static str) {
+        match self {
+            Platform::Win64 => (&["windows"], "a Windows host"),
+            Platform::Linux => (&["windows", "linux"], "a Windows or Linux host"),
+            Platform::Mac | Platform::IOS => (&["macos"], "a macOS host"),
+            Platform::Android => (&["windows", "linux", "macos"], "a Windows, Linux or macOS host"),
+            Platform::PS4 | Platform::PS5 | Platform::XBoxOne | Platform::XBoxSeries | Platform::Switch => {
+                (&["windows"], "a Windows host")
+            }
+        }
+    }
+
+    /// Whether an entry of a .uproject `TargetPlatforms` list designates this
+    /// platform. Entries such as "WindowsNoEditor" or "Android_ASTC" match
+    /// their base platform.
+    pub fn matches_target_name(self, name: &str) -> bool {
+        let name = name.split('_').next().unwrap_or_default();
+        let name = ["NoEditor", "Client", "Server"]
+            .into_iter()
+            .find_map(|suffix| name.strip_suffix(suffix))
+            .unwrap_or(name);
+        self.aliases().iter().any(|alias| alias.eq_ignore_ascii_case(name))
+    }
+
+    /// Checks whether the platform can be built on this machine with
+    /// `engine` for `project`, returning why not otherwise.
+    pub fn availability(self, engine: Option<&Engine>, project: Option<&Project>) -> Result<(), String> {
+        self.availability_on(std::env::consts::OS, engine, project)
+    }
+
+    /// `availability` on a host running the operating system `host`.
+    fn availability_on(self, host: &str, engine: Option<&Engine>, project: Option<&Project>) -> Result<(), String> {
+        let (hosts, host_description) = self.build_hosts();
+        if !hosts.contains(&host) {
+            return Err(format!("{} requires {}", self, host_description));
+        }
+        if let Some(engine) = engine {
+            let platforms_dir = engine.root().join("Engine").join("Platforms");
+            let extensions = self.extension_dirs();
+            if !extensions.is_empty() && !extensions.iter().any(|dir| platforms_dir.join(dir).is_dir()) {
+                return Err(format!(
+                    "the engine has no {} platform extension (Engine/Platforms/{})",
+                    self, extensions[0]
+                ));
+            }
+        }
+        if let Some(project) = project {
+            if !project.target_platforms.is_empty()
+                && !project.target_platforms.iter().any(|name| self.matches_target_name(name))
+            {
+                return Err(format!(
+                    "{} is not in the project's TargetPlatforms ({})",
+                    self,
+                    project.target_platforms.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fixture;
+    use std::fs;
+
+    #[test]
+    fn target_names_match_their_base_platform() {
+        assert!(Platform::Win64.matches_target_name("Windows"));
+        assert!(Platform::Win64.matches_target_name("WindowsNoEditor"));
+        assert!(Platform::Win64.matches_target_name("win64"));
+        assert!(Platform::Linux.matches_target_name("LinuxServer"));
+        assert!(Platform::Android.matches_target_name("Android_ASTC"));
+        assert!(Platform::IOS.matches_target_name("IOSClient"));
+        assert!(Platform::XBoxOne.matches_target_name("XboxOneGDK"));
+        assert!(Platform::XBoxSeries.matches_target_name("XSX"));
+        assert!(!Platform::Win64.matches_target_name("Linux"));
+        assert!(!Platform::Linux.matches_target_name("LinuxArm64"));
+        assert!(!Platform::PS4.matches_target_name("PS5"));
+    }
+
+    #[test]
+    fn availability_checks_host_engine_and_project() {
+        let (root, mut project, engine) = fixture("platform");
+
+        assert!(Platform::Linux.availability_on("linux", None, None).is_ok());
+        assert!(Platform::Android.availability_on("macos", Some(&engine), Some(&project)).is_ok());
+        let error = Platform::Mac.availability_on("windows", None, None).unwrap_err();
+        assert_eq!(error, "Mac requires a macOS host");
+
+        // Console platforms need their extension in Engine/Platforms.
+        let error = Platform::PS5.availability_on("windows", Some(&engine), None).unwrap_err();
+        assert!(error.contains("Engine/Platforms/PS5"), "{}", error);
+        assert!(Platform::PS5.availability_on("windows", None, None).is_ok());
+        fs::create_dir_all(root.join("Engine").join("Platforms").join("XSX")).unwrap();
+        assert!(Platform::XBoxSeries.availability_on("windows", Some(&engine), None).is_ok());
+
+        // An empty TargetPlatforms list allows every platform.
+        assert!(Platform::Win64.availability_on("windows", Some(&engine), Some(&project)).is_ok());
+        project.target_platforms = vec!["WindowsNoEditor".to_owned(), "Android_ASTC".to_owned()];
+        assert!(Platform::Win64.availability_on("windows", Some(&engine), Some(&project)).is_ok());
+        assert!(Platform::Android.availability_on("windows", Some(&engine), Some(&project)).is_ok());
+        let error = Platform::Linux.availability_on("windows", Some(&engine), Some(&project)).unwrap_err();
+        assert_eq!(error, "Linux is not in the project's TargetPlatforms (WindowsNoEditor, Android_ASTC)");
+    }
+}
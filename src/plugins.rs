@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A plugin entry from the `Plugins` array of a .uproject or .uplugin file.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct PluginReference {
+    pub name: String,
+    pub enabled: bool,
+    pub optional: bool,
+    #[serde(rename = "MarketplaceURL", skip_serializing_if = "Option::is_none")]
+    pub marketplace_url: Option<String>,
+    #[serde(alias = "WhitelistTargets", skip_serializing_if = "Vec::is_empty")]
+    pub target_allow_list: Vec<String>,
+    #[serde(alias = "WhitelistPlatforms", skip_serializing_if = "Vec::is_empty")]
+    pub platform_allow_list: Vec<String>,
+}
+
+/// A module declared by a .uplugin file.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct PluginModule {
+    pub name: String,
+    #[serde(rename = "Type")]
+    pub module_type: String,
+    pub loading_phase: String,
+    #[serde(alias = "WhitelistPlatforms")]
+    pub platform_allow_list: Vec<String>,
+    #[serde(alias = "WhitelistTargets")]
+    pub target_allow_list: Vec<String>,
+}
+
+/// A plugin descriptor parsed from a .uplugin file.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct PluginDescriptor {
+    /// Plugin name, taken from the .uplugin file stem.
+    #[serde(skip)]
+    pub name: String,
+    /// Location of the .uplugin file.
+    #[serde(skip)]
+    pub location: PathBuf,
+    pub friendly_name: String,
+    pub version: i64,
+    pub version_name: String,
+    pub engine_version: Option<String>,
+    pub category: String,
+    pub description: String,
+    #[serde(rename = "MarketplaceURL")]
+    pub marketplace_url: Option<String>,
+    pub enabled_by_default: Option<bool>,
+    /// Platforms the plugin can be built for; empty means every platform.
+    pub supported_target_platforms: Vec<String>,
+    pub modules: Vec<PluginModule>,
+    /// Plugins this plugin depends on.
+    #[serde(rename = "Plugins")]
+    pub dependencies: Vec<PluginReference>,
+}
+
+impl PluginDescriptor {
+    /// Reads and parses a .uplugin file.
+    pub fn load(location: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(location)?;
+        let mut descriptor: PluginDescriptor = serde_json::from_str(content.trim_start_matches('\u{feff}'))?;
+        descriptor.name = location
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        descriptor.location = location.to_path_buf();
+        Ok(descriptor)
+    }
+}
+
+/// Parses the `Plugins` array of a .uproject (or .uplugin) document.
+pub fn plugin_references(document: &Value) -> Vec<PluginReference> {
+    document
+        .get("Plugins")
+        .and_then(Value::as_array)
+        .map(|plugins| {
+            plugins
+                .iter()
+                .filter_map(|plugin| serde_json::from_value(plugin.clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Recursively finds `.uplugin` files under `root`. A directory holding a
+/// .uplugin file is a plugin root and is not searched any further.
+pub fn find_uplugins(root: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut subdirs = Vec::new();
+        let mut uplugin = None;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => subdirs.push(path),
+                Ok(file_type)
+                    if file_type.is_file()
+                        && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("uplugin")) =>
+                {
+                    uplugin = Some(path)
+                }
+                _ => {}
+            }
+        }
+        match uplugin {
+            Some(uplugin) => found.push(uplugin),
+            None => pending.extend(subdirs),
+        }
+    }
+    found.sort();
+    found
+}
+
+/// Loads every plugin descriptor under `root`, skipping unreadable files.
+pub fn load_plugins_under(root: &Path) -> Vec<PluginDescriptor> {
+    find_uplugins(root)
+        .iter()
+        .filter_map(|location| match PluginDescriptor::load(location) {
+            Ok(descriptor) => Some(descriptor),
+            Err(e) => {
+                eprintln!("Failed to read plugin {:?}: {}", location, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Loads the project-local plugins found in `<project>/Plugins`.
+pub fn project_plugins(uproject_location: &Path) -> Vec<PluginDescriptor> {
+    match uproject_location.parent() {
+        Some(project_dir) => load_plugins_under(&project_dir.join("Plugins")),
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{fixture, TempDir};
+    use serde_json::json;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn finds_plugins_in_category_folders_but_not_inside_other_plugins() {
+        let root = TempDir::new("plugins");
+        write(&root.join("Top/Top.uplugin"), "{}");
+        write(&root.join("Top/Extras/Inner/Inner.uplugin"), "{}");
+        write(&root.join("Runtime/Audio/Deep/Deep.UPLUGIN"), "{}");
+        write(&root.join("Editor/Notes.txt"), "");
+
+        assert_eq!(
+            find_uplugins(&root),
+            [root.join("Runtime/Audio/Deep/Deep.UPLUGIN"), root.join("Top/Top.uplugin")]
+        );
+        assert!(find_uplugins(&root.join("Missing")).is_empty());
+    }
+
+    #[test]
+    fn loads_plugin_descriptors() {
+        let root = TempDir::new("plugins");
+        let location = root.join("Water/Water.uplugin");
+        write(
+            &location,
+            "\u{feff}{
+                \"FriendlyName\": \"Water\",
+                \"EngineVersion\": \"5.3.0\",
+                \"SupportedTargetPlatforms\": [\"Win64\", \"Linux\"],
+                \"Modules\": [
+                    { \"Name\": \"Water\", \"Type\": \"Runtime\", \"PlatformAllowList\": [\"Win64\"] },
+                    { \"Name\": \"WaterEditor\", \"Type\": \"Editor\", \"WhitelistPlatforms\": [\"Mac\"] }
+                ],
+                \"Plugins\": [{ \"Name\": \"Landmass\", \"Enabled\": true }]
+            }",
+        );
+
+        let descriptor = PluginDescriptor::load(&location).unwrap();
+        assert_eq!(descriptor.name, "Water");
+        assert_eq!(descriptor.location, location);
+        assert_eq!(descriptor.engine_version.as_deref(), Some("5.3.0"));
+        assert_eq!(descriptor.supported_target_platforms, ["Win64", "Linux"]);
+        assert_eq!(descriptor.modules[0].module_type, "Runtime");
+        assert_eq!(descriptor.modules[0].platform_allow_list, ["Win64"]);
+        assert_eq!(descriptor.modules[1].platform_allow_list, ["Mac"]);
+        assert_eq!(descriptor.dependencies[0].name, "Landmass");
+
+        write(&root.join("Broken/Broken.uplugin"), "{ not json");
+        assert!(PluginDescriptor::load(&root.join("Broken/Broken.uplugin")).is_err());
+        let loaded: Vec<String> = load_plugins_under(&root).into_iter().map(|plugin| plugin.name).collect();
+        assert_eq!(loaded, ["Water"]);
+    }
+
+    #[test]
+    fn reads_project_plugin_entries() {
+        let document = json!({ "Plugins": [
+            { "Name": "Paper2D", "Enabled": true },
+            { "Name": "Store", "Enabled": false, "MarketplaceURL": "com.epicgames.launcher://ue/marketplace/store" },
+            { "Name": "Legacy", "Enabled": true, "WhitelistPlatforms": ["Win64"], "WhitelistTargets": ["Game"] },
+            { "Name": "Optional", "Optional": true, "PlatformAllowList": ["Linux"] },
+            "not an object"
+        ] });
+        let references = plugin_references(&document);
+        assert_eq!(references.len(), 4);
+        assert!(references[0].enabled && !references[1].enabled);
+        assert_eq!(
+            references[1].marketplace_url.as_deref(),
+            Some("com.epicgames.launcher://ue/marketplace/store")
+        );
+        assert_eq!(references[2].platform_allow_list, ["Win64"]);
+        assert_eq!(references[2].target_allow_list, ["Game"]);
+        assert!(references[3].optional && !references[3].enabled);
+        assert_eq!(references[3].platform_allow_list, ["Linux"]);
+        assert!(plugin_references(&json!({})).is_empty());
+
+        let (root, project, _engine) = fixture("plugins");
+        write(&root.join("Game/Plugins/Local/Local.uplugin"), r#"{ "EngineVersion": "5.4.0" }"#);
+        write(&root.join("Engine/Plugins/Runtime/Engine/Engine.uplugin"), "{}");
+        let local = project_plugins(&project.location);
+        assert_eq!(local.len(), 1);
+        assert_eq!((local[0].name.as_str(), local[0].engine_version.as_deref()), ("Local", Some("5.4.0")));
+    }
+}
@@ -0,0 +1,432 @@
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::archive::{archive_dir, write_checksum_file, ArchiveFormat};
+use crate::commands::{open_in_file_browser, ProgressUpdate};
+use crate::disk::{format_size, written_size};
+use crate::history::{self, load_history, HistoryEntry};
+use crate::jobs::JobSpec;
+use crate::output::staged_platform_dir;
+use crate::platform::Platform;
+use crate::provenance::{write_build_manifest, BuildManifest, PackageRun};
+use crate::size_report::{report_path, SizeReport, SizeReportFile};
+use crate::storage::PostAction;
+
+/// Everything done once a package succeeded: measures the staged build
+/// and compares it with the previous package of the same project, platform
+/// and configuration in the history kept in `data_dir`, writes its build
+/// manifest from `run`, records it in the history, removes old archives
+/// and runs the post actions, whose commands are then added to the
+/// manifest. Ends with `Finished` unless a post action failed, and returns
+/// whether none did.
+pub fn after_package(
+    spec: &JobSpec,
+    data_dir: Option<&Path>,
+    run: PackageRun,
+    report: &mut dyn FnMut(ProgressUpdate),
+) -> bool {
+    let started = run.started;
+    report(ProgressUpdate::Stage("Measuring package size...".to_owned()));
+    let size = SizeReport::measure(&staged_platform_dir(&spec.output_dir(), spec.platform));
+    let history = data_dir.map(load_history).unwrap_or_default();
+    let previous = history::latest(&history, &spec.uproject, Some(spec.platform), Some(spec.mode))
+        .and_then(|entry| Some((entry.size.as_ref()?, entry.finished)));
+    for line in size.summary_lines(previous) {
+        report(ProgressUpdate::Log(line));
+    }
+    let file = SizeReportFile {
+        report: size.clone(),
+        previous_finished: previous.map(|(_, finished)| finished),
+        previous: previous.map(|(report, _)| report.clone()),
+    };
+    let path = report_path(spec);
+    let written = serde_json::to_string_pretty(&file)
+        .map_err(Into::into)
+        .and_then(|json| fs::write(&path, json).map_err(Box::<dyn std::error::Error>::from));
+    match written {
+        Ok(()) => report(ProgressUpdate::SizeReport(path)),
+        Err(e) => eprintln!("Failed to write {:?}: {}", path, e),
+    }
+
+    let mut manifest = BuildManifest::collect(spec, run);
+    match &manifest {
+        Ok(manifest) => write_manifest(spec, manifest, report),
+        Err(e) => {
+            eprintln!("Failed to describe the build of {}: {}", spec.label(), e);
+            report(ProgressUpdate::Log(format!("Failed to write the build manifest: {}", e)));
+        }
+    }
+
+    if let Some(dir) = data_dir {
+        record_package(dir, spec, started, size);
+    }
+    prune_archives(spec, report);
+    let mut post_commands = Vec::new();
+    let succeeded = run_post_actions(spec, &mut |update| {
+        if let ProgressUpdate::Command(command) = &update {
+            post_commands.push(command.clone());
+        }
+        report(update);
+    });
+    if let Ok(manifest) = &mut manifest {
+        if !post_commands.is_empty() {
+            manifest.commands.extend(post_commands);
+            write_manifest(spec, manifest, report);
+        }
+    }
+    if succeeded {
+        report(ProgressUpdate::Finished("Package finished".to_owned()));
+    }
+    succeeded
+}
+
+/// Writes the build manifest of `spec`, reporting where or why not.
+fn write_manifest(spec: &JobSpec, manifest: &BuildManifest, report: &mut dyn FnMut(ProgressUpdate)) {
+    match write_build_manifest(spec, manifest) {
+        Ok(paths) => {
+            for path in paths {
+                report(ProgressUpdate::Log(format!("Wrote {}", path.display())));
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to write the build manifest of {}: {}", spec.label(), e);
+            report(ProgressUpdate::Log(format!("Failed to write the build manifest: {}", e)));
+        }
+    }
+}
+
+/// Adds a successful package to the history, with the size of what it
+/// wrote to its output directory since `started` and its size report.
+fn record_package(dir: &Path, spec: &JobSpec, started: SystemTime, size: SizeReport) {
+    let output_dir = spec.output_dir();
+    let entry = HistoryEntry {
+        project: spec.uproject.clone(),
+        platform: spec.platform,
+        config: spec.mode,
+        finished: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+        staged_bytes: written_size(&output_dir, started),
+        output_dir,
+        size: Some(size),
+    };
+    if let Err(e) = history::record(dir, entry) {
+        eprintln!("Failed to record package history: {}", e);
+    }
+}
+
+/// Marks the new archive and removes the archives past the number to keep.
+fn prune_archives(spec: &JobSpec, report: &mut dyn FnMut(ProgressUpdate)) {
+    let Some(output) = &spec.output else {
+        return;
+    };
+    if let Err(e) = output.mark_archive() {
+        eprintln!("Failed to mark the archive of {}: {}", spec.label(), e);
+    }
+    match output.prune_archives() {
+        Ok(removed) => {
+            for path in removed {
+                println!("Removed old archive {:?}", path);
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to remove old archives of {}: {}", spec.label(), e);
+            report(ProgressUpdate::Log(format!("Old archives were not removed: {}", e)));
+        }
+    }
+}
+
+/// Runs the post actions of a successful package in order, reporting each
+/// one as a stage followed by its result. Stops at the first failure, which
+/// is reported as `Failed`. Returns whether every action succeeded.
+pub fn run_post_actions(spec: &JobSpec, report: &mut dyn FnMut(ProgressUpdate)) -> bool {
+    let dir = spec.output_dir();
+    for action in &spec.post_actions {
+        report(ProgressUpdate::Stage(format!("{}...", action.label())));
+        let result = match action {
+            PostAction::OpenFolder => open_in_file_browser(&dir)
+                .map(|_| format!("Opened {}", dir.display()))
+                .map_err(Into::into),
+            PostAction::CopyTo { destination } => copy_to(spec, &dir, destination),
+            PostAction::Archive { format } => archive(spec, &dir, *format, report),
+            PostAction::Command { command } => run_command(spec, &dir, command, report),
+            PostAction::Launch => launch(spec, &dir),
+        };
+        match result {
+            Ok(message) => report(ProgressUpdate::Log(message)),
+            Err(e) => {
+                eprintln!("{}: {} failed: {}", spec.label(), action.label(), e);
+                report(ProgressUpdate::Failed(format!("{} failed: {}", action.label(), e)));
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Copies the staging directory into `destination` (relative to the
+/// project folder), under the staging directory's name.
+fn copy_to(spec: &JobSpec, dir: &Path, destination: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    if destination.as_os_str().is_empty() {
+        return Err("no destination set".into());
+    }
+    let project_dir = spec.uproject.parent().unwrap_or(Path::new(""));
+    let target = project_dir.join(destination).join(dir.file_name().unwrap_or_default());
+    if target.starts_with(dir) {
+        return Err(format!("{} is inside the staging directory", target.display()).into());
+    }
+    let bytes = copy_dir(dir, &target)?;
+    Ok(format!("Copied {} to {}", format_size(bytes), target.display()))
+}
+
+/// Copies the files under `source` into `target`, returning the bytes copied.
+fn copy_dir(source: &Path, target: &Path) -> std::io::Result<u64> {
+    fs::create_dir_all(target)?;
+    let mut bytes = 0;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            bytes += copy_dir(&path, &target.join(entry.file_name()))?;
+        } else {
+            bytes += fs::copy(&path, target.join(entry.file_name()))?;
+        }
+    }
+    Ok(bytes)
+}
+
+/// Compresses the staged platform folder into
+/// `<project>-<platform>-<config>.<extension>` next to it, and writes the
+/// archive's SHA-256 alongside.
+fn archive(
+    spec: &JobSpec,
+    dir: &Path,
+    format: ArchiveFormat,
+    report: &mut dyn FnMut(ProgressUpdate),
+) -> Result<String, Box<dyn std::error::Error>> {
+    let source = staged_platform_dir(dir, spec.platform);
+    let name = format!("{}-{}-{}.{}", spec.project, spec.platform, spec.mode, format.extension());
+    let destination = source.with_file_name(name);
+    report(ProgressUpdate::Log(format!("Archiving {} into {}", source.display(), destination.display())));
+    let mut last_percent = None;
+    let manifest = archive_dir(&source, &destination, format, &mut |done, total| {
+        let percent = (done * 100).checked_div(total).unwrap_or(100);
+        if last_percent != Some(percent) {
+            last_percent = Some(percent);
+            report(ProgressUpdate::Progress(percent as f32 / 100.0));
+            if percent % 10 == 0 {
+                report(ProgressUpdate::Log(format!("Archived {} of {}", format_size(done), format_size(total))));
+            }
+        }
+    })?;
+    report(ProgressUpdate::Stage("Hashing archive...".to_owned()));
+    let sha256 = write_checksum_file(&destination)?;
+    let size = fs::metadata(&destination)?.len();
+    Ok(format!(
+        "Wrote {} ({}, {} files), SHA-256 {}",
+        destination.display(),
+        format_size(size),
+        manifest.files.len(),
+        sha256
+    ))
+}
+
+/// Runs `command` through the shell from the staging directory, with the
+/// package described by `UBT_*` environment variables. Its output is
+/// reported as log lines.
+fn run_command(
+    spec: &JobSpec,
+    dir: &Path,
+    command: &str,
+    report: &mut dyn FnMut(ProgressUpdate),
+) -> Result<String, Box<dyn std::error::Error>> {
+    if command.trim().is_empty() {
+        return Err("no command set".into());
+    }
+    let command_line = if cfg!(target_os = "windows") {
+        ["cmd", "/C", command]
+    } else {
+        ["sh", "-c", command]
+    }
+    .map(String::from);
+    let archive_dir = spec.output.as_ref().and_then(|output| output.archive_dir.clone());
+    let mut child = Command::new(&command_line[0])
+        .args(&command_line[1..])
+        .current_dir(dir)
+        .env("UBT_PROJECT", &spec.project)
+        .env("UBT_UPROJECT", &spec.uproject)
+        .env("UBT_PLATFORM", spec.platform.name())
+        .env("UBT_CONFIG", spec.mode.as_str())
+        .env("UBT_STAGING_DIR", dir)
+        .env("UBT_ARCHIVE_DIR", archive_dir.unwrap_or_default())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    report(ProgressUpdate::Process(child.id()));
+    report(ProgressUpdate::Command(command_line.into()));
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            report(ProgressUpdate::Log(line));
+        }
+    }
+    let status = child.wait()?;
+    match status.code() {
+        Some(0) => Ok(format!("`{}` finished", command)),
+        Some(code) => Err(format!("exit code {}", code).into()),
+        None => Err("terminated".into()),
+    }
+}
+
+/// Starts the packaged game without waiting for it.
+fn launch(spec: &JobSpec, dir: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let host = match spec.platform {
+        Platform::Win64 => "windows",
+        Platform::Linux => "linux",
+        Platform::Mac => "macos",
+        platform => return Err(format!("{} packages cannot be launched from here", platform).into()),
+    };
+    if host != std::env::consts::OS {
+        return Err(format!("{} packages do not run on this machine", spec.platform).into());
+    }
+    let name = match spec.platform {
+        Platform::Win64 => format!("{}.exe", spec.project),
+        Platform::Mac => format!("{}.app", spec.project),
+        _ => format!("{}.sh", spec.project),
+    };
+    let game = find_game(dir, &name, 2).ok_or_else(|| format!("no {} in {}", name, dir.display()))?;
+    let mut command = if spec.platform == Platform::Mac {
+        let mut command = Command::new("open");
+        command.arg(&game);
+        command
+    } else {
+        Command::new(&game)
+    };
+    let mut child = command.current_dir(game.parent().unwrap_or(dir)).spawn()?;
+    std::thread::spawn(move || child.wait());
+    Ok(format!("Launched {}", game.display()))
+}
+
+/// Looks for `name` in `dir` and its subfolders, down to `depth` levels:
+/// the game sits in a per-platform folder of the staging directory.
+fn find_game(dir: &Path, name: &str, depth: usize) -> Option<PathBuf> {
+    let candidate = dir.join(name);
+    if candidate.exists() {
+        return Some(candidate);
+    }
+    if depth == 0 {
+        return None;
+    }
+    let mut subdirs: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    subdirs.sort();
+    subdirs.into_iter().find_map(|subdir| find_game(&subdir, name, depth - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::BuildMode;
+    use crate::jobs::{BuildTarget, JobKind};
+    use crate::test_support::{fixture, TempDir};
+
+    #[test]
+    fn reports_the_written_size_report() {
+        let root = TempDir::new("post-actions");
+        let staging = root.join("Game").join("Builds");
+        fs::create_dir_all(staging.join("Linux")).unwrap();
+        fs::write(staging.join("Linux").join("Game.pak"), vec![0; 100]).unwrap();
+        let spec = JobSpec {
+            kind: JobKind::Package,
+            project: "Game".to_owned(),
+            target: BuildTarget::Game,
+            uproject: root.join("Game").join("Game.uproject"),
+            engine: root.join("UE5.sln"),
+            platform: Platform::Linux,
+            mode: BuildMode::Shipping,
+            output_settings: None,
+            output: None,
+            version_stamp: None,
+            post_actions: Vec::new(),
+        };
+
+        let mut updates = Vec::new();
+        let run = PackageRun { started: SystemTime::now(), commands: Vec::new(), project_version: None };
+        assert!(after_package(&spec, None, run, &mut |update| updates.push(update)));
+        let path = updates
+            .iter()
+            .find_map(|update| match update {
+                ProgressUpdate::SizeReport(path) => Some(path.clone()),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(path, staging.join("Game-Linux-Shipping.size.json"));
+        assert_eq!(crate::size_report::load_report(&path).unwrap().report.total_bytes, 100);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn records_post_action_commands() {
+        let (root, project, engine) = fixture("post-actions");
+        fs::create_dir_all(root.join("Game").join("Builds").join("Linux")).unwrap();
+        let mut spec = JobSpec::new(JobKind::Package, &project, &engine, Platform::Linux, BuildMode::Shipping);
+        spec.post_actions = vec![PostAction::Command { command: "true".to_owned() }];
+        let uat = vec!["RunUAT.sh".to_owned(), "BuildCookRun".to_owned()];
+
+        let run = PackageRun { started: SystemTime::now(), commands: vec![uat.clone()], project_version: None };
+        assert!(after_package(&spec, None, run, &mut |_| {}));
+        let path = root.join("Game").join("Builds").join("Linux").join(crate::provenance::BUILD_MANIFEST_FILE);
+        let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(manifest["commands"], serde_json::json!([uat, ["sh", "-c", "true"]]));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_actions_until_one_fails() {
+        let root = TempDir::new("post-actions");
+        let staging = root.join("Game").join("Builds");
+        fs::create_dir_all(staging.join("Linux")).unwrap();
+        fs::write(staging.join("Linux").join("Game.sh"), "#!/bin/sh\n").unwrap();
+        let spec = JobSpec {
+            kind: JobKind::Package,
+            project: "Game".to_owned(),
+            target: BuildTarget::Game,
+            uproject: root.join("Game").join("Game.uproject"),
+            engine: root.join("UE5.sln"),
+            platform: Platform::Linux,
+            mode: BuildMode::Shipping,
+            output_settings: None,
+            output: None,
+            version_stamp: None,
+            post_actions: vec![
+                PostAction::CopyTo { destination: PathBuf::from("../Copies") },
+                PostAction::Archive { format: ArchiveFormat::Zip },
+                PostAction::Command { command: "echo $UBT_PLATFORM $UBT_CONFIG; exit 3".to_owned() },
+                PostAction::OpenFolder,
+            ],
+        };
+
+        let mut updates = Vec::new();
+        assert!(!run_post_actions(&spec, &mut |update| updates.push(update)));
+        assert!(root.join("Copies").join("Builds").join("Linux").join("Game.sh").is_file());
+        let archive = root.join("Game").join("Builds").join("Game-Linux-Shipping.zip");
+        assert!(archive.is_file());
+        let checksum = fs::read_to_string(archive.with_extension("zip.sha256")).unwrap();
+        assert!(checksum.ends_with("  Game-Linux-Shipping.zip\n"), "{}", checksum);
+        let messages: Vec<String> = updates
+            .iter()
+            .filter_map(|update| match update {
+                ProgressUpdate::Log(message) | ProgressUpdate::Failed(message) => Some(message.clone()),
+                _ => None,
+            })
+            .collect();
+        assert!(messages.contains(&"Linux Shipping".to_owned()), "{:?}", messages);
+        assert_eq!(
+            messages.last().unwrap(),
+            "Run `echo $UBT_PLATFORM $UBT_CONFIG; exit 3` failed: exit code 3"
+        );
+    }
+}
@@ -0,0 +1,604 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::compatibility::check_plugins;
+use crate::disk::{dir_size, existing_ancestor, format_size, free_space, MIN_FREE_SPACE};
+use crate::history::{self, HistoryEntry};
+use crate::jobs::{JobKind, JobSpec};
+use crate::sdk::{check_sdk, SdkContext};
+use crate::storage::{self, read_uproject, Engine, Project};
+
+/// Free space, beyond what the job needs, below which a warning is shown.
+const LOW_FREE_SPACE: u64 = 20 << 30;
+/// A package is expected to need this many times the size of the project's
+/// Content folder when there is no earlier package to go by: the cooked data
+/// plus its staged copy.
+const CONTENT_SIZE_FACTOR: u64 = 2;
+/// How long a measured Content folder size is reused.
+const CONTENT_SIZE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Outcome of one preflight check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    /// Worth a look, but the job can run.
+    Warn,
+    /// The job would fail; fix it or override the check.
+    Fail,
+}
+
+/// Result of one preflight check.
+#[derive(Clone, Debug, Serialize)]
+pub struct PreflightCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+impl PreflightCheck {
+    fn pass(name: &'static str, message: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Pass, message: message.into() }
+    }
+
+    fn warn(name: &'static str, message: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Warn, message: message.into() }
+    }
+
+    fn fail(name: &'static str, message: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Fail, message: message.into() }
+    }
+}
+
+/// The checks run before a job, in display order.
+#[derive(Clone, Debug, Serialize)]
+pub struct Preflight {
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl Preflight {
+    /// Whether every check passed.
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.status == CheckStatus::Pass)
+    }
+
+    /// The checks that failed.
+    pub fn failures(&self) -> impl Iterator<Item = &PreflightCheck> {
+        self.checks.iter().filter(|check| check.status == CheckStatus::Fail)
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.failures().next().is_some()
+    }
+}
+
+/// Runs one check.
+type CheckFn = fn(&PreflightContext) -> PreflightCheck;
+
+/// What the checks look at.
+struct PreflightContext<'a> {
+    spec: &'a JobSpec,
+    project: &'a Project,
+    engine: Engine,
+    sdk: SdkContext,
+}
+
+/// The checks run for each kind of job. Adding a check is a matter of
+/// writing a function and listing it here.
+fn checks_for(kind: &JobKind) -> &'static [CheckFn] {
+    match kind {
+        JobKind::Build | JobKind::Package => &[
+            check_engine,
+            check_script,
+            check_project_file,
+            check_platform,
+            check_platform_sdk,
+            check_plugins_compatible,
+            check_output_dir,
+            check_disk_space,
+            check_build_mutex,
+        ],
+        JobKind::PackagePlugin(_) => &[
+            check_engine,
+            check_script,
+            check_platform_sdk,
+            check_output_dir,
+            check_disk_space,
+            check_build_mutex,
+        ],
+        JobKind::Clean => &[check_engine, check_script, check_project_file, check_build_mutex],
+        JobKind::GenerateProjectFiles => &[check_engine, check_script, check_project_file],
+    }
+}
+
+/// Checks that `spec` can run for `project` before it is queued.
+pub fn run_preflight(spec: &JobSpec, project: &Project) -> Preflight {
+    let engine = Engine { location: spec.engine.clone() };
+    let context = PreflightContext {
+        spec,
+        project,
+        sdk: SdkContext::new(&engine),
+        engine,
+    };
+    Preflight {
+        checks: checks_for(&spec.kind).iter().map(|check| check(&context)).collect(),
+    }
+}
+
+/// Runs the preflight of each job on a worker thread, since some checks
+/// start processes or walk folders. The results arrive together, in the
+/// order of `jobs`.
+pub fn spawn_preflight(jobs: Vec<(JobSpec, Project)>) -> Receiver<Vec<Preflight>> {
+    let (tx, rx) = mpsc::channel::<Vec<Preflight>>();
+    std::thread::spawn(move || {
+        let results = jobs.iter().map(|(spec, project)| run_preflight(spec, project)).collect();
+        let _ = tx.send(results);
+    });
+    rx
+}
+
+fn check_engine(context: &PreflightContext) -> PreflightCheck {
+    const NAME: &str = "Engine";
+    let root = context.engine.root();
+    if !context.engine.location.is_file() || !root.join("Engine").is_dir() {
+        return PreflightCheck::fail(NAME, format!("no engine found at {}", context.engine.location.display()));
+    }
+    let version = match context.engine.build_version() {
+        Ok(version) => format!("UE {}", version.major_minor()),
+        Err(_) => "unknown version".to_owned(),
+    };
+    let binaries = root.join("Engine").join("Binaries");
+    let build_tool_built = [
+        binaries.join("DotNET").join("UnrealBuildTool").join("UnrealBuildTool.dll"),
+        binaries.join("DotNET").join("UnrealBuildTool.exe"),
+    ]
+    .iter()
+    .any(|path| path.is_file());
+    let host = if cfg!(target_os = "windows") {
+        "Win64"
+    } else if cfg!(target_os = "macos") {
+        "Mac"
+    } else {
+        "Linux"
+    };
+    let editor_built = fs::read_dir(binaries.join(host)).is_ok_and(|entries| {
+        entries.flatten().any(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.starts_with("UnrealEditor") || name.starts_with("UE4Editor")
+        })
+    });
+
+    if context.spec.kind == JobKind::Package && !editor_built {
+        PreflightCheck::fail(NAME, format!("{}: the editor is not built, so the content cannot be cooked", version))
+    } else if !build_tool_built {
+        PreflightCheck::warn(NAME, format!("{}: UnrealBuildTool is not built yet and will be compiled first", version))
+    } else {
+        PreflightCheck::pass(NAME, format!("{} at {}", version, root.display()))
+    }
+}
+
+fn check_script(context: &PreflightContext) -> PreflightCheck {
+    const NAME: &str = "Build script";
+    let script = context.spec.script();
+    if script.is_file() {
+        PreflightCheck::pass(NAME, script.display().to_string())
+    } else {
+        PreflightCheck::fail(NAME, format!("{} does not exist", script.display()))
+    }
+}
+
+fn check_project_file(context: &PreflightContext) -> PreflightCheck {
+    const NAME: &str = "Project file";
+    match read_uproject(&context.spec.uproject) {
+        Ok(_) => PreflightCheck::pass(NAME, context.spec.uproject.display().to_string()),
+        Err(e) => PreflightCheck::fail(NAME, format!("{}: {}", context.spec.uproject.display(), e)),
+    }
+}
+
+fn check_platform(context: &PreflightContext) -> PreflightCheck {
+    const NAME: &str = "Platform";
+    match context.spec.platform.availability(Some(&context.engine), Some(context.project)) {
+        Ok(()) => PreflightCheck::pass(NAME, context.spec.platform.name()),
+        Err(reason) => PreflightCheck::fail(NAME, reason),
+    }
+}
+
+fn check_platform_sdk(context: &PreflightContext) -> PreflightCheck {
+    const NAME: &str = "Platform SDK";
+    let checks = check_sdk(context.spec.platform, &context.sdk);
+    let missing: Vec<String> = checks
+        .iter()
+        .filter_map(|check| check.result.as_ref().err().map(|hint| format!("{}: {}", check.name, hint)))
+        .collect();
+    if missing.is_empty() {
+        let found: Vec<String> = checks
+            .iter()
+            .filter_map(|check| check.result.as_ref().ok().map(|detail| format!("{}: {}", check.name, detail)))
+            .collect();
+        PreflightCheck::pass(NAME, found.join("\n"))
+    } else {
+        PreflightCheck::fail(NAME, missing.join("\n"))
+    }
+}
+
+fn check_plugins_compatible(context: &PreflightContext) -> PreflightCheck {
+    const NAME: &str = "Plugins";
+    let issues = check_plugins(context.project, &context.engine, context.spec.platform);
+    if issues.is_empty() {
+        let enabled = context.project.enabled_plugin_names().len();
+        PreflightCheck::pass(NAME, format!("{} enabled plugin(s) compatible", enabled))
+    } else {
+        let issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+        PreflightCheck::warn(NAME, issues.join("\n"))
+    }
+}
+
+fn check_output_dir(context: &PreflightContext) -> PreflightCheck {
+    const NAME: &str = "Output directory";
+    let archive_dir = context.spec.output.as_ref().and_then(|output| output.archive_dir.clone());
+    let dirs: Vec<PathBuf> = std::iter::once(context.spec.output_dir()).chain(archive_dir).collect();
+    for dir in &dirs {
+        if let Err(message) = writable(dir) {
+            return PreflightCheck::fail(NAME, message);
+        }
+    }
+    let dirs: Vec<String> = dirs.iter().map(|dir| dir.display().to_string()).collect();
+    PreflightCheck::pass(NAME, format!("{} writable", dirs.join(" and ")))
+}
+
+/// Checks that `dir` can be created (if needed) and written to, without
+/// creating it.
+fn writable(dir: &Path) -> Result<(), String> {
+    let Some(existing) = existing_ancestor(dir) else {
+        return Err(format!("{} cannot be created", dir.display()));
+    };
+    if !existing.is_dir() {
+        return Err(format!("{} is not a directory", existing.display()));
+    }
+    let probe = existing.join(format!(".preflight-{}", std::process::id()));
+    fs::write(&probe, b"").map_err(|e| format!("cannot write to {}: {}", existing.display(), e))?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+fn check_disk_space(context: &PreflightContext) -> PreflightCheck {
+    let output_dir = context.spec.output_dir();
+    let dir = existing_ancestor(&output_dir).unwrap_or(&output_dir);
+    let needed = (context.spec.kind == JobKind::Package).then(|| {
+        let history = history::load_history(&storage::config_dir());
+        let content_dir = context.spec.uproject.with_file_name("Content");
+        estimate_package_size(context.spec, &history, || content_size(&content_dir))
+    });
+    disk_space_check(dir, free_space(dir), needed)
+}
+
+/// Size of a Content folder, measured at most once per `CONTENT_SIZE_TTL`
+/// so that the packages of a matrix or batch do not each walk it.
+fn content_size(dir: &Path) -> u64 {
+    static SIZES: OnceLock<Mutex<HashMap<PathBuf, (Instant, u64)>>> = OnceLock::new();
+    let sizes = SIZES.get_or_init(Default::default);
+    let cached = sizes.lock().unwrap().get(dir).copied();
+    if let Some((measured, size)) = cached.filter(|(measured, _)| measured.elapsed() < CONTENT_SIZE_TTL) {
+        eprintln!("Reusing the size of {:?} measured {}s ago", dir, measured.elapsed().as_secs());
+        return size;
+    }
+    let size = dir_size(dir);
+    sizes.lock().unwrap().insert(dir.to_path_buf(), (Instant::now(), size));
+    size
+}
+
+/// Estimates how much a package writes, from the last package of the same
+/// project (preferably with the same platform and configuration), or from
+/// the size of its Content folder. Returns the estimate and what it is
+/// based on.
+fn estimate_package_size(spec: &JobSpec, history: &[HistoryEntry], content_size: impl FnOnce() -> u64) -> (u64, String) {
+    if let Some(entry) = history::latest(history, &spec.uproject, Some(spec.platform), Some(spec.mode)) {
+        return (entry.staged_bytes, format!("last {} {} package", entry.platform, entry.config));
+    }
+    if let Some(entry) = history::latest(history, &spec.uproject, None, None) {
+        return (entry.staged_bytes, format!("last package, {} {}", entry.platform, entry.config));
+    }
+    let content = content_size();
+    (
+        content * CONTENT_SIZE_FACTOR,
+        format!("{} times the Content folder, {}", CONTENT_SIZE_FACTOR, format_size(content)),
+    )
+}
+
+/// Judges `free` bytes available in `dir` for a job expected to write
+/// `needed` bytes (with what the estimate is based on), if known.
+fn disk_space_check(dir: &Path, free: Option<u64>, needed: Option<(u64, String)>) -> PreflightCheck {
+    const NAME: &str = "Disk space";
+    let Some(free) = free else {
+        return PreflightCheck::warn(NAME, format!("could not determine the free space in {}", dir.display()));
+    };
+    let message = format!("{} free in {}", format_size(free), dir.display());
+    let Some((needed, basis)) = needed else {
+        return if free < MIN_FREE_SPACE {
+            PreflightCheck::fail(NAME, format!("only {}; at least {} is needed", message, format_size(MIN_FREE_SPACE)))
+        } else if free < LOW_FREE_SPACE {
+            PreflightCheck::warn(NAME, format!("only {}", message))
+        } else {
+            PreflightCheck::pass(NAME, message)
+        };
+    };
+    let estimate = format!("about {} needed ({})", format_size(needed), basis);
+    if free < needed + MIN_FREE_SPACE {
+        PreflightCheck::fail(
+            NAME,
+            format!("only {}; {} plus {} to spare", message, estimate, format_size(MIN_FREE_SPACE)),
+        )
+    } else if free < needed + LOW_FREE_SPACE {
+        PreflightCheck::warn(NAME, format!("only {}; {}", message, estimate))
+    } else {
+        PreflightCheck::pass(NAME, format!("{}; {}", message, estimate))
+    }
+}
+
+fn check_build_mutex(_context: &PreflightContext) -> PreflightCheck {
+    let listing = if cfg!(target_os = "windows") {
+        Command::new("tasklist").args(["/FO", "CSV", "/NH"]).output()
+    } else {
+        Command::new("ps").args(["-A", "-o", "args="]).output()
+    };
+    let count = listing
+        .map(|output| count_build_tool_processes(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or(0);
+    build_mutex_check(count)
+}
+
+/// Counts the UnrealBuildTool processes in a `ps` or `tasklist` listing.
+fn count_build_tool_processes(listing: &str) -> usize {
+    listing
+        .lines()
+        .filter(|line| {
+            line.split(|c: char| c.is_whitespace() || c == ',' || c == '"').any(|token| {
+                let file_name = token.rsplit(['/', '\\']).next().unwrap_or(token);
+                ["UnrealBuildTool", "UnrealBuildTool.exe", "UnrealBuildTool.dll"]
+                    .iter()
+                    .any(|name| file_name.eq_ignore_ascii_case(name))
+            })
+        })
+        .count()
+}
+
+/// UnrealBuildTool holds a mutex while it runs; jobs started meanwhile wait
+/// for it (`-waitmutex`).
+fn build_mutex_check(running: usize) -> PreflightCheck {
+    const NAME: &str = "UnrealBuildTool";
+    if running == 0 {
+        PreflightCheck::pass(NAME, "no other instance is running")
+    } else {
+        PreflightCheck::warn(
+            NAME,
+            format!("{} instance(s) already running; the job waits until they release the build mutex", running),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::BuildMode;
+    use crate::platform::Platform;
+    use crate::test_support::fixture;
+
+    fn run(check: CheckFn, kind: JobKind, platform: Platform, project: &Project, engine: &Engine) -> PreflightCheck {
+        run_with_vars(check, kind, platform, project, engine, Vec::new())
+    }
+
+    /// Runs `check` with the environment variables `vars` for the SDK checks.
+    fn run_with_vars(
+        check: CheckFn,
+        kind: JobKind,
+        platform: Platform,
+        project: &Project,
+        engine: &Engine,
+        vars: Vec<(&str, &Path)>,
+    ) -> PreflightCheck {
+        let spec = JobSpec::new(kind, project, engine, platform, BuildMode::Development);
+        let vars = vars.into_iter().map(|(name, value)| (name.into(), value.into()));
+        check(&PreflightContext {
+            spec: &spec,
+            project,
+            engine: engine.clone(),
+            sdk: SdkContext::with_vars(engine, vars),
+        })
+    }
+
+    #[test]
+    fn engine_must_exist_and_be_built() {
+        let (root, project, engine) = fixture("engine");
+        let missing = Engine { location: root.join("Missing").join("UE5.sln") };
+        let check = run(check_engine, JobKind::Build, Platform::Linux, &project, &missing);
+        assert_eq!(check.status, CheckStatus::Fail);
+
+        let check = run(check_engine, JobKind::Build, Platform::Linux, &project, &engine);
+        assert_eq!(check.status, CheckStatus::Warn, "{}", check.message);
+        let check = run(check_engine, JobKind::Package, Platform::Linux, &project, &engine);
+        assert_eq!(check.status, CheckStatus::Fail, "{}", check.message);
+
+        let binaries = root.join("Engine").join("Binaries");
+        let build_tool = binaries.join("DotNET").join("UnrealBuildTool").join("UnrealBuildTool.dll");
+        fs::create_dir_all(build_tool.parent().unwrap()).unwrap();
+        fs::write(build_tool, "").unwrap();
+        for host in ["Win64", "Mac", "Linux"] {
+            fs::create_dir_all(binaries.join(host)).unwrap();
+            fs::write(binaries.join(host).join("UnrealEditor"), "").unwrap();
+        }
+        let check = run(check_engine, JobKind::Package, Platform::Linux, &project, &engine);
+        assert_eq!(check.status, CheckStatus::Pass, "{}", check.message);
+        assert!(check.message.starts_with("UE 5.4"), "{}", check.message);
+    }
+
+    #[test]
+    fn script_must_exist() {
+        let (_root, project, engine) = fixture("script");
+        let check = run(check_script, JobKind::Package, Platform::Linux, &project, &engine);
+        assert_eq!(check.status, CheckStatus::Fail);
+
+        let spec = JobSpec::new(JobKind::Package, &project, &engine, Platform::Linux, BuildMode::Development);
+        fs::create_dir_all(spec.script().parent().unwrap()).unwrap();
+        fs::write(spec.script(), "").unwrap();
+        let check = run(check_script, JobKind::Package, Platform::Linux, &project, &engine);
+        assert_eq!(check.status, CheckStatus::Pass);
+    }
+
+    #[test]
+    fn project_file_must_be_valid_json() {
+        let (_root, project, engine) = fixture("project");
+        let check = run(check_project_file, JobKind::Build, Platform::Linux, &project, &engine);
+        assert_eq!(check.status, CheckStatus::Pass);
+
+        fs::write(&project.location, r#"{ "EngineAssociation": "5.4", "#).unwrap();
+        let check = run(check_project_file, JobKind::Build, Platform::Linux, &project, &engine);
+        assert_eq!(check.status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn platform_must_be_available() {
+        let (_root, project, engine) = fixture("platform");
+        let host = if cfg!(target_os = "windows") {
+            Platform::Win64
+        } else if cfg!(target_os = "macos") {
+            Platform::Mac
+        } else {
+            Platform::Linux
+        };
+        let check = run(check_platform, JobKind::Build, host, &project, &engine);
+        assert_eq!(check.status, CheckStatus::Pass, "{}", check.message);
+        // Needs a Windows host and the fixture engine has no console platform extensions.
+        let check = run(check_platform, JobKind::Build, Platform::PS5, &project, &engine);
+        assert_eq!(check.status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn platform_sdk_must_be_installed() {
+        let (root, project, engine) = fixture("sdk");
+        let no_sdk = root.join("NoSdk");
+        let vars = vec![("SCE_PROSPERO_SDK_DIR", no_sdk.as_path())];
+        let check = run_with_vars(check_platform_sdk, JobKind::Build, Platform::PS5, &project, &engine, vars);
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.message.starts_with("PS5 SDK"), "{}", check.message);
+
+        let vars = vec![("SCE_PROSPERO_SDK_DIR", &*root)];
+        let check = run_with_vars(check_platform_sdk, JobKind::Build, Platform::PS5, &project, &engine, vars);
+        assert_eq!(check.status, CheckStatus::Pass);
+    }
+
+    #[test]
+    fn plugin_issues_are_warnings() {
+        let (_root, project, engine) = fixture("plugins");
+        let check = run(check_plugins_compatible, JobKind::Build, Platform::Linux, &project, &engine);
+        assert_eq!(check.status, CheckStatus::Pass);
+
+        fs::write(
+            &project.location,
+            r#"{ "EngineAssociation": "5.4", "Plugins": [{ "Name": "Nowhere", "Enabled": true }] }"#,
+        )
+        .unwrap();
+        let project = Project::new(project.location.clone()).unwrap();
+        let check = run(check_plugins_compatible, JobKind::Build, Platform::Linux, &project, &engine);
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert!(check.message.contains("Nowhere"), "{}", check.message);
+    }
+
+    #[test]
+    fn output_dir_must_be_writable() {
+        let (root, project, engine) = fixture("output");
+        // Builds/ does not exist yet; the project folder it goes in is writable.
+        let check = run(check_output_dir, JobKind::Package, Platform::Linux, &project, &engine);
+        assert_eq!(check.status, CheckStatus::Pass, "{}", check.message);
+        assert!(!root.join("Game").join("Builds").exists());
+
+        // A file where the staging directory should be.
+        fs::write(root.join("Game").join("Builds"), "").unwrap();
+        let check = run(check_output_dir, JobKind::Package, Platform::Linux, &project, &engine);
+        assert_eq!(check.status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn disk_space_thresholds() {
+        let dir = Path::new("/builds");
+        assert_eq!(disk_space_check(dir, None, None).status, CheckStatus::Warn);
+        assert_eq!(disk_space_check(dir, Some(1 << 30), None).status, CheckStatus::Fail);
+        assert_eq!(disk_space_check(dir, Some(10 << 30), None).status, CheckStatus::Warn);
+        assert_eq!(disk_space_check(dir, Some(100 << 30), None).status, CheckStatus::Pass);
+
+        let needed = || Some((50 << 30, "last package".to_owned()));
+        assert_eq!(disk_space_check(dir, Some(51 << 30), needed()).status, CheckStatus::Fail);
+        assert_eq!(disk_space_check(dir, Some(60 << 30), needed()).status, CheckStatus::Warn);
+        assert_eq!(disk_space_check(dir, Some(100 << 30), needed()).status, CheckStatus::Pass);
+    }
+
+    #[test]
+    fn package_size_estimate_prefers_history() {
+        let (root, project, engine) = fixture("estimate");
+        let spec = JobSpec::new(JobKind::Package, &project, &engine, Platform::Android, BuildMode::Shipping);
+        let entry = |platform, config, staged_bytes| HistoryEntry {
+            project: project.location.clone(),
+            platform,
+            config,
+            finished: 0,
+            output_dir: root.join("Game").join("Builds"),
+            staged_bytes,
+            size: None,
+        };
+
+        assert_eq!(estimate_package_size(&spec, &[], || 1000).0, 1000 * CONTENT_SIZE_FACTOR);
+        let history = [entry(Platform::Win64, BuildMode::Development, 300)];
+        assert_eq!(estimate_package_size(&spec, &history, || unreachable!()).0, 300);
+        let history = [
+            entry(Platform::Android, BuildMode::Shipping, 200),
+            entry(Platform::Win64, BuildMode::Development, 300),
+        ];
+        assert_eq!(estimate_package_size(&spec, &history, || unreachable!()).0, 200);
+    }
+
+    #[test]
+    fn content_size_is_measured_once() {
+        let (root, _project, _engine) = fixture("content-size");
+        let content = root.join("Game").join("Content");
+        fs::create_dir_all(&content).unwrap();
+        fs::write(content.join("Map.umap"), [0; 100]).unwrap();
+        assert_eq!(content_size(&content), 100);
+        fs::write(content.join("Texture.uasset"), [0; 50]).unwrap();
+        assert_eq!(content_size(&content), 100);
+    }
+
+    #[test]
+    fn running_build_tool_is_a_warning() {
+        let ps = "/sbin/init\n\
+                  dotnet /opt/Epic Games/UE_5.4/Engine/Binaries/DotNET/UnrealBuildTool/UnrealBuildTool.dll Game Linux\n\
+                  vim UnrealBuildTool.md\n";
+        assert_eq!(count_build_tool_processes(ps), 1);
+        let tasklist = "\"explorer.exe\",\"4120\",\"Console\",\"1\",\"95,312 K\"\n\
+                        \"UnrealBuildTool.exe\",\"9876\",\"Console\",\"1\",\"80,000 K\"\n";
+        assert_eq!(count_build_tool_processes(tasklist), 1);
+
+        assert_eq!(build_mutex_check(0).status, CheckStatus::Pass);
+        assert_eq!(build_mutex_check(2).status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn checks_depend_on_the_job_kind() {
+        let (_root, project, engine) = fixture("kinds");
+        let spec = JobSpec::new(JobKind::GenerateProjectFiles, &project, &engine, Platform::Linux, BuildMode::Debug);
+        let preflight = run_preflight(&spec, &project);
+        let names: Vec<&str> = preflight.checks.iter().map(|check| check.name).collect();
+        assert_eq!(names, ["Engine", "Build script", "Project file"]);
+        // No GenerateProjectFiles script in the fixture.
+        assert!(preflight.has_failures());
+        assert_eq!(preflight.failures().next().unwrap().name, "Build script");
+
+        let results = spawn_preflight(vec![(spec, project)]).recv().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].checks.len(), names.len());
+    }
+}
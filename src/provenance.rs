@@ -0,0 +1,261 @@
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+
+use crate::archive::sha256_file;
+use crate::commands::BuildMode;
+use crate::ini::get_value;
+use crate::jobs::JobSpec;
+use crate::output::{staged_platform_dir, UtcDateTime};
+use crate::platform::Platform;
+use crate::plugins::{plugin_references, project_plugins};
+use crate::source_control::detect_revision;
+use crate::storage::{read_uproject, BuildVersion, Engine};
+use crate::version_stamp::{game_config_path, PROJECT_SETTINGS};
+
+/// File written into every staged build.
+pub const BUILD_MANIFEST_FILE: &str = "build-manifest.json";
+
+/// How a staged build was made, so that a reported build can be traced back.
+#[derive(Debug, Serialize)]
+pub struct BuildManifest {
+    /// Version of this tool.
+    pub tool_version: &'static str,
+    pub project: String,
+    /// `ProjectVersion` from `DefaultGame.ini`, as stamped for the build.
+    pub project_version: Option<String>,
+    pub uproject: PathBuf,
+    pub uproject_sha256: String,
+    /// Git commit or Perforce changelist of the project, when detectable.
+    pub revision: Option<String>,
+    pub engine: PathBuf,
+    /// The engine's `Build.version`, when readable.
+    pub engine_version: Option<BuildVersion>,
+    pub plugins: Vec<ManifestPlugin>,
+    pub target: String,
+    pub platform: Platform,
+    pub config: BuildMode,
+    /// Program and arguments of every command run, in order.
+    pub commands: Vec<Vec<String>>,
+    pub host: Host,
+    /// UTC, ISO 8601.
+    pub started: String,
+    pub finished: String,
+}
+
+/// How a package ran, as recorded in its manifest.
+pub struct PackageRun {
+    pub started: SystemTime,
+    /// Program and arguments of every process it started, in order.
+    pub commands: Vec<Vec<String>>,
+    /// `ProjectVersion` stamped for it, if any; the config is restored by
+    /// the time the manifest is written.
+    pub project_version: Option<String>,
+}
+
+/// A plugin enabled for the build.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ManifestPlugin {
+    pub name: String,
+    /// `VersionName` of project plugins; engine and marketplace plugins
+    /// follow the engine.
+    pub version: Option<String>,
+    /// Whether the plugin lives in the project's `Plugins/` folder.
+    pub project_plugin: bool,
+}
+
+/// The machine the build was made on.
+#[derive(Debug, Serialize)]
+pub struct Host {
+    pub name: String,
+    pub user: String,
+    pub os: &'static str,
+    pub arch: &'static str,
+}
+
+impl Host {
+    fn current() -> Self {
+        Self {
+            name: host_name(),
+            user: env::var("USERNAME").or_else(|_| env::var("USER")).unwrap_or_default(),
+            os: env::consts::OS,
+            arch: env::consts::ARCH,
+        }
+    }
+}
+
+fn host_name() -> String {
+    env::var("COMPUTERNAME")
+        .or_else(|_| env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .or_else(|| {
+            let output = Command::new("hostname").output().ok()?;
+            Some(String::from_utf8_lossy(&output.stdout).into_owned())
+        })
+        .map(|name| name.trim().to_owned())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_owned())
+}
+
+/// Plugins enabled by the .uproject, plus the project plugins it does not
+/// disable (they are enabled by default), sorted by name.
+fn enabled_plugins(uproject: &Path) -> Vec<ManifestPlugin> {
+    let references = read_uproject(uproject)
+        .map(|document| plugin_references(&document))
+        .unwrap_or_default();
+    let local = project_plugins(uproject);
+    let mut plugins: Vec<ManifestPlugin> = references
+        .iter()
+        .filter(|reference| reference.enabled)
+        .map(|reference| {
+            let descriptor = local.iter().find(|plugin| plugin.name.eq_ignore_ascii_case(&reference.name));
+            ManifestPlugin {
+                name: reference.name.clone(),
+                version: descriptor.map(|plugin| plugin.version_name.clone()),
+                project_plugin: descriptor.is_some(),
+            }
+        })
+        .collect();
+    for plugin in &local {
+        let listed = references.iter().any(|reference| reference.name.eq_ignore_ascii_case(&plugin.name));
+        if !listed && plugin.enabled_by_default != Some(false) {
+            plugins.push(ManifestPlugin {
+                name: plugin.name.clone(),
+                version: Some(plugin.version_name.clone()),
+                project_plugin: true,
+            });
+        }
+    }
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    plugins
+}
+
+impl BuildManifest {
+    /// Describes the package `spec` that ran as `run` and finished now.
+    pub fn collect(spec: &JobSpec, run: PackageRun) -> Result<Self, Box<dyn std::error::Error>> {
+        let project_dir = spec.uproject.parent().unwrap_or(Path::new(""));
+        Ok(Self {
+            tool_version: env!("CARGO_PKG_VERSION"),
+            project: spec.project.clone(),
+            project_version: run.project_version.or_else(|| {
+                fs::read_to_string(game_config_path(&spec.uproject))
+                    .ok()
+                    .and_then(|content| get_value(&content, PROJECT_SETTINGS, "ProjectVersion"))
+            }),
+            uproject: spec.uproject.clone(),
+            uproject_sha256: sha256_file(&spec.uproject)?,
+            revision: detect_revision(project_dir),
+            engine: spec.engine.clone(),
+            engine_version: Engine { location: spec.engine.clone() }.build_version().ok(),
+            plugins: enabled_plugins(&spec.uproject),
+            target: spec.target_name(),
+            platform: spec.platform,
+            config: spec.mode,
+            commands: run.commands,
+            host: Host::current(),
+            started: UtcDateTime::from_system_time(run.started).iso8601(),
+            finished: UtcDateTime::from_system_time(SystemTime::now()).iso8601(),
+        })
+    }
+}
+
+/// Writes `manifest` into the staged platform folder of package `spec` and
+/// into the copy UAT archived, if any, and returns the paths written.
+pub fn write_build_manifest(spec: &JobSpec, manifest: &BuildManifest) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let json = serde_json::to_string_pretty(manifest)?;
+    let mut dirs = vec![staged_platform_dir(&spec.output_dir(), spec.platform)];
+    if let Some(archive_dir) = spec.output.as_ref().and_then(|output| output.archive_dir.as_ref()) {
+        if archive_dir.is_dir() {
+            dirs.push(staged_platform_dir(archive_dir, spec.platform));
+        }
+    }
+    let mut paths = Vec::new();
+    for dir in dirs {
+        let path = dir.join(BUILD_MANIFEST_FILE);
+        fs::write(&path, &json)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::JobKind;
+    use crate::storage::OutputSettings;
+    use crate::test_support::fixture;
+
+    #[test]
+    fn records_how_a_package_was_made() {
+        let (root, project, engine) = fixture("provenance");
+        let uproject = project.location.clone();
+        let plugins = root.join("Game").join("Plugins");
+        for (name, enabled_by_default) in [("Local", true), ("Optional", false), ("Disabled", true)] {
+            let dir = plugins.join(name);
+            fs::create_dir_all(&dir).unwrap();
+            let descriptor = format!(r#"{{ "VersionName": "1.2", "EnabledByDefault": {} }}"#, enabled_by_default);
+            fs::write(dir.join(format!("{}.uplugin", name)), descriptor).unwrap();
+        }
+        fs::write(
+            &uproject,
+            r#"{ "EngineAssociation": "5.4", "Plugins": [
+                { "Name": "OnlineSubsystemSteam", "Enabled": true },
+                { "Name": "Disabled", "Enabled": false },
+                { "Name": "Paper2D", "Enabled": false }
+            ] }"#,
+        )
+        .unwrap();
+        let staged = root.join("Game").join("Builds").join("Linux");
+        fs::create_dir_all(&staged).unwrap();
+
+        let spec = JobSpec::new(JobKind::Package, &project, &engine, Platform::Linux, BuildMode::Shipping);
+        let command_line: Vec<String> = ["RunUAT.sh", "BuildCookRun", "-platform=Linux"].map(String::from).into();
+        let run = PackageRun {
+            started: SystemTime::now(),
+            commands: vec![command_line],
+            project_version: Some("1.4.2".to_owned()),
+        };
+        let manifest = BuildManifest::collect(&spec, run).unwrap();
+        let paths = write_build_manifest(&spec, &manifest).unwrap();
+        assert_eq!(paths, [staged.join(BUILD_MANIFEST_FILE)]);
+
+        let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(&paths[0]).unwrap()).unwrap();
+        assert_eq!(manifest["project"], "Game");
+        assert_eq!(manifest["project_version"], "1.4.2");
+        assert_eq!(manifest["uproject_sha256"], sha256_file(&uproject).unwrap());
+        assert_eq!(manifest["engine_version"]["MinorVersion"], 4);
+        assert_eq!(manifest["platform"], "Linux");
+        assert_eq!(manifest["config"], "Shipping");
+        let plugins: Vec<&str> = manifest["plugins"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|plugin| plugin["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(plugins, ["Local", "OnlineSubsystemSteam"]);
+        assert_eq!(manifest["commands"][0][1], "BuildCookRun");
+        assert!(manifest["started"].as_str().unwrap().ends_with('Z'));
+    }
+
+    #[test]
+    fn writes_into_the_archived_copy() {
+        let (root, project, engine) = fixture("provenance");
+        let staged = root.join("Game").join("Builds").join("Linux");
+        let archived = root.join("Game").join("Archive").join("Linux");
+        fs::create_dir_all(&staged).unwrap();
+        fs::create_dir_all(&archived).unwrap();
+        let mut spec = JobSpec::new(JobKind::Package, &project, &engine, Platform::Linux, BuildMode::Shipping);
+        let settings = OutputSettings { archive_root: Some(PathBuf::from("Archive")), ..Default::default() };
+        spec.set_output(&settings, &project);
+
+        let run = PackageRun { started: SystemTime::now(), commands: Vec::new(), project_version: None };
+        let manifest = BuildManifest::collect(&spec, run).unwrap();
+        let paths = write_build_manifest(&spec, &manifest).unwrap();
+        assert_eq!(paths, [staged.join(BUILD_MANIFEST_FILE), archived.join(BUILD_MANIFEST_FILE)]);
+        assert_eq!(fs::read(&paths[0]).unwrap(), fs::read(&paths[1]).unwrap());
+    }
+}
@@ -0,0 +1,97 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+/// Directories that never contain projects worth registering.
+const SKIPPED_DIRS: [&str; 3] = ["Intermediate", "Saved", "Engine"];
+
+/// Recursively finds `.uproject` files under `root`, skipping generated
+/// folders (`Intermediate`, `Saved`), engine trees and hidden directories.
+/// Symbolic links are not followed.
+pub fn find_uprojects(root: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Failed to read directory {:?}: {}", dir, e);
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let path = entry.path();
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+            if file_type.is_dir() {
+                if !file_name.starts_with('.') && !SKIPPED_DIRS.contains(&file_name.as_ref()) {
+                    pending.push(path);
+                }
+            } else if file_type.is_file()
+                && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("uproject"))
+            {
+                found.push(path);
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+/// Scans `roots` on a background thread and sends every discovered
+/// `.uproject` file once the scan is complete.
+pub fn spawn_scan(roots: Vec<PathBuf>) -> Receiver<Vec<PathBuf>> {
+    let (tx, rx) = mpsc::channel::<Vec<PathBuf>>();
+    std::thread::spawn(move || {
+        let mut found: Vec<PathBuf> = roots.iter().flat_map(|root| find_uprojects(root)).collect();
+        found.sort();
+        found.dedup();
+        eprintln!("Scan found {} project(s) under {:?}", found.len(), roots);
+        let _ = tx.send(found);
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn finds_nested_projects_and_skips_generated_folders() {
+        let root = TempDir::new("scanner");
+        let files = [
+            "Game/Game.uproject",
+            "Game/Plugins/Sample/Sample.UPROJECT",
+            "Work/Nested/Deeper/Deeper.uproject",
+            "Game/Intermediate/Copy.uproject",
+            "Game/Saved/Autosave.uproject",
+            "UE5/Engine/Templates/Template.uproject",
+            ".git/Hidden.uproject",
+            "Work/.cache/Cached.uproject",
+            "Work/notes.txt",
+        ];
+        for file in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "{}").unwrap();
+        }
+
+        assert_eq!(
+            find_uprojects(&root),
+            [
+                root.join("Game/Game.uproject"),
+                root.join("Game/Plugins/Sample/Sample.UPROJECT"),
+                root.join("Work/Nested/Deeper/Deeper.uproject"),
+            ]
+        );
+        // Overlapping roots report each project once.
+        assert_eq!(
+            spawn_scan(vec![root.join("Work"), root.join("Work")]).recv().unwrap(),
+            [root.join("Work/Nested/Deeper/Deeper.uproject")]
+        );
+    }
+}
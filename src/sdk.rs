@@ -0,0 +1,392 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::platform::Platform;
+use crate::storage::Engine;
+
+/// Outcome of one SDK check: where the component was found, or what is
+/// missing and how to fix it.
+pub struct SdkCheck {
+    pub name: &'static str,
+    pub result: Result<String, String>,
+}
+
+impl SdkCheck {
+    fn found(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, result: Ok(detail.into()) }
+    }
+
+    fn missing(name: &'static str, hint: impl Into<String>) -> Self {
+        Self { name, result: Err(hint.into()) }
+    }
+}
+
+/// Checks one SDK component.
+type SdkCheckFn = fn(&SdkContext) -> SdkCheck;
+
+/// What the SDK checks look at.
+pub struct SdkContext {
+    engine_root: PathBuf,
+    /// Environment variables, normally those of this process.
+    vars: Vec<(OsString, OsString)>,
+}
+
+impl SdkContext {
+    /// Checks SDKs for `engine` using the environment of this process.
+    pub fn new(engine: &Engine) -> Self {
+        Self::with_vars(engine, env::vars_os())
+    }
+
+    /// Checks SDKs for `engine` using the environment variables `vars`.
+    pub fn with_vars(engine: &Engine, vars: impl IntoIterator<Item = (OsString, OsString)>) -> Self {
+        Self {
+            engine_root: engine.root(),
+            vars: vars.into_iter().collect(),
+        }
+    }
+
+    /// The value of environment variable `name`. Names are case-insensitive
+    /// on Windows.
+    fn var(&self, name: &str) -> Option<&OsString> {
+        self.vars
+            .iter()
+            .find(|(key, _)| key == name || (cfg!(target_os = "windows") && key.eq_ignore_ascii_case(name)))
+            .map(|(_, value)| value)
+    }
+
+    /// The directory named by environment variable `var`, if it exists.
+    fn env_dir(&self, var: &str) -> Option<(String, PathBuf)> {
+        let dir = PathBuf::from(self.var(var)?);
+        dir.is_dir().then(|| (format!("{}={}", var, dir.to_string_lossy()), dir))
+    }
+
+    /// `<UE_SDKS_ROOT>/Host<host>/<name>`, the layout Turnkey's AutoSDK
+    /// system installs SDKs into, if it exists.
+    fn auto_sdk(&self, name: &str) -> Option<(String, PathBuf)> {
+        let root = PathBuf::from(self.var("UE_SDKS_ROOT")?);
+        let dir = root.join(auto_sdk_host()).join(name);
+        dir.is_dir().then(|| (format!("AutoSDK {}", dir.to_string_lossy()), dir))
+    }
+
+    /// The first existing directory named by `vars`, or the AutoSDK folder.
+    fn sdk_dir(&self, vars: &[&str], auto_sdk: &str) -> Option<(String, PathBuf)> {
+        vars.iter()
+            .find_map(|var| self.env_dir(var))
+            .or_else(|| self.auto_sdk(auto_sdk))
+    }
+}
+
+/// The AutoSDK folder of this host.
+fn auto_sdk_host() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "HostWin64"
+    } else if cfg!(target_os = "macos") {
+        "HostMac"
+    } else {
+        "HostLinux"
+    }
+}
+
+/// The checks run for each platform. Validating another SDK component is a
+/// matter of adding a function to the platform's list.
+fn checks_for(platform: Platform) -> &'static [SdkCheckFn] {
+    match platform {
+        Platform::Win64 => &[check_visual_studio, check_windows_sdk],
+        Platform::Linux => &[check_linux_toolchain],
+        Platform::Mac | Platform::IOS => &[check_xcode],
+        Platform::Android => &[check_android_sdk, check_android_ndk, check_jdk],
+        Platform::PS4 => &[check_ps4_sdk],
+        Platform::PS5 => &[check_ps5_sdk],
+        Platform::XBoxOne | Platform::XBoxSeries => &[check_gdk],
+        Platform::Switch => &[check_switch_sdk],
+    }
+}
+
+/// Checks that the SDKs needed to build `platform` are present.
+pub fn check_sdk(platform: Platform, context: &SdkContext) -> Vec<SdkCheck> {
+    checks_for(platform).iter().map(|check| check(context)).collect()
+}
+
+/// The last subdirectory of `dir` in name order, usually the newest version.
+fn latest_subdir(dir: &Path) -> Option<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs.pop()
+}
+
+fn check_visual_studio(context: &SdkContext) -> SdkCheck {
+    const NAME: &str = "Visual Studio C++ toolchain";
+    if let Some((detail, _)) = context.auto_sdk("Win64") {
+        return SdkCheck::found(NAME, detail);
+    }
+    let program_files = context.var("ProgramFiles(x86)").map(PathBuf::from).unwrap_or_default();
+    let vswhere = program_files
+        .join("Microsoft Visual Studio")
+        .join("Installer")
+        .join("vswhere.exe");
+    let output = Command::new(&vswhere)
+        .args(["-latest", "-products", "*"])
+        .args(["-requires", "Microsoft.VisualStudio.Component.VC.Tools.x86.x64"])
+        .args(["-property", "installationPath"])
+        .output();
+    match output {
+        Ok(output) if !String::from_utf8_lossy(&output.stdout).trim().is_empty() => {
+            SdkCheck::found(NAME, String::from_utf8_lossy(&output.stdout).trim().to_owned())
+        }
+        _ => SdkCheck::missing(
+            NAME,
+            "no Visual Studio with the \"Desktop development with C++\" workload was found",
+        ),
+    }
+}
+
+fn check_windows_sdk(context: &SdkContext) -> SdkCheck {
+    const NAME: &str = "Windows SDK";
+    if let Some((detail, _)) = context.auto_sdk("Win64") {
+        return SdkCheck::found(NAME, detail);
+    }
+    let program_files = context.var("ProgramFiles(x86)").map(PathBuf::from).unwrap_or_default();
+    let include = program_files.join("Windows Kits").join("10").join("Include");
+    match latest_subdir(&include) {
+        Some(version) => SdkCheck::found(NAME, version.to_string_lossy()),
+        None => SdkCheck::missing(NAME, "install the Windows 10/11 SDK with the Visual Studio installer"),
+    }
+}
+
+fn check_linux_toolchain(context: &SdkContext) -> SdkCheck {
+    const NAME: &str = "Linux toolchain";
+    if let Some((detail, _)) = context
+        .env_dir("LINUX_MULTIARCH_ROOT")
+        .or_else(|| context.env_dir("LINUX_ROOT"))
+        .or_else(|| context.auto_sdk("Linux_x64"))
+    {
+        return SdkCheck::found(NAME, detail);
+    }
+    if cfg!(target_os = "linux") {
+        let bundled = context
+            .engine_root
+            .join("Engine")
+            .join("Extras")
+            .join("ThirdPartyNotUE")
+            .join("SDKs")
+            .join("HostLinux")
+            .join("Linux_x64");
+        if let Some(version) = latest_subdir(&bundled) {
+            return SdkCheck::found(NAME, format!("bundled clang {}", version.to_string_lossy()));
+        }
+        return SdkCheck::missing(
+            NAME,
+            "run Engine/Build/BatchFiles/Linux/SetupToolchain.sh to fetch the bundled clang, or set LINUX_MULTIARCH_ROOT",
+        );
+    }
+    SdkCheck::missing(
+        NAME,
+        "set LINUX_MULTIARCH_ROOT to the cross-compile toolchain, or install it with Turnkey (AutoSDK)",
+    )
+}
+
+fn check_xcode(_context: &SdkContext) -> SdkCheck {
+    const NAME: &str = "Xcode";
+    match Command::new("xcode-select").arg("-p").output() {
+        Ok(output) if output.status.success() => {
+            let path = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+            if Path::new(&path).is_dir() {
+                return SdkCheck::found(NAME, path);
+            }
+            SdkCheck::missing(NAME, format!("xcode-select points to {}, which does not exist", path))
+        }
+        _ => SdkCheck::missing(NAME, "install Xcode and run xcode-select --install"),
+    }
+}
+
+fn check_android_sdk(context: &SdkContext) -> SdkCheck {
+    const NAME: &str = "Android SDK";
+    match context.sdk_dir(&["ANDROID_HOME", "ANDROID_SDK_ROOT"], "Android") {
+        Some((detail, _)) => SdkCheck::found(NAME, detail),
+        None => SdkCheck::missing(NAME, "set ANDROID_HOME to the Android SDK (run SetupAndroid from Engine/Extras/Android)"),
+    }
+}
+
+fn check_android_ndk(context: &SdkContext) -> SdkCheck {
+    const NAME: &str = "Android NDK";
+    if let Some((detail, _)) = context.sdk_dir(&["NDKROOT", "ANDROID_NDK_ROOT", "NDK_ROOT"], "Android") {
+        return SdkCheck::found(NAME, detail);
+    }
+    let side_by_side = ["ANDROID_HOME", "ANDROID_SDK_ROOT"]
+        .iter()
+        .find_map(|var| context.env_dir(var))
+        .and_then(|(_, sdk)| latest_subdir(&sdk.join("ndk")));
+    match side_by_side {
+        Some(ndk) => SdkCheck::found(NAME, ndk.to_string_lossy()),
+        None => SdkCheck::missing(NAME, "set NDKROOT or install an NDK under $ANDROID_HOME/ndk"),
+    }
+}
+
+fn check_jdk(context: &SdkContext) -> SdkCheck {
+    const NAME: &str = "Java Development Kit";
+    let java = if cfg!(target_os = "windows") { "java.exe" } else { "java" };
+    if let Some((detail, _)) = context
+        .env_dir("JAVA_HOME")
+        .filter(|(_, dir)| dir.join("bin").join(java).is_file())
+    {
+        return SdkCheck::found(NAME, detail);
+    }
+    let android_studio_jbr = if cfg!(target_os = "windows") {
+        PathBuf::from(r"C:\Program Files\Android\Android Studio\jbr")
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from("/Applications/Android Studio.app/Contents/jbr/Contents/Home")
+    } else {
+        PathBuf::from("/opt/android-studio/jbr")
+    };
+    if android_studio_jbr.join("bin").join(java).is_file() {
+        return SdkCheck::found(NAME, format!("Android Studio JBR {}", android_studio_jbr.to_string_lossy()));
+    }
+    SdkCheck::missing(NAME, "set JAVA_HOME to a JDK (Android Studio ships one in its jbr folder)")
+}
+
+fn check_ps4_sdk(context: &SdkContext) -> SdkCheck {
+    const NAME: &str = "PS4 SDK";
+    match context.sdk_dir(&["SCE_ORBIS_SDK_DIR"], "PS4") {
+        Some((detail, _)) => SdkCheck::found(NAME, detail),
+        None => SdkCheck::missing(NAME, "set SCE_ORBIS_SDK_DIR or install the SDK with Turnkey (AutoSDK)"),
+    }
+}
+
+fn check_ps5_sdk(context: &SdkContext) -> SdkCheck {
+    const NAME: &str = "PS5 SDK";
+    match context.sdk_dir(&["SCE_PROSPERO_SDK_DIR"], "PS5") {
+        Some((detail, _)) => SdkCheck::found(NAME, detail),
+        None => SdkCheck::missing(NAME, "set SCE_PROSPERO_SDK_DIR or install the SDK with Turnkey (AutoSDK)"),
+    }
+}
+
+fn check_gdk(context: &SdkContext) -> SdkCheck {
+    const NAME: &str = "Microsoft GDK";
+    match context.sdk_dir(&["GameDKLatest", "GRDKLatest", "GXDKLatest"], "GDK") {
+        Some((detail, _)) => SdkCheck::found(NAME, detail),
+        None => SdkCheck::missing(NAME, "install the Microsoft GDK (GameDKLatest is not set)"),
+    }
+}
+
+fn check_switch_sdk(context: &SdkContext) -> SdkCheck {
+    const NAME: &str = "Nintendo SDK";
+    match context.sdk_dir(&["NINTENDO_SDK_ROOT"], "Switch") {
+        Some((detail, _)) => SdkCheck::found(NAME, detail),
+        None => SdkCheck::missing(NAME, "set NINTENDO_SDK_ROOT or install the SDK with Turnkey (AutoSDK)"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{fixture, TempDir};
+
+    fn var(name: &str, value: &Path) -> (OsString, OsString) {
+        (name.into(), value.into())
+    }
+
+    /// Results of the checks for `platform`, as `(name, found)` pairs.
+    fn found(platform: Platform, context: &SdkContext) -> Vec<(&'static str, bool)> {
+        check_sdk(platform, context)
+            .into_iter()
+            .map(|check| (check.name, check.result.is_ok()))
+            .collect()
+    }
+
+    #[test]
+    fn auto_sdk_layout_satisfies_every_platform() {
+        let (_root, _project, engine) = fixture("sdk-auto");
+        let sdks = TempDir::new("sdk-auto-root");
+        for name in ["Win64", "Linux_x64", "Android", "PS4", "PS5", "GDK", "Switch"] {
+            fs::create_dir_all(sdks.join(auto_sdk_host()).join(name)).unwrap();
+        }
+        let jdk = sdks.join("jdk");
+        fs::create_dir_all(jdk.join("bin")).unwrap();
+        let java = if cfg!(target_os = "windows") { "java.exe" } else { "java" };
+        fs::write(jdk.join("bin").join(java), "").unwrap();
+        let context = SdkContext::with_vars(&engine, [var("UE_SDKS_ROOT", &sdks), var("JAVA_HOME", &jdk)]);
+
+        for platform in Platform::ALL.into_iter().filter(|platform| !matches!(platform, Platform::Mac | Platform::IOS)) {
+            for check in check_sdk(platform, &context) {
+                let detail = check.result.unwrap_or_else(|hint| panic!("{}: {}", check.name, hint));
+                assert!(detail.starts_with("AutoSDK ") || detail.starts_with("JAVA_HOME="), "{}", detail);
+            }
+        }
+        let detail = check_ps5_sdk(&context).result.unwrap();
+        assert!(detail.ends_with("PS5"), "{}", detail);
+    }
+
+    #[test]
+    fn missing_sdks_are_reported() {
+        let (_root, _project, engine) = fixture("sdk-missing");
+        let context = SdkContext::with_vars(&engine, []);
+        assert_eq!(found(Platform::PS4, &context), [("PS4 SDK", false)]);
+        assert_eq!(found(Platform::PS5, &context), [("PS5 SDK", false)]);
+        assert_eq!(found(Platform::XBoxSeries, &context), [("Microsoft GDK", false)]);
+        assert_eq!(found(Platform::Switch, &context), [("Nintendo SDK", false)]);
+        assert_eq!(found(Platform::Linux, &context), [("Linux toolchain", false)]);
+        assert_eq!(
+            found(Platform::Android, &context)[..2],
+            [("Android SDK", false), ("Android NDK", false)]
+        );
+        let hint = check_ps5_sdk(&context).result.unwrap_err();
+        assert!(hint.contains("SCE_PROSPERO_SDK_DIR"), "{}", hint);
+
+        // A variable naming a folder that does not exist does not count.
+        let context = SdkContext::with_vars(&engine, [var("SCE_PROSPERO_SDK_DIR", Path::new("/nonexistent/sdk"))]);
+        assert!(check_ps5_sdk(&context).result.is_err());
+    }
+
+    #[test]
+    fn environment_variables_come_before_auto_sdk() {
+        let (root, _project, engine) = fixture("sdk-env");
+        let sdks = TempDir::new("sdk-env-root");
+        fs::create_dir_all(sdks.join(auto_sdk_host()).join("PS5")).unwrap();
+        let context = SdkContext::with_vars(
+            &engine,
+            [var("UE_SDKS_ROOT", &sdks), var("SCE_PROSPERO_SDK_DIR", &root)],
+        );
+        let detail = check_ps5_sdk(&context).result.unwrap();
+        assert_eq!(detail, format!("SCE_PROSPERO_SDK_DIR={}", root.to_string_lossy()));
+
+        // An NDK installed side by side in the Android SDK.
+        let android = sdks.join("android");
+        fs::create_dir_all(android.join("ndk").join("25.1.8937393")).unwrap();
+        fs::create_dir_all(android.join("ndk").join("26.1.10909125")).unwrap();
+        let context = SdkContext::with_vars(&engine, [var("ANDROID_HOME", &android)]);
+        assert!(check_android_sdk(&context).result.unwrap().starts_with("ANDROID_HOME="));
+        assert!(check_android_ndk(&context).result.unwrap().ends_with("26.1.10909125"));
+    }
+
+    #[test]
+    fn linux_toolchain_can_be_bundled_with_the_engine() {
+        let (root, _project, engine) = fixture("sdk-linux");
+        let context = SdkContext::with_vars(&engine, []);
+        let toolchain = check_linux_toolchain(&context);
+        assert!(toolchain.result.is_err());
+
+        let bundled = root
+            .join("Engine")
+            .join("Extras")
+            .join("ThirdPartyNotUE")
+            .join("SDKs")
+            .join("HostLinux")
+            .join("Linux_x64")
+            .join("v22_clang-16.0.6-centos7");
+        fs::create_dir_all(&bundled).unwrap();
+        let toolchain = check_linux_toolchain(&context);
+        // Only a Linux host builds with the bundled toolchain.
+        assert_eq!(toolchain.result.is_ok(), cfg!(target_os = "linux"));
+
+        let context = SdkContext::with_vars(&engine, [var("LINUX_MULTIARCH_ROOT", &bundled)]);
+        assert!(check_linux_toolchain(&context).result.unwrap().starts_with("LINUX_MULTIARCH_ROOT="));
+    }
+}
@@ -0,0 +1,529 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::commands::BuildMode;
+use crate::jobs::{JobKind, JobQueue, JobSpec};
+use crate::platform::Platform;
+use crate::preflight::{run_preflight, Preflight};
+use crate::storage::Settings;
+
+/// Address the API server binds to when none is configured.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:8787";
+/// Largest request body accepted, in bytes.
+const MAX_BODY_SIZE: usize = 64 * 1024;
+/// How long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// Interval between keep-alive comments on idle event streams.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Returns the settings requests are resolved against. The GUI hands out
+/// the snapshot it last saved.
+pub type SettingsSource = Arc<dyn Fn() -> Settings + Send + Sync>;
+
+/// State shared by the connection threads.
+struct Context {
+    token: Option<String>,
+    settings: SettingsSource,
+    jobs: Arc<JobQueue>,
+}
+
+/// Local HTTP/JSON server exposing projects, engines and the job queue.
+/// Stops accepting connections when dropped.
+pub struct ApiServer {
+    address: SocketAddr,
+    stop: Arc<AtomicBool>,
+}
+
+impl ApiServer {
+    /// Binds `address` and serves requests on background threads. Jobs
+    /// started through the API are added to `jobs`.
+    pub fn start(
+        address: &str,
+        token: Option<String>,
+        settings: SettingsSource,
+        jobs: Arc<JobQueue>,
+    ) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let context = Arc::new(Context {
+            token: token.filter(|token| !token.is_empty()),
+            settings,
+            jobs,
+        });
+
+        let thread_stop = stop.clone();
+        let thread_context = context.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if thread_stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let context = thread_context.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = handle_connection(stream, &context) {
+                        eprintln!("API connection error: {}", e);
+                    }
+                });
+            }
+        });
+        println!("API server listening on http://{}", address);
+        Ok(Self { address, stop })
+    }
+
+    /// The address actually bound (useful when binding port 0).
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // Wake the accept loop so it notices the stop flag.
+        let mut wake = self.address;
+        if wake.ip().is_unspecified() {
+            wake.set_ip(Ipv4Addr::LOCALHOST.into());
+        }
+        let _ = TcpStream::connect(wake);
+    }
+}
+
+/// A parsed HTTP request.
+struct Request {
+    method: String,
+    path: String,
+    /// Header names are lowercased.
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+/// Body of `POST /api/jobs`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JobRequest {
+    /// "build", "package" or "clean".
+    kind: String,
+    /// Registered project name, alias or .uproject path.
+    project: String,
+    /// Registered engine solution; defaults to the selected engine.
+    engine: Option<PathBuf>,
+    /// Build profile supplying the platform and configuration.
+    profile: Option<String>,
+    platform: Option<String>,
+    config: Option<String>,
+    /// Queue the job even if preflight checks fail.
+    #[serde(default)]
+    force: bool,
+}
+
+/// Why a job request was not queued.
+enum JobRejection {
+    /// The request is malformed or names something unknown (400).
+    Invalid(String),
+    /// A preflight check failed and `force` was not set (409).
+    Preflight(Preflight),
+}
+
+impl From<String> for JobRejection {
+    fn from(message: String) -> Self {
+        JobRejection::Invalid(message)
+    }
+}
+
+fn read_request(stream: &TcpStream) -> Result<Request, String> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|e| e.to_string())?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err("malformed request line".to_owned());
+    };
+    let (method, path) = (method.to_owned(), path.to_owned());
+
+    let mut headers = HashMap::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line).map_err(|e| e.to_string())?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_owned());
+        }
+    }
+
+    let length = match headers.get("content-length") {
+        Some(length) => length.parse::<usize>().map_err(|_| "invalid Content-Length".to_owned())?,
+        None => 0,
+    };
+    if length > MAX_BODY_SIZE {
+        return Err("request body too large".to_owned());
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+    Ok(Request { method, path, headers, body })
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        415 => "Unsupported Media Type",
+        _ => "Internal Server Error",
+    }
+}
+
+fn write_json(stream: &mut TcpStream, status: u16, body: &Value) -> std::io::Result<()> {
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason_phrase(status),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn write_error(stream: &mut TcpStream, status: u16, message: &str) -> std::io::Result<()> {
+    write_json(stream, status, &json!({ "error": message }))
+}
+
+/// Compares two byte strings in time that depends only on their lengths, so
+/// that response times do not reveal how much of a token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn handle_connection(mut stream: TcpStream, context: &Context) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let request = match read_request(&stream) {
+        Ok(request) => request,
+        Err(message) => return write_error(&mut stream, 400, &message),
+    };
+    if let Some(token) = &context.token {
+        let expected = format!("Bearer {}", token);
+        let given = request.headers.get("authorization").map(String::as_str).unwrap_or_default();
+        if !constant_time_eq(given.as_bytes(), expected.as_bytes()) {
+            return write_error(&mut stream, 401, "missing or invalid bearer token");
+        }
+    }
+
+    let path = request.path.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["api", "projects"]) => {
+            let mut settings = (context.settings)();
+            let projects: Vec<Value> = settings
+                .projects
+                .iter_mut()
+                .map(|project| {
+                    if let Err(e) = project.refresh() {
+                        eprintln!("Failed to refresh project {:?}: {}", project.location, e);
+                    }
+                    project.summary()
+                })
+                .collect();
+            write_json(&mut stream, 200, &json!(projects))
+        }
+        ("GET", ["api", "engines"]) => {
+            let settings = (context.settings)();
+            let selected = settings.preferences.selected_engine.as_ref();
+            let engines: Vec<Value> = settings
+                .engines
+                .iter()
+                .map(|engine| engine.summary(Some(&engine.location) == selected))
+                .collect();
+            write_json(&mut stream, 200, &json!(engines))
+        }
+        ("GET", ["api", "profiles"]) => write_json(&mut stream, 200, &json!((context.settings)().profiles)),
+        ("GET", ["api", "jobs"]) => {
+            let jobs: Vec<Value> = context.jobs.with_jobs(|jobs| jobs.iter().map(|job| job.summary()).collect());
+            write_json(&mut stream, 200, &json!(jobs))
+        }
+        ("POST", ["api", "jobs"]) => {
+            let is_json = request
+                .headers
+                .get("content-type")
+                .is_some_and(|content_type| content_type.starts_with("application/json"));
+            if !is_json {
+                return write_error(&mut stream, 415, "expected an application/json body");
+            }
+            match start_job(&request.body, context) {
+                Ok((id, preflight)) => write_json(&mut stream, 201, &json!({ "id": id, "preflight": preflight.checks })),
+                Err(JobRejection::Invalid(message)) => write_error(&mut stream, 400, &message),
+                Err(JobRejection::Preflight(preflight)) => write_json(
+                    &mut stream,
+                    409,
+                    &json!({ "error": "preflight checks failed", "preflight": preflight.checks }),
+                ),
+            }
+        }
+        ("GET", ["api", "jobs", id]) => match id.parse().ok().and_then(|id| context.jobs.summary(id)) {
+            Some(job) => write_json(&mut stream, 200, &job),
+            None => write_error(&mut stream, 404, "no such job"),
+        },
+        ("GET", ["api", "jobs", id, "events"]) => match id.parse() {
+            Ok(id) => stream_events(&mut stream, &context.jobs, id),
+            Err(_) => write_error(&mut stream, 404, "no such job"),
+        },
+        (
+            _,
+            ["api", "projects" | "engines" | "profiles" | "jobs"] | ["api", "jobs", _] | ["api", "jobs", _, "events"],
+        ) => write_error(&mut stream, 405, "method not allowed"),
+        _ => write_error(&mut stream, 404, "not found"),
+    }
+}
+
+/// Resolves a job request against the settings, runs its preflight checks
+/// and queues it. Only registered projects and engines can be built.
+fn start_job(body: &[u8], context: &Context) -> Result<(u64, Preflight), JobRejection> {
+    let request: JobRequest = serde_json::from_slice(body).map_err(|e| format!("invalid job request: {}", e))?;
+    let settings = (context.settings)();
+    let project = settings
+        .find_project(&request.project)
+        .ok_or_else(|| format!("no registered project matches {:?}", request.project))?;
+    let engine = match &request.engine {
+        Some(location) => settings
+            .engines
+            .iter()
+            .find(|engine| &engine.location == location)
+            .ok_or_else(|| format!("engine {:?} is not registered", location))?,
+        None => settings.default_engine().ok_or("no engine selected".to_owned())?,
+    };
+    let profile = match &request.profile {
+        Some(name) => Some(settings.find_profile(name).ok_or_else(|| format!("no profile named {:?}", name))?),
+        None => None,
+    };
+    let platform_name = request
+        .platform
+        .as_deref()
+        .or(profile.map(|profile| profile.platform.as_str()))
+        .unwrap_or("Win64");
+    let platform = Platform::from_name(platform_name).ok_or_else(|| format!("unknown platform {:?}", platform_name))?;
+    let config_name = request
+        .config
+        .as_deref()
+        .or(profile.map(|profile| profile.configuration.as_str()))
+        .unwrap_or("Development");
+    let mode = BuildMode::from_name(config_name).ok_or_else(|| format!("unknown configuration {:?}", config_name))?;
+
+    let kind = JobKind::from_name(&request.kind)
+        .ok_or_else(|| format!("unknown job kind {:?}; expected build, package or clean", request.kind))?;
+    let mut spec = JobSpec::new(kind, project, engine, platform, mode);
+    if let Some(profile) = profile {
+        spec.apply_profile(profile, project);
+    }
+    let preflight = run_preflight(&spec, project);
+    if preflight.has_failures() && !request.force {
+        return Err(JobRejection::Preflight(preflight));
+    }
+    Ok((context.jobs.enqueue(spec), preflight))
+}
+
+/// Streams a job's updates as server-sent events: the updates so far, then
+/// new ones as they arrive. The stream ends with an `end` event carrying
+/// the job summary once the job has finished.
+fn stream_events(stream: &mut TcpStream, jobs: &JobQueue, id: u64) -> std::io::Result<()> {
+    if jobs.summary(id).is_none() {
+        return write_error(stream, 404, "no such job");
+    }
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n"
+    )?;
+
+    let mut seen = 0;
+    while let Some((events, next, finished)) = jobs.wait_for_events(id, seen, KEEP_ALIVE_INTERVAL) {
+        if events.is_empty() && !finished {
+            write!(stream, ": keep-alive\n\n")?;
+        }
+        for update in &events {
+            let data = serde_json::to_value(update).unwrap_or_default();
+            let name = data["event"].as_str().unwrap_or("message").to_owned();
+            write!(stream, "event: {}\ndata: {}\n\n", name, data)?;
+        }
+        seen = next;
+        if finished {
+            let summary = jobs.summary(id).unwrap_or_default();
+            write!(stream, "event: end\ndata: {}\n\n", summary)?;
+            break;
+        }
+        stream.flush()?;
+    }
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, TempDir};
+    use std::fs;
+
+    const BUILD_SCRIPT: &str = "#!/bin/sh\necho \"[1/2] Compile\"\necho \"[2/2] Link\"\necho \"BUILD SUCCESSFUL\"\n";
+
+    /// A throwaway engine and project: `Build.sh` prints two actions and
+    /// "BUILD SUCCESSFUL".
+    fn fixture(name: &str) -> (TempDir, Settings) {
+        let (root, project, engine) = test_support::fixture(name);
+        let batch_files = root.join("Engine").join("Build").join("BatchFiles");
+        for platform in ["Linux", "Mac"] {
+            let script = batch_files.join(platform).join("Build.sh");
+            fs::create_dir_all(script.parent().unwrap()).unwrap();
+            fs::write(&script, BUILD_SCRIPT).unwrap();
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+            }
+        }
+
+        let mut settings = Settings {
+            projects: vec![project],
+            engines: vec![engine.clone()],
+            ..Default::default()
+        };
+        settings.preferences.selected_engine = Some(engine.location);
+        (root, settings)
+    }
+
+    fn start(settings: Settings, token: Option<&str>) -> ApiServer {
+        let settings: SettingsSource = Arc::new(move || settings.clone());
+        ApiServer::start("127.0.0.1:0", token.map(str::to_owned), settings, JobQueue::new(1, None)).unwrap()
+    }
+
+    /// Sends one request and returns the status code and body.
+    fn request(server: &ApiServer, method: &str, path: &str, headers: &[&str], body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(server.address()).unwrap();
+        let mut head = format!("{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n", method, path, body.len());
+        for header in headers {
+            head.push_str(header);
+            head.push_str("\r\n");
+        }
+        write!(stream, "{}\r\n{}", head, body).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, body.to_owned())
+    }
+
+    fn post_job(server: &ApiServer, body: &str) -> (u16, String) {
+        request(server, "POST", "/api/jobs", &["Content-Type: application/json"], body)
+    }
+
+    #[test]
+    fn lists_projects_and_engines() {
+        let (_root, settings) = fixture("list");
+        let server = start(settings, None);
+
+        let (status, body) = request(&server, "GET", "/api/projects", &[], "");
+        assert_eq!(status, 200);
+        let projects: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(projects[0]["name"], "Game");
+        assert_eq!(projects[0]["engine_version"], "5.4");
+
+        let (status, body) = request(&server, "GET", "/api/engines", &[], "");
+        assert_eq!(status, 200);
+        let engines: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(engines[0]["selected"], true);
+
+        assert_eq!(request(&server, "GET", "/api/unknown", &[], "").0, 404);
+        assert_eq!(request(&server, "DELETE", "/api/projects", &[], "").0, 405);
+    }
+
+    #[test]
+    fn rejects_invalid_job_requests() {
+        let (_root, settings) = fixture("invalid");
+        let server = start(settings, None);
+
+        assert_eq!(post_job(&server, r#"{ "kind": "build", "project": "Other" }"#).0, 400);
+        assert_eq!(post_job(&server, r#"{ "kind": "deploy", "project": "Game" }"#).0, 400);
+        assert_eq!(post_job(&server, r#"{ "kind": "build", "project": "Game", "platform": "Amiga" }"#).0, 400);
+        let unregistered = r#"{ "kind": "build", "project": "Game", "engine": "/elsewhere/UE5.sln" }"#;
+        assert_eq!(post_job(&server, unregistered).0, 400);
+        let plain_text = ["Content-Type: text/plain"];
+        let valid = r#"{ "kind": "build", "project": "Game" }"#;
+        assert_eq!(request(&server, "POST", "/api/jobs", &plain_text, valid).0, 415);
+        assert_eq!(request(&server, "GET", "/api/jobs", &[], "").1, "[]");
+    }
+
+    #[test]
+    fn rejects_jobs_failing_preflight() {
+        let (_root, settings) = fixture("preflight");
+        let server = start(settings, None);
+
+        // PS5 needs a Windows host and a platform extension the fixture engine lacks.
+        let (status, body) = post_job(&server, r#"{ "kind": "build", "project": "Game", "platform": "PS5" }"#);
+        assert_eq!(status, 409, "{}", body);
+        let rejection: Value = serde_json::from_str(&body).unwrap();
+        let failed: Vec<&str> = rejection["preflight"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|check| check["status"] == "fail")
+            .filter_map(|check| check["name"].as_str())
+            .collect();
+        assert!(failed.contains(&"Platform"), "{}", body);
+        assert_eq!(request(&server, "GET", "/api/jobs", &[], "").1, "[]");
+    }
+
+    #[test]
+    fn requires_the_configured_token() {
+        let (_root, settings) = fixture("token");
+        let server = start(settings, Some("secret"));
+
+        assert_eq!(request(&server, "GET", "/api/projects", &[], "").0, 401);
+        assert_eq!(request(&server, "GET", "/api/projects", &["Authorization: Bearer wrong"], "").0, 401);
+        assert_eq!(request(&server, "GET", "/api/projects", &["Authorization: Bearer secreT"], "").0, 401);
+        assert_eq!(request(&server, "GET", "/api/projects", &["Authorization: Bearer secret2"], "").0, 401);
+        assert_eq!(request(&server, "GET", "/api/projects", &["Authorization: Bearer secret"], "").0, 200);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn streams_job_events() {
+        let (_root, settings) = fixture("events");
+        let server = start(settings, None);
+
+        // The fixture engine is not built and there may be no toolchain; skip the preflight failures.
+        let body = r#"{ "kind": "build", "project": "Game", "platform": "Linux", "force": true }"#;
+        let (status, body) = post_job(&server, body);
+        assert_eq!(status, 201, "{}", body);
+        let id = serde_json::from_str::<Value>(&body).unwrap()["id"].as_u64().unwrap();
+
+        // The stream replays earlier updates and closes once the process exits.
+        let (status, events) = request(&server, "GET", &format!("/api/jobs/{}/events", id), &[], "");
+        assert_eq!(status, 200);
+        assert!(events.contains("event: log\n"), "{}", events);
+        assert!(events.contains(r#"data: {"event":"progress","data":0.5}"#), "{}", events);
+        assert!(events.contains(r#"data: {"event":"finished","data":"Build finished"}"#), "{}", events);
+        let end = events.split("event: end\ndata: ").nth(1).expect("missing end event");
+        assert_eq!(serde_json::from_str::<Value>(end.trim()).unwrap()["status"], "succeeded");
+
+        let (status, body) = request(&server, "GET", &format!("/api/jobs/{}", id), &[], "");
+        assert_eq!(status, 200);
+        let job: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(job["platform"], "Linux");
+        assert_eq!(job["config"], "Development");
+        assert_eq!(job["status"], "succeeded");
+    }
+}
@@ -0,0 +1,248 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::disk::{format_size, walk_files};
+use crate::jobs::JobSpec;
+use crate::output::{staged_platform_dir, UtcDateTime};
+
+/// Number of largest files listed by a report.
+const TOP_FILES: usize = 20;
+/// Rows per section in the printed summary.
+const SUMMARY_ROWS: usize = 10;
+/// Extensions of pak and IoStore containers.
+const CONTAINER_EXTENSIONS: [&str; 3] = ["pak", "utoc", "ucas"];
+
+/// Bytes taken by a file, a file type or a container.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SizeEntry {
+    pub name: String,
+    pub bytes: u64,
+    pub files: u64,
+}
+
+/// Size breakdown of a staged build.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SizeReport {
+    pub total_bytes: u64,
+    pub files: u64,
+    /// Bytes per file extension (lowercase, `.pak`; empty for none), largest first.
+    pub by_type: Vec<SizeEntry>,
+    /// Every .pak, .utoc and .ucas file, by path.
+    pub containers: Vec<SizeEntry>,
+    /// The largest files, largest first.
+    pub largest: Vec<SizeEntry>,
+}
+
+impl SizeReport {
+    /// Walks the files under `dir`.
+    pub fn measure(dir: &Path) -> Self {
+        let mut report = SizeReport::default();
+        let mut by_type: BTreeMap<String, SizeEntry> = BTreeMap::new();
+        let mut files = Vec::new();
+        walk_files(dir, &mut |path, bytes, _| {
+            let name = relative_name(dir, path);
+            let extension = path
+                .extension()
+                .map(|extension| format!(".{}", extension.to_string_lossy().to_lowercase()))
+                .unwrap_or_default();
+            let entry = by_type.entry(extension.clone()).or_insert_with(|| SizeEntry {
+                name: extension.clone(),
+                ..Default::default()
+            });
+            entry.bytes += bytes;
+            entry.files += 1;
+            report.total_bytes += bytes;
+            report.files += 1;
+            let file = SizeEntry { name, bytes, files: 1 };
+            if extension.strip_prefix('.').is_some_and(|extension| CONTAINER_EXTENSIONS.contains(&extension)) {
+                report.containers.push(file.clone());
+            }
+            files.push(file);
+        });
+
+        report.by_type = by_type.into_values().collect();
+        report.by_type.sort_by_key(|entry| std::cmp::Reverse(entry.bytes));
+        report.containers.sort_by(|a, b| a.name.cmp(&b.name));
+        files.sort_by_key(|entry| std::cmp::Reverse(entry.bytes));
+        files.truncate(TOP_FILES);
+        report.largest = files;
+        report
+    }
+
+    /// Human-readable summary, with the changes since `previous` (a report
+    /// and the Unix time of its package) when given.
+    pub fn summary_lines(&self, previous: Option<(&SizeReport, u64)>) -> Vec<String> {
+        let before = previous.map(|(report, _)| report);
+        let mut lines = vec![match previous {
+            Some((report, finished)) => format!(
+                "Staged size: {} in {} files ({} since the package of {})",
+                format_size(self.total_bytes),
+                self.files,
+                format_change(report.total_bytes, self.total_bytes),
+                UtcDateTime::from_system_time(std::time::UNIX_EPOCH + std::time::Duration::from_secs(finished)).date()
+            ),
+            None => format!("Staged size: {} in {} files", format_size(self.total_bytes), self.files),
+        }];
+
+        let sections = [
+            ("By type", &self.by_type, before.map(|report| &report.by_type)),
+            ("Containers", &self.containers, before.map(|report| &report.containers)),
+        ];
+        for (title, entries, previous_entries) in sections {
+            if entries.is_empty() && previous_entries.is_none_or(|entries| entries.is_empty()) {
+                continue;
+            }
+            lines.push(format!("{}:", title));
+            match previous_entries {
+                Some(previous_entries) => {
+                    let changes = compare(previous_entries, entries);
+                    if changes.is_empty() {
+                        lines.push("  unchanged".to_owned());
+                    }
+                    for change in changes.iter().take(SUMMARY_ROWS) {
+                        lines.push(format!(
+                            "  {:<40} {:>10}  {}",
+                            change.name,
+                            format_size(change.after),
+                            format_change(change.before, change.after)
+                        ));
+                    }
+                }
+                None => {
+                    for entry in entries.iter().take(SUMMARY_ROWS) {
+                        lines.push(format!("  {:<40} {:>10}", entry.name, format_size(entry.bytes)));
+                    }
+                }
+            }
+        }
+
+        lines.push("Largest files:".to_owned());
+        for entry in self.largest.iter().take(SUMMARY_ROWS) {
+            lines.push(format!("  {:<60} {:>10}", entry.name, format_size(entry.bytes)));
+        }
+        lines
+    }
+}
+
+/// `path` relative to `dir`, with `/` separators.
+fn relative_name(dir: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(dir).unwrap_or(path);
+    let parts: Vec<String> = relative.iter().map(|part| part.to_string_lossy().into_owned()).collect();
+    parts.join("/")
+}
+
+/// How an entry changed between two reports.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SizeChange {
+    pub name: String,
+    /// 0 for new entries.
+    pub before: u64,
+    /// 0 for removed entries.
+    pub after: u64,
+}
+
+/// Entries of `after` and `before` that differ, largest change first.
+pub fn compare(before: &[SizeEntry], after: &[SizeEntry]) -> Vec<SizeChange> {
+    let mut changes: BTreeMap<&str, SizeChange> = BTreeMap::new();
+    for entry in before {
+        changes.insert(&entry.name, SizeChange { name: entry.name.clone(), before: entry.bytes, after: 0 });
+    }
+    for entry in after {
+        changes
+            .entry(&entry.name)
+            .or_insert_with(|| SizeChange { name: entry.name.clone(), before: 0, after: 0 })
+            .after = entry.bytes;
+    }
+    let mut changes: Vec<SizeChange> = changes.into_values().filter(|change| change.before != change.after).collect();
+    changes.sort_by_key(|change| std::cmp::Reverse(change.after.abs_diff(change.before)));
+    changes
+}
+
+/// e.g. "+35.0 MiB (+2.9%)", "new" or "removed".
+pub fn format_change(before: u64, after: u64) -> String {
+    match (before, after) {
+        (0, 0) => "unchanged".to_owned(),
+        (0, _) => "new".to_owned(),
+        (_, 0) => "removed".to_owned(),
+        _ if before == after => "unchanged".to_owned(),
+        _ => {
+            let sign = if after > before { '+' } else { '-' };
+            let percent = (after as f64 - before as f64) / before as f64 * 100.0;
+            format!("{}{} ({:+.1}%)", sign, format_size(after.abs_diff(before)), percent)
+        }
+    }
+}
+
+/// A report as written next to the staged build.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SizeReportFile {
+    pub report: SizeReport,
+    /// Unix time the package compared against finished, if any.
+    pub previous_finished: Option<u64>,
+    pub previous: Option<SizeReport>,
+}
+
+/// `<project>-<platform>-<config>.size.json` next to the staged platform folder.
+pub fn report_path(spec: &JobSpec) -> PathBuf {
+    staged_platform_dir(&spec.output_dir(), spec.platform).with_file_name(format!(
+        "{}-{}-{}.size.json",
+        spec.project, spec.platform, spec.mode
+    ))
+}
+
+/// Reads a report written by a package job.
+pub fn load_report(path: &Path) -> Result<SizeReportFile, Box<dyn std::error::Error>> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn measures_and_compares_staged_builds() {
+        let dir = TempDir::new("size");
+        let paks = dir.join("Game").join("Content").join("Paks");
+        fs::create_dir_all(&paks).unwrap();
+        fs::write(paks.join("pakchunk0-Windows.ucas"), vec![0; 3000]).unwrap();
+        fs::write(paks.join("pakchunk0-Windows.utoc"), vec![0; 100]).unwrap();
+        fs::write(paks.join("pakchunk0-Windows.PAK"), vec![0; 50]).unwrap();
+        fs::write(dir.join("Game.exe"), vec![0; 1000]).unwrap();
+        fs::write(dir.join("Manifest_NonUFSFiles_Win64"), vec![0; 10]).unwrap();
+
+        let report = SizeReport::measure(&dir);
+        assert_eq!((report.total_bytes, report.files), (4160, 5));
+        let types: Vec<(&str, u64)> = report.by_type.iter().map(|entry| (entry.name.as_str(), entry.bytes)).collect();
+        assert_eq!(types, [(".ucas", 3000), (".exe", 1000), (".utoc", 100), (".pak", 50), ("", 10)]);
+        let containers: Vec<&str> = report.containers.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(
+            containers,
+            [
+                "Game/Content/Paks/pakchunk0-Windows.PAK",
+                "Game/Content/Paks/pakchunk0-Windows.ucas",
+                "Game/Content/Paks/pakchunk0-Windows.utoc"
+            ]
+        );
+        assert_eq!(report.largest[0].name, "Game/Content/Paks/pakchunk0-Windows.ucas");
+
+        fs::write(paks.join("pakchunk0-Windows.ucas"), vec![0; 4500]).unwrap();
+        fs::remove_file(dir.join("Game.exe")).unwrap();
+        let later = SizeReport::measure(&dir);
+        let changes = compare(&report.by_type, &later.by_type);
+        assert_eq!(
+            changes,
+            [
+                SizeChange { name: ".ucas".to_owned(), before: 3000, after: 4500 },
+                SizeChange { name: ".exe".to_owned(), before: 1000, after: 0 },
+            ]
+        );
+        assert_eq!(format_change(3000, 4500), "+1.5 KiB (+50.0%)");
+        let lines = later.summary_lines(Some((&report, 1_792_333_805)));
+        assert_eq!(lines[0], "Staged size: 4.6 KiB in 4 files (+500 B (+12.0%) since the package of 2026-10-18)");
+    }
+}
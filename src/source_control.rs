@@ -0,0 +1,35 @@
+use std::path::{Path, MAIN_SEPARATOR};
+use std::process::Command;
+
+/// Revision of the working copy holding `dir`: the Git commit, or the last
+/// Perforce changelist synced to the workspace. `None` when neither can be
+/// found.
+pub fn detect_revision(dir: &Path) -> Option<String> {
+    git_revision(dir).or_else(|| perforce_changelist(dir))
+}
+
+fn git_revision(dir: &Path) -> Option<String> {
+    if !dir.ancestors().any(|ancestor| ancestor.join(".git").exists()) {
+        return None;
+    }
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()?;
+    let revision = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+    (output.status.success() && !revision.is_empty()).then_some(revision)
+}
+
+fn perforce_changelist(dir: &Path) -> Option<String> {
+    let files = format!("{}{}...#have", dir.to_string_lossy().trim_end_matches(MAIN_SEPARATOR), MAIN_SEPARATOR);
+    let output = Command::new("p4")
+        .current_dir(dir)
+        .args(["-ztag", "-F", "%change%", "changes", "-m1"])
+        .arg(files)
+        .output()
+        .ok()?;
+    let change = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+    (output.status.success() && !change.is_empty() && change.bytes().all(|b| b.is_ascii_digit())).then_some(change)
+}
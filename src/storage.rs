@@ -1,106 +1,771 @@
 ﻿use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::archive::ArchiveFormat;
+use crate::commands::BuildMode;
+use crate::platform::Platform;
+use crate::plugins::{self, PluginDescriptor, PluginReference};
+use std::env;
+use std::ffi::OsString;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Environment variable that overrides the configuration directory.
+pub const CONFIG_DIR_ENV: &str = "UBT_CONFIG_DIR";
+
+const APP_DIR_NAME: &str = "UnrealBuildTool";
+const PROJECTS_FILE: &str = "projects.json";
+const ENGINE_FILE: &str = "engine.json";
+const SETTINGS_FILE: &str = "settings.json";
+
+static CONFIG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Resolves the configuration directory, creates it and migrates any
+/// configuration files left in the working directory by older versions.
+/// `cli_override` (from `--config-dir`) wins over `UBT_CONFIG_DIR`, which
+/// wins over the platform default.
+pub fn init_config_dir(cli_override: Option<PathBuf>) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let dir = cli_override
+        .or_else(|| env::var_os(CONFIG_DIR_ENV).map(PathBuf::from))
+        .unwrap_or_else(default_config_dir);
+    fs::create_dir_all(&dir)?;
+    let dir = CONFIG_DIR.get_or_init(|| dir).clone();
+    migrate_working_dir_files(&dir)?;
+    eprintln!("Using configuration directory: {:?}", dir);
+    Ok(dir)
+}
+
+/// Returns the configuration directory, falling back to the platform default
+/// if `init_config_dir` has not been called.
+pub fn config_dir() -> PathBuf {
+    CONFIG_DIR.get_or_init(default_config_dir).clone()
+}
+
+/// Per-user configuration directory: `%APPDATA%` on Windows,
+/// `~/Library/Application Support` on macOS and `$XDG_CONFIG_HOME`
+/// (or `~/.config`) elsewhere.
+fn default_config_dir() -> PathBuf {
+    default_config_dir_from(|name| env::var_os(name))
+}
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
+/// `default_config_dir` with the environment read through `var`.
+fn default_config_dir_from(var: impl Fn(&str) -> Option<OsString>) -> PathBuf {
+    let base = if cfg!(target_os = "windows") {
+        var("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| PathBuf::from(home).join("Library").join("Application Support"))
+    } else {
+        var("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| var("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    match base {
+        Some(base) if cfg!(any(target_os = "windows", target_os = "macos")) => base.join(APP_DIR_NAME),
+        Some(base) => base.join(APP_DIR_NAME.to_lowercase()),
+        None => PathBuf::from("."),
+    }
+}
+
+/// Copies `projects.json` and `engine.json` from the working directory into
+/// the configuration directory, unless the latter already has its own copy
+/// or has been upgraded to a settings document.
+fn migrate_working_dir_files(dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    migrate_files_from(&env::current_dir()?, dir)
+}
+
+fn migrate_files_from(cwd: &Path, dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    if dir.join(SETTINGS_FILE).exists() || fs::canonicalize(cwd).ok() == fs::canonicalize(dir).ok() {
+        return Ok(());
+    }
+    for name in [PROJECTS_FILE, ENGINE_FILE] {
+        let legacy = cwd.join(name);
+        let target = dir.join(name);
+        if legacy.is_file() && !target.exists() {
+            fs::copy(&legacy, &target)?;
+            eprintln!("Migrated {:?} to {:?}", legacy, target);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
 pub struct Project {
     pub name: String,
     pub location: PathBuf,
     pub engine_version: String,
-    pub plugins: Vec<String>,
+    /// Plugins listed in the .uproject file.
+    pub plugins: Vec<PluginReference>,
+    /// `TargetPlatforms` of the .uproject file; empty means every platform.
+    pub target_platforms: Vec<String>,
+    /// Project-local plugins found under `Plugins/`.
+    #[serde(skip)]
+    pub local_plugins: Vec<PluginDescriptor>,
+    /// Optional display name shown instead of `name`.
+    pub alias: Option<String>,
+    /// Pinned projects are listed first.
+    pub pinned: bool,
+    /// Where the project's packages are staged and archived.
+    pub output: OutputSettings,
+    /// Run in order after each successful package.
+    #[serde(default = "default_post_actions")]
+    pub post_actions: Vec<PostAction>,
+    /// Set when the .uproject file no longer exists on disk.
+    #[serde(skip)]
+    pub missing: bool,
 }
 
 impl Project {
     /// Creates a new Project from the given .uproject file.
-    pub fn new(location: PathBuf) -> Self {
-        let name = location.file_stem().unwrap().to_string_lossy().to_string();
-        let engine_version = Self::get_engine_version(&location);
-        let plugins = Self::get_plugins(&location);
-        Self {
+    pub fn new(location: PathBuf) -> Result<Self, Box<dyn std::error::Error>> {
+        let name = location
+            .file_stem()
+            .ok_or_else(|| format!("{:?} is not a .uproject file", location))?
+            .to_string_lossy()
+            .to_string();
+        let uproject = read_uproject(&location)?;
+        let engine_version = Self::get_engine_version(&uproject);
+        let plugins = Self::get_plugins(&uproject);
+        let target_platforms = Self::get_target_platforms(&uproject);
+        let local_plugins = plugins::project_plugins(&location);
+        Ok(Self {
             name,
             location,
             engine_version,
             plugins,
+            target_platforms,
+            local_plugins,
+            alias: None,
+            pinned: false,
+            output: OutputSettings::default(),
+            post_actions: default_post_actions(),
+            missing: false,
+        })
+    }
+
+    /// Returns the alias if one is set, otherwise the project name.
+    pub fn display_name(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+
+    /// JSON summary shared by the command line and the API server.
+    pub fn summary(&self) -> Value {
+        json!({
+            "name": self.name,
+            "display_name": self.display_name(),
+            "location": self.location,
+            "engine_version": self.engine_version,
+            "enabled_plugins": self.enabled_plugin_names(),
+            "missing": self.missing,
+        })
+    }
+
+    /// Re-reads the engine version and plugins from the .uproject file.
+    /// A project whose file has disappeared is flagged as missing and keeps
+    /// its last known metadata.
+    pub fn refresh(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.missing = !self.location.is_file();
+        if self.missing {
+            return Ok(());
         }
+        let uproject = read_uproject(&self.location)?;
+        self.engine_version = Self::get_engine_version(&uproject);
+        self.plugins = Self::get_plugins(&uproject);
+        self.target_platforms = Self::get_target_platforms(&uproject);
+        self.local_plugins = plugins::project_plugins(&self.location);
+        Ok(())
     }
 
-    fn get_engine_version(location: &PathBuf) -> String {
-        let uproject_content = fs::read_to_string(location)
-            .expect("Unable to read uproject file");
-        let engine_association: serde_json::Value =
-            serde_json::from_str(&uproject_content)
-                .expect("Unable to parse uproject file");
-        if let Some(engine_association) = engine_association.get("EngineAssociation") {
-            let engine_association_str = engine_association.as_str().unwrap();
-            if engine_association_str.starts_with('{') && engine_association_str.ends_with('}') {
+    fn get_engine_version(uproject: &Value) -> String {
+        match uproject.get("EngineAssociation").and_then(Value::as_str) {
+            Some(association) if association.starts_with('{') && association.ends_with('}') => {
                 "From Source".to_string()
-            } else {
-                engine_association_str.to_string()
             }
-        } else {
-            "Unknown".to_string()
+            Some(association) => association.to_string(),
+            None => "Unknown".to_string(),
         }
     }
 
-    fn get_plugins(location: &PathBuf) -> Vec<String> {
-        let uproject_content = fs::read_to_string(location)
-            .expect("Unable to read uproject file");
-        let uproject: serde_json::Value =
-            serde_json::from_str(&uproject_content)
-                .expect("Unable to parse uproject file");
-        if let Some(plugins) = uproject.get("Plugins") {
-            plugins
-                .as_array()
-                .unwrap()
-                .iter()
-                .map(|plugin| {
-                    plugin.get("Name")
-                        .unwrap()
-                        .as_str()
-                        .unwrap()
-                        .to_string()
-                })
-                .collect()
-        } else {
-            Vec::new()
+    fn get_plugins(uproject: &Value) -> Vec<PluginReference> {
+        plugins::plugin_references(uproject)
+    }
+
+    fn get_target_platforms(uproject: &Value) -> Vec<String> {
+        uproject["TargetPlatforms"]
+            .as_array()
+            .map(|platforms| platforms.iter().filter_map(|name| name.as_str().map(str::to_owned)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Names of the plugins enabled in the .uproject file.
+    pub fn enabled_plugin_names(&self) -> Vec<&str> {
+        self.plugins
+            .iter()
+            .filter(|plugin| plugin.enabled)
+            .map(|plugin| plugin.name.as_str())
+            .collect()
+    }
+}
+
+/// Reads and parses a .uproject file.
+pub fn read_uproject(location: &Path) -> Result<Value, Box<dyn std::error::Error>> {
+    let uproject_content = fs::read_to_string(location)?;
+    Ok(serde_json::from_str(uproject_content.trim_start_matches('\u{feff}'))?)
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Engine {
+    pub location: PathBuf,
+}
+
+impl Engine {
+    /// Root directory of the engine (the folder holding the solution file).
+    pub fn root(&self) -> PathBuf {
+        self.location.parent().map(Path::to_path_buf).unwrap_or_default()
+    }
+
+    /// Reads `Engine/Build/Build.version`.
+    pub fn build_version(&self) -> Result<BuildVersion, Box<dyn std::error::Error>> {
+        let path = self.root().join("Engine").join("Build").join("Build.version");
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(content.trim_start_matches('\u{feff}'))?)
+    }
+
+    /// JSON summary shared by the command line and the API server.
+    pub fn summary(&self, selected: bool) -> Value {
+        json!({
+            "location": self.location,
+            "version": self.build_version().ok().map(|version| version.major_minor()),
+            "selected": selected,
+        })
+    }
+}
+
+/// Contents of an engine's `Engine/Build/Build.version` file.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct BuildVersion {
+    pub major_version: u32,
+    pub minor_version: u32,
+    pub patch_version: u32,
+    pub changelist: u64,
+    pub branch_name: String,
+}
+
+impl BuildVersion {
+    /// The "major.minor" version, as used by `EngineAssociation`.
+    pub fn major_minor(&self) -> String {
+        format!("{}.{}", self.major_version, self.minor_version)
+    }
+}
+
+/// A named set of build settings that can be reused across runs.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    pub platform: String,
+    pub configuration: String,
+    /// Replaces the project's output settings for packages using this profile.
+    pub output: Option<OutputSettings>,
+    /// Replaces the project's post actions for packages using this profile.
+    pub post_actions: Option<Vec<PostAction>>,
+    /// Version written to `Config/DefaultGame.ini` before packaging.
+    pub version: Option<VersionStamp>,
+}
+
+/// Version strings stamped into `Config/DefaultGame.ini` before a package.
+/// Templates may use the placeholders of output templates and `{counter}`,
+/// a per-project number incremented by every stamped package.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct VersionStamp {
+    /// `ProjectVersion`, e.g. `1.4.{counter}`.
+    pub project_version: String,
+    /// `BuildVersion` of the project settings, if set.
+    pub build_version: Option<String>,
+    /// `BuildConfiguration` of the packaging settings, e.g. `PPBC_{config}`.
+    pub build_configuration: Option<String>,
+    /// Leaves the stamped file in place after the package, e.g. to commit
+    /// the bump; otherwise the original file is restored.
+    pub keep: bool,
+}
+
+/// Where packages are staged and archived. Templates may use `{project}`,
+/// `{platform}`, `{config}`, `{date}`, `{time}` and `{changelist}`.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct OutputSettings {
+    /// Staging root; the project's `Builds` folder when unset. Relative
+    /// paths are relative to the project folder.
+    pub staging_root: Option<PathBuf>,
+    /// Subfolder of the staging root, e.g. `{platform}/{config}`.
+    pub staging_template: String,
+    /// Archive root; packages are not archived when unset.
+    pub archive_root: Option<PathBuf>,
+    /// Subfolder of the archive root, e.g. `{project}/{platform}/{config}/{date}-{changelist}`.
+    pub archive_template: String,
+    /// Number of archives kept next to each new one; 0 keeps them all.
+    pub keep_archives: usize,
+}
+
+/// Something done with a package once it succeeded.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PostAction {
+    /// Opens the staging directory in the file browser.
+    OpenFolder,
+    /// Copies the staging directory into `destination`.
+    CopyTo { destination: PathBuf },
+    /// Compresses the staged platform folder next to it, with a manifest of
+    /// file sizes and SHA-256 hashes.
+    #[serde(alias = "zip")]
+    Archive {
+        #[serde(default)]
+        format: ArchiveFormat,
+    },
+    /// Runs `command` through the shell from the staging directory.
+    Command { command: String },
+    /// Starts the packaged game, when it runs on this machine.
+    Launch,
+}
+
+impl PostAction {
+    pub fn label(&self) -> String {
+        match self {
+            PostAction::OpenFolder => "Open folder".to_owned(),
+            PostAction::CopyTo { destination } => format!("Copy to {}", destination.display()),
+            PostAction::Archive { format } => format!("Archive as .{}", format.extension()),
+            PostAction::Command { command } => format!("Run `{}`", command),
+            PostAction::Launch => "Launch game".to_owned(),
         }
     }
 }
 
-pub fn save_project_locations(projects: &[Project]) -> Result<(), Box<dyn std::error::Error>> {
-    let json = serde_json::to_string_pretty(projects)?;
-    fs::write("projects.json", json)?;
-    println!("projects.json file updated");
-    Ok(())
+/// Packages open their staging directory unless configured otherwise.
+fn default_post_actions() -> Vec<PostAction> {
+    vec![PostAction::OpenFolder]
+}
+
+/// User preferences that are not tied to a project or engine.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Preferences {
+    /// Location of the engine selected in the UI.
+    pub selected_engine: Option<PathBuf>,
+    /// Re-parse registered .uproject files when they change on disk.
+    pub watch_projects: bool,
+    /// Folders previously scanned for .uproject files.
+    pub scan_roots: Vec<PathBuf>,
+    /// Rescan `scan_roots` for new projects when the tool starts.
+    pub rescan_on_startup: bool,
+    /// Run the local HTTP/JSON API server while the GUI is open.
+    pub api_server: bool,
+    /// Address the API server binds to; empty means `127.0.0.1:8787`.
+    pub api_address: String,
+    /// When set, API requests must send `Authorization: Bearer <token>`.
+    pub api_token: Option<String>,
+    /// How many queued jobs may run at once (at least one).
+    pub max_parallel_jobs: usize,
+    /// Platforms ticked in the build matrix.
+    pub matrix_platforms: Vec<Platform>,
+    /// Configurations ticked in the build matrix.
+    pub matrix_configurations: Vec<BuildMode>,
+}
+
+/// Everything the tool persists, stored as a single versioned document.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    pub projects: Vec<Project>,
+    pub engines: Vec<Engine>,
+    pub profiles: Vec<Profile>,
+    pub preferences: Preferences,
+}
+
+impl Settings {
+    /// Returns the engine selected in the preferences, if it is registered.
+    pub fn selected_engine(&self) -> Option<&Engine> {
+        let selected = self.preferences.selected_engine.as_ref()?;
+        self.engines.iter().find(|engine| &engine.location == selected)
+    }
+
+    /// Finds a registered project by name, alias or .uproject path.
+    pub fn find_project(&self, query: &str) -> Option<&Project> {
+        let path = Path::new(query);
+        let canonical = fs::canonicalize(path).ok();
+        self.projects.iter().find(|project| {
+            project.name.eq_ignore_ascii_case(query)
+                || project.alias.as_deref().is_some_and(|alias| alias.eq_ignore_ascii_case(query))
+                || Some(&project.location) == canonical.as_ref()
+                || project.location == path
+        })
+    }
+
+    /// Finds a build profile by name.
+    pub fn find_profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The engine used when none is given explicitly: the selected one, or
+    /// the only registered engine.
+    pub fn default_engine(&self) -> Option<&Engine> {
+        let only_engine = match self.engines.as_slice() {
+            [engine] => Some(engine),
+            _ => None,
+        };
+        self.selected_engine().or(only_engine)
+    }
+}
+
+/// Current version of the settings document.
+pub const SETTINGS_VERSION: u32 = 2;
+
+/// Forward migrations; `MIGRATIONS[n]` upgrades a version `n` document to `n + 1`.
+const MIGRATIONS: &[fn(&mut Value)] = &[migrate_v0_to_v1, migrate_v1_to_v2];
+
+/// Version 0 is the pre-settings layout synthesized from `projects.json`
+/// and `engine.json`: `{ "projects": [...], "engine": {...} }`.
+fn migrate_v0_to_v1(document: &mut Value) {
+    let Some(object) = document.as_object_mut() else {
+        return;
+    };
+    let engine = object.remove("engine").filter(|engine| !engine.is_null());
+    let selected = engine.as_ref().and_then(|engine| engine.get("location").cloned());
+    object.insert("engines".into(), Value::Array(engine.into_iter().collect()));
+    object.insert("preferences".into(), json!({ "selected_engine": selected }));
 }
 
-pub fn load_project_locations() -> Result<Vec<Project>, Box<dyn std::error::Error>> {
-    if !Path::new("projects.json").exists() {
-        return Ok(Vec::new());
+/// Version 1 stored project plugins as bare names; version 2 stores the
+/// full .uproject plugin entries.
+fn migrate_v1_to_v2(document: &mut Value) {
+    let Some(projects) = document.get_mut("projects").and_then(Value::as_array_mut) else {
+        return;
+    };
+    for project in projects {
+        let Some(plugins) = project.get_mut("plugins").and_then(Value::as_array_mut) else {
+            continue;
+        };
+        for plugin in plugins.iter_mut() {
+            if let Some(name) = plugin.as_str() {
+                *plugin = json!({ "Name": name, "Enabled": true });
+            }
+        }
     }
-    let json = fs::read_to_string("projects.json")?;
-    let projects: Vec<Project> = serde_json::from_str(&json)?;
-    Ok(projects)
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Engine {
-    pub location: PathBuf,
+/// A settings document written by a newer version of the tool. It must be
+/// left as it is rather than replaced.
+#[derive(Debug)]
+pub struct NewerSettingsError {
+    pub version: u32,
+}
+
+impl std::fmt::Display for NewerSettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "settings version {} is newer than supported version {}",
+            self.version, SETTINGS_VERSION
+        )
+    }
 }
 
-pub fn save_engine_location(location: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-    let engine = Engine { location };
-    let json = serde_json::to_string_pretty(&engine)?;
-    fs::write("engine.json", json)?;
-    println!("engine.json file updated");
+impl std::error::Error for NewerSettingsError {}
+
+/// Applies every migration needed to bring `document` up to `SETTINGS_VERSION`.
+fn migrate(mut document: Value, from: u32) -> Result<Value, Box<dyn std::error::Error>> {
+    if from > SETTINGS_VERSION {
+        return Err(NewerSettingsError { version: from }.into());
+    }
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+        migration(&mut document);
+        eprintln!("Migrated settings from version {} to {}", version, version + 1);
+    }
+    if let Some(object) = document.as_object_mut() {
+        object.insert("version".into(), json!(SETTINGS_VERSION));
+    }
+    Ok(document)
+}
+
+/// Loads the settings document, migrating older documents (or the legacy
+/// `projects.json`/`engine.json` files) to the current version. A backup of
+/// the original files is kept whenever a migration happens.
+pub fn load_settings() -> Result<Settings, Box<dyn std::error::Error>> {
+    load_settings_in(&config_dir())
+}
+
+fn load_settings_in(dir: &Path) -> Result<Settings, Box<dyn std::error::Error>> {
+    let path = dir.join(SETTINGS_FILE);
+    if path.exists() {
+        let document: Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        let version = document.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+        if version == SETTINGS_VERSION {
+            return Ok(serde_json::from_value(document)?);
+        }
+        let backup = dir.join(format!("settings.v{}.bak.json", version));
+        fs::copy(&path, &backup)?;
+        eprintln!("Backed up settings to {:?}", backup);
+        let settings: Settings = serde_json::from_value(migrate(document, version)?)?;
+        save_settings_in(dir, &settings)?;
+        return Ok(settings);
+    }
+
+    let legacy_files = [dir.join(PROJECTS_FILE), dir.join(ENGINE_FILE)];
+    if !legacy_files.iter().any(|file| file.exists()) {
+        return Ok(Settings {
+            version: SETTINGS_VERSION,
+            ..Settings::default()
+        });
+    }
+    let read_legacy = |file: &Path| -> Result<Value, Box<dyn std::error::Error>> {
+        if file.exists() {
+            Ok(serde_json::from_str(&fs::read_to_string(file)?)?)
+        } else {
+            Ok(Value::Null)
+        }
+    };
+    let mut projects = read_legacy(&legacy_files[0])?;
+    if projects.is_null() {
+        projects = json!([]);
+    }
+    let document = json!({
+        "projects": projects,
+        "engine": read_legacy(&legacy_files[1])?,
+    });
+    let settings: Settings = serde_json::from_value(migrate(document, 0)?)?;
+    save_settings_in(dir, &settings)?;
+    for file in legacy_files.iter().filter(|file| file.exists()) {
+        let backup = file.with_extension("json.bak");
+        fs::rename(file, &backup)?;
+        eprintln!("Backed up {:?} to {:?}", file, backup);
+    }
+    Ok(settings)
+}
+
+/// Writes the settings document atomically (temp file plus rename).
+pub fn save_settings(settings: &Settings) -> Result<(), Box<dyn std::error::Error>> {
+    save_settings_in(&config_dir(), settings)
+}
+
+fn save_settings_in(dir: &Path, settings: &Settings) -> Result<(), Box<dyn std::error::Error>> {
+    let mut document = settings.clone();
+    document.version = SETTINGS_VERSION;
+    let json = serde_json::to_string_pretty(&document)?;
+    let path = dir.join(SETTINGS_FILE);
+    write_atomic(&path, json.as_bytes())?;
+    eprintln!("{:?} file updated", path);
     Ok(())
 }
 
-pub fn load_engine_location() -> Result<Option<Engine>, Box<dyn std::error::Error>> {
-    if !Path::new("engine.json").exists() {
+/// Renames a `settings.json` that could not be loaded to
+/// `settings.unreadable-<unix time>.json`, so that saving does not replace
+/// it. Returns the new path, or `None` if there is no settings document.
+pub fn set_aside_settings() -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+    set_aside_settings_in(&config_dir())
+}
+
+fn set_aside_settings_in(dir: &Path) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+    let path = dir.join(SETTINGS_FILE);
+    if !path.exists() {
         return Ok(None);
     }
-    let json = fs::read_to_string("engine.json")?;
-    let engine: Engine = serde_json::from_str(&json)?;
-    Ok(Some(engine))
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let aside = dir.join(format!("settings.unreadable-{}.json", seconds));
+    fs::rename(&path, &aside)?;
+    eprintln!("Moved unreadable settings to {:?}", aside);
+    Ok(Some(aside))
+}
+
+/// Writes `contents` to a sibling temp file, flushes it and renames it over `path`.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file_name = path.file_name().ok_or("path has no file name")?.to_os_string();
+    file_name.push(".tmp");
+    let temp_path = path.with_file_name(file_name);
+    {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn migrates_v0_engine_to_engine_list() {
+        let mut document = json!({ "projects": [], "engine": { "location": "/ue/UE5.sln" } });
+        migrate_v0_to_v1(&mut document);
+        assert_eq!(document["engines"], json!([{ "location": "/ue/UE5.sln" }]));
+        assert_eq!(document["preferences"], json!({ "selected_engine": "/ue/UE5.sln" }));
+        assert!(document.get("engine").is_none());
+
+        let mut without_engine = json!({ "projects": [], "engine": null });
+        migrate_v0_to_v1(&mut without_engine);
+        assert_eq!(without_engine["engines"], json!([]));
+        assert_eq!(without_engine["preferences"], json!({ "selected_engine": null }));
+    }
+
+    #[test]
+    fn migrates_v1_plugin_names_to_entries() {
+        let mut document = json!({
+            "projects": [{ "name": "Game", "plugins": ["Paper2D", { "Name": "Water", "Enabled": false }] }]
+        });
+        migrate_v1_to_v2(&mut document);
+        assert_eq!(
+            document["projects"][0]["plugins"],
+            json!([{ "Name": "Paper2D", "Enabled": true }, { "Name": "Water", "Enabled": false }])
+        );
+    }
+
+    #[test]
+    fn migrate_runs_every_step_from_the_given_version() {
+        let document = json!({ "projects": [{ "plugins": ["Paper2D"] }], "engine": null });
+        let migrated = migrate(document, 0).unwrap();
+        assert_eq!(migrated["version"], json!(SETTINGS_VERSION));
+        assert_eq!(migrated["engines"], json!([]));
+        assert_eq!(migrated["projects"][0]["plugins"][0], json!({ "Name": "Paper2D", "Enabled": true }));
+
+        // Version 1 documents already have an engine list and are left alone by the first step.
+        let document = json!({ "version": 1, "engines": [{ "location": "/ue/UE5.sln" }], "projects": [] });
+        let migrated = migrate(document, 1).unwrap();
+        assert_eq!(migrated["engines"], json!([{ "location": "/ue/UE5.sln" }]));
+        assert!(migrated.get("preferences").is_none());
+    }
+
+    #[test]
+    fn rejects_newer_settings_and_leaves_them_in_place() {
+        let error = migrate(json!({}), SETTINGS_VERSION + 1).unwrap_err();
+        assert!(error.is::<NewerSettingsError>());
+
+        let dir = TempDir::new("storage-newer");
+        let newer = format!("{{ \"version\": {}, \"projects\": [] }}", SETTINGS_VERSION + 1);
+        fs::write(dir.join(SETTINGS_FILE), &newer).unwrap();
+        let error = load_settings_in(&dir).unwrap_err();
+        assert!(error.is::<NewerSettingsError>());
+        assert_eq!(fs::read_to_string(dir.join(SETTINGS_FILE)).unwrap(), newer);
+    }
+
+    #[test]
+    fn migrates_and_backs_up_older_settings() {
+        let dir = TempDir::new("storage-v1");
+        let v1 = r#"{ "version": 1, "projects": [{ "name": "Game", "plugins": ["Paper2D"] }], "engines": [] }"#;
+        fs::write(dir.join(SETTINGS_FILE), v1).unwrap();
+        let settings = load_settings_in(&dir).unwrap();
+        assert_eq!(settings.projects[0].plugins[0].name, "Paper2D");
+        assert!(settings.projects[0].plugins[0].enabled);
+        assert_eq!(fs::read_to_string(dir.join("settings.v1.bak.json")).unwrap(), v1);
+        let saved: Value = serde_json::from_str(&fs::read_to_string(dir.join(SETTINGS_FILE)).unwrap()).unwrap();
+        assert_eq!(saved["version"], json!(SETTINGS_VERSION));
+    }
+
+    #[test]
+    fn imports_legacy_project_and_engine_files() {
+        let dir = TempDir::new("storage-legacy");
+        let projects = r#"[{ "name": "Game", "location": "/games/Game/Game.uproject", "engine_version": "5.4" }]"#;
+        fs::write(dir.join(PROJECTS_FILE), projects).unwrap();
+        fs::write(dir.join(ENGINE_FILE), r#"{ "location": "/ue/UE5.sln" }"#).unwrap();
+
+        let settings = load_settings_in(&dir).unwrap();
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(settings.projects.len(), 1);
+        assert_eq!(settings.projects[0].name, "Game");
+        assert_eq!(settings.engines.len(), 1);
+        assert_eq!(settings.preferences.selected_engine.as_deref(), Some(Path::new("/ue/UE5.sln")));
+        assert!(!dir.join(PROJECTS_FILE).exists() && !dir.join(ENGINE_FILE).exists());
+        assert_eq!(fs::read_to_string(dir.join("projects.json.bak")).unwrap(), projects);
+        assert!(dir.join(SETTINGS_FILE).exists());
+
+        // The imported document is read back as is.
+        let reloaded = load_settings_in(&dir).unwrap();
+        assert_eq!(reloaded.projects[0].location, settings.projects[0].location);
+    }
+
+    #[test]
+    fn picks_the_platform_config_dir() {
+        let vars = |name: &str| match name {
+            "APPDATA" => Some(OsString::from("/appdata")),
+            "HOME" => Some(OsString::from("/home/dev")),
+            "XDG_CONFIG_HOME" => Some(OsString::from("/xdg")),
+            _ => None,
+        };
+        let expected = if cfg!(target_os = "windows") {
+            PathBuf::from("/appdata").join("UnrealBuildTool")
+        } else if cfg!(target_os = "macos") {
+            PathBuf::from("/home/dev/Library/Application Support/UnrealBuildTool")
+        } else {
+            PathBuf::from("/xdg/unrealbuildtool")
+        };
+        assert_eq!(default_config_dir_from(vars), expected);
+        assert_eq!(default_config_dir_from(|_| None), PathBuf::from("."));
+        if cfg!(target_os = "linux") {
+            // A relative XDG_CONFIG_HOME is ignored.
+            let relative = |name: &str| match name {
+                "HOME" => Some(OsString::from("/home/dev")),
+                "XDG_CONFIG_HOME" => Some(OsString::from("relative")),
+                _ => None,
+            };
+            assert_eq!(default_config_dir_from(relative), PathBuf::from("/home/dev/.config/unrealbuildtool"));
+        }
+    }
+
+    #[test]
+    fn migrates_files_from_the_working_dir() {
+        let cwd = TempDir::new("storage-cwd");
+        let dir = TempDir::new("storage-config");
+        fs::write(cwd.join(PROJECTS_FILE), "[]").unwrap();
+        fs::write(cwd.join(ENGINE_FILE), "{ \"location\": \"/ue/UE5.sln\" }").unwrap();
+        // A copy the config dir already has is kept.
+        fs::write(dir.join(ENGINE_FILE), "{}").unwrap();
+
+        migrate_files_from(&cwd, &dir).unwrap();
+        assert_eq!(fs::read_to_string(dir.join(PROJECTS_FILE)).unwrap(), "[]");
+        assert_eq!(fs::read_to_string(dir.join(ENGINE_FILE)).unwrap(), "{}");
+        assert!(cwd.join(PROJECTS_FILE).exists());
+
+        // Nothing is copied once the config dir has a settings document, or
+        // when it is the working directory.
+        let upgraded = TempDir::new("storage-upgraded");
+        fs::write(upgraded.join(SETTINGS_FILE), "{}").unwrap();
+        migrate_files_from(&cwd, &upgraded).unwrap();
+        assert!(!upgraded.join(PROJECTS_FILE).exists());
+        migrate_files_from(&cwd, &cwd).unwrap();
+        assert_eq!(fs::read_dir(&*cwd).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn reports_unreadable_projects() {
+        let dir = TempDir::new("storage-project");
+        assert!(Project::new(dir.join("Missing.uproject")).is_err());
+        fs::write(dir.join("Broken.uproject"), "{ not json").unwrap();
+        assert!(Project::new(dir.join("Broken.uproject")).is_err());
+        fs::write(dir.join("Game.uproject"), r#"{ "EngineAssociation": "5.4" }"#).unwrap();
+        let project = Project::new(dir.join("Game.uproject")).unwrap();
+        assert_eq!((project.name.as_str(), project.engine_version.as_str()), ("Game", "5.4"));
+    }
+
+    #[test]
+    fn sets_aside_unreadable_settings() {
+        let dir = TempDir::new("storage-unreadable");
+        assert!(set_aside_settings_in(&dir).unwrap().is_none());
+
+        fs::write(dir.join(SETTINGS_FILE), "{ not json").unwrap();
+        assert!(load_settings_in(&dir).is_err());
+        let aside = set_aside_settings_in(&dir).unwrap().unwrap();
+        assert!(!dir.join(SETTINGS_FILE).exists());
+        assert_eq!(fs::read_to_string(&aside).unwrap(), "{ not json");
+        assert!(aside.file_name().unwrap().to_string_lossy().starts_with("settings.unreadable-"));
+    }
 }
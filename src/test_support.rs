@@ -0,0 +1,54 @@
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::storage::{Engine, Project};
+
+/// A fresh directory under the system temp dir, removed when dropped so
+/// that failing tests do not leave it behind.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "ubt-{}-{}-{}",
+            name,
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A throwaway engine (`UE5.sln` with a 5.4 `Build.version`) and an empty
+/// project (`Game/Game.uproject`) in a temp dir.
+pub fn fixture(name: &str) -> (TempDir, Project, Engine) {
+    let root = TempDir::new(name);
+    let build = root.join("Engine").join("Build");
+    fs::create_dir_all(&build).unwrap();
+    fs::write(build.join("Build.version"), r#"{ "MajorVersion": 5, "MinorVersion": 4 }"#).unwrap();
+    fs::write(root.join("UE5.sln"), "").unwrap();
+    let uproject = root.join("Game").join("Game.uproject");
+    fs::create_dir_all(uproject.parent().unwrap()).unwrap();
+    fs::write(&uproject, r#"{ "EngineAssociation": "5.4", "Plugins": [] }"#).unwrap();
+    let engine = Engine { location: root.join("UE5.sln") };
+    (root, Project::new(uproject).unwrap(), engine)
+}
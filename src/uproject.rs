@@ -0,0 +1,228 @@
+use serde_json::ser::PrettyFormatter;
+use serde_json::{json, Map, Value};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::storage::write_atomic;
+
+/// An editable .uproject document. Keys keep their original order and the
+/// file is written back with its original indentation and line endings, so
+/// an edit only changes the lines it touches.
+pub struct UProjectFile {
+    path: PathBuf,
+    document: Value,
+    indent: String,
+    crlf: bool,
+    bom: bool,
+    trailing_newline: bool,
+}
+
+impl UProjectFile {
+    /// Reads and parses a .uproject file.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path)?;
+        let bom = content.starts_with('\u{feff}');
+        let content = content.trim_start_matches('\u{feff}');
+        let document: Value = serde_json::from_str(content)?;
+        if !document.is_object() {
+            return Err(format!("{:?} is not a JSON object", path).into());
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            document,
+            indent: detect_indent(content),
+            crlf: content.contains("\r\n"),
+            bom,
+            trailing_newline: content.ends_with('\n'),
+        })
+    }
+
+    fn root_mut(&mut self) -> &mut Map<String, Value> {
+        self.document.as_object_mut().expect("uproject root is an object")
+    }
+
+    /// Returns the `Plugins` array, creating it if needed.
+    fn plugins_mut(&mut self) -> &mut Vec<Value> {
+        let plugins = self.root_mut().entry("Plugins").or_insert_with(|| json!([]));
+        if !plugins.is_array() {
+            *plugins = json!([]);
+        }
+        plugins.as_array_mut().unwrap()
+    }
+
+    fn find_plugin(plugins: &mut [Value], name: &str) -> Option<usize> {
+        plugins.iter().position(|plugin| {
+            plugin
+                .get("Name")
+                .and_then(Value::as_str)
+                .is_some_and(|plugin_name| plugin_name.eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// Sets the `Enabled` flag of a plugin entry, adding the entry if the
+    /// project does not list the plugin yet.
+    pub fn set_plugin_enabled(&mut self, name: &str, enabled: bool) {
+        let plugins = self.plugins_mut();
+        match Self::find_plugin(plugins, name) {
+            Some(index) => {
+                if let Some(plugin) = plugins[index].as_object_mut() {
+                    plugin.insert("Enabled".into(), json!(enabled));
+                }
+            }
+            None => plugins.push(json!({ "Name": name, "Enabled": enabled })),
+        }
+    }
+
+    /// Removes a plugin entry. Returns `false` if the project does not list it.
+    pub fn remove_plugin(&mut self, name: &str) -> bool {
+        let plugins = self.plugins_mut();
+        match Self::find_plugin(plugins, name) {
+            Some(index) => {
+                plugins.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sets a top-level field, keeping its position if it already exists.
+    pub fn set(&mut self, key: &str, value: Value) {
+        self.root_mut().insert(key.to_owned(), value);
+    }
+
+    /// Serializes the document using the original file's formatting.
+    fn to_text(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let mut buffer = Vec::new();
+        let formatter = PrettyFormatter::with_indent(self.indent.as_bytes());
+        let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+        self.document.serialize(&mut serializer)?;
+        let mut text = String::from_utf8(buffer)?;
+        if self.trailing_newline {
+            text.push('\n');
+        }
+        if self.crlf {
+            text = text.replace('\n', "\r\n");
+        }
+        if self.bom {
+            text.insert(0, '\u{feff}');
+        }
+        Ok(text)
+    }
+
+    /// Backs up the original file next to it (`<name>.uproject.bak`) and
+    /// writes the edited document. Returns the backup location.
+    pub fn save(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let text = self.to_text()?;
+        // Never write something Unreal would fail to parse.
+        serde_json::from_str::<Value>(text.trim_start_matches('\u{feff}'))?;
+
+        let mut backup_name = self.path.file_name().ok_or("path has no file name")?.to_os_string();
+        backup_name.push(".bak");
+        let backup = self.path.with_file_name(backup_name);
+        fs::copy(&self.path, &backup)?;
+        write_atomic(&self.path, text.as_bytes())?;
+        eprintln!("Updated {:?} (backup: {:?})", self.path, backup);
+        Ok(backup)
+    }
+}
+
+/// Returns the indentation of the first indented line, defaulting to the
+/// tab Unreal uses.
+fn detect_indent(content: &str) -> String {
+    content
+        .lines()
+        .skip(1)
+        .map(|line| &line[..line.len() - line.trim_start_matches([' ', '\t']).len()])
+        .find(|indent| !indent.is_empty())
+        .unwrap_or("\t")
+        .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    /// A .uproject as the editor writes it: tab indentation, CRLF line
+    /// endings and a byte order mark.
+    const EDITOR_FILE: &str = "\u{feff}{\r\n\
+        \t\"FileVersion\": 3,\r\n\
+        \t\"EngineAssociation\": \"5.4\",\r\n\
+        \t\"Category\": \"\",\r\n\
+        \t\"Plugins\": [\r\n\
+        \t\t{\r\n\
+        \t\t\t\"Name\": \"ModelingToolsEditorMode\",\r\n\
+        \t\t\t\"Enabled\": true,\r\n\
+        \t\t\t\"TargetAllowList\": [\r\n\
+        \t\t\t\t\"Editor\"\r\n\
+        \t\t\t]\r\n\
+        \t\t},\r\n\
+        \t\t{\r\n\
+        \t\t\t\"Name\": \"Paper2D\",\r\n\
+        \t\t\t\"Enabled\": false\r\n\
+        \t\t}\r\n\
+        \t],\r\n\
+        \t\"Description\": \"\"\r\n\
+        }\r\n";
+
+    fn load(dir: &TempDir, content: &str) -> UProjectFile {
+        let path = dir.join("Game.uproject");
+        fs::write(&path, content).unwrap();
+        UProjectFile::load(&path).unwrap()
+    }
+
+    #[test]
+    fn unchanged_document_round_trips() {
+        let dir = TempDir::new("uproject-round-trip");
+        assert_eq!(load(&dir, EDITOR_FILE).to_text().unwrap(), EDITOR_FILE);
+
+        let spaces = "{\n  \"FileVersion\": 3,\n  \"Plugins\": []\n}";
+        let file = load(&dir, spaces);
+        assert_eq!(file.indent, "  ");
+        assert_eq!(file.to_text().unwrap(), spaces);
+    }
+
+    #[test]
+    fn edits_only_touch_their_lines() {
+        let dir = TempDir::new("uproject-edit");
+        let mut file = load(&dir, EDITOR_FILE);
+        file.set_plugin_enabled("paper2d", true);
+        assert_eq!(
+            file.to_text().unwrap(),
+            EDITOR_FILE.replace("\"Paper2D\",\r\n\t\t\t\"Enabled\": false", "\"Paper2D\",\r\n\t\t\t\"Enabled\": true")
+        );
+
+        assert!(file.remove_plugin("ModelingToolsEditorMode"));
+        assert!(!file.remove_plugin("ModelingToolsEditorMode"));
+        let text = file.to_text().unwrap();
+        assert!(text.starts_with("\u{feff}{\r\n\t\"FileVersion\": 3,\r\n\t\"EngineAssociation\""));
+        assert!(text.contains("\t\"Plugins\": [\r\n\t\t{\r\n\t\t\t\"Name\": \"Paper2D\",\r\n\t\t\t\"Enabled\": true\r\n\t\t}\r\n\t],\r\n"));
+        assert!(text.ends_with("\t\"Description\": \"\"\r\n}\r\n"));
+        assert!(!text.replace("\r\n", "").contains('\n'));
+    }
+
+    #[test]
+    fn adds_plugins_and_the_plugin_list() {
+        let dir = TempDir::new("uproject-add");
+        let mut file = load(&dir, "{\n    \"FileVersion\": 3,\n    \"EngineAssociation\": \"5.4\"\n}\n");
+        file.set_plugin_enabled("Water", true);
+        assert_eq!(
+            file.to_text().unwrap(),
+            "{\n    \"FileVersion\": 3,\n    \"EngineAssociation\": \"5.4\",\n    \"Plugins\": [\n        {\n            \"Name\": \"Water\",\n            \"Enabled\": true\n        }\n    ]\n}\n"
+        );
+    }
+
+    #[test]
+    fn save_keeps_a_backup() {
+        let dir = TempDir::new("uproject-save");
+        let mut file = load(&dir, EDITOR_FILE);
+        file.set("EngineAssociation", json!("5.5"));
+        let backup = file.save().unwrap();
+        assert_eq!(fs::read_to_string(&backup).unwrap(), EDITOR_FILE);
+        assert_eq!(
+            fs::read_to_string(dir.join("Game.uproject")).unwrap(),
+            EDITOR_FILE.replace("\"5.4\"", "\"5.5\"")
+        );
+    }
+}
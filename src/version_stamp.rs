@@ -0,0 +1,393 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File, TryLockError};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex};
+use std::time::SystemTime;
+
+use crate::commands::ProgressUpdate;
+use crate::ini::set_value;
+use crate::jobs::JobSpec;
+use crate::output::{expand_template, TemplateValues, UtcDateTime, UNKNOWN_REVISION};
+use crate::source_control::detect_revision;
+use crate::storage::{write_atomic, VersionStamp};
+
+/// Per-project `{counter}` values, in the data directory.
+const COUNTERS_FILE: &str = "version-counters.json";
+/// Section of `ProjectVersion` and `BuildVersion`.
+pub const PROJECT_SETTINGS: &str = "/Script/EngineSettings.GeneralProjectSettings";
+/// Section of `BuildConfiguration`.
+const PACKAGING_SETTINGS: &str = "/Script/UnrealEd.ProjectPackagingSettings";
+/// Appended to the name of the copy of a config kept while it is stamped.
+/// The package that made it keeps it locked, so that other processes can
+/// tell it from one left by an interrupted package.
+const BACKUP_SUFFIX: &str = ".ubt-backup";
+
+/// Configs stamped by a running package of this process; the next package
+/// of the same project waits until they are restored.
+static STAMPED: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+static RESTORED: Condvar = Condvar::new();
+
+/// `Config/DefaultGame.ini` of the project of `uproject`.
+pub fn game_config_path(uproject: &Path) -> PathBuf {
+    uproject.parent().unwrap_or(Path::new("")).join("Config").join("DefaultGame.ini")
+}
+
+/// Where the original of a stamped `config` is kept until it is restored.
+fn backup_path(config: &Path) -> PathBuf {
+    let mut path = config.as_os_str().to_owned();
+    path.push(BACKUP_SUFFIX);
+    PathBuf::from(path)
+}
+
+/// What was found next to a config.
+#[derive(Debug, PartialEq)]
+enum Backup {
+    None,
+    /// A package of another process still has the config stamped.
+    InUse,
+    /// A backup left by an interrupted package, now written back.
+    Restored,
+}
+
+/// Writes `contents` to `path` through a temporary file, like
+/// `write_atomic`, and returns it locked: it is never seen unlocked.
+fn write_locked(path: &Path, contents: &[u8]) -> io::Result<File> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let mut file = File::create(&temp_path)?;
+    file.lock()?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(&temp_path, path)?;
+    Ok(file)
+}
+
+/// Puts back the `DefaultGame.ini` of `uproject` if a package was
+/// interrupted before restoring it, and returns its path if it did.
+pub fn restore_leftover_stamp(uproject: &Path) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+    let path = game_config_path(uproject);
+    if STAMPED.lock().unwrap().contains(&path) {
+        return Ok(None);
+    }
+    Ok((restore_backup(&path)? == Backup::Restored).then_some(path))
+}
+
+/// Writes the backup of `config` back over it and removes it, unless there
+/// is none or its package is still running.
+fn restore_backup(config: &Path) -> Result<Backup, Box<dyn std::error::Error>> {
+    let backup = backup_path(config);
+    let mut file = match File::open(&backup) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Backup::None),
+        Err(e) => return Err(e.into()),
+    };
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => return Ok(Backup::InUse),
+        Err(TryLockError::Error(e)) => return Err(e.into()),
+    }
+    // Its package may have restored and removed it before it was unlocked.
+    if !backup.exists() {
+        return Ok(Backup::None);
+    }
+    let mut original = Vec::new();
+    file.read_to_end(&mut original)?;
+    write_atomic(config, &original)?;
+    fs::remove_file(&backup)?;
+    Ok(Backup::Restored)
+}
+
+/// Holds a config for one package from stamping until it is restored.
+struct ConfigLock(PathBuf);
+
+impl ConfigLock {
+    /// Waits until no other package has `path` stamped, then takes it.
+    fn acquire(path: &Path) -> Self {
+        let mut stamped = STAMPED.lock().unwrap();
+        if stamped.contains(path) {
+            println!("Waiting for another package to restore {:?}", path);
+        }
+        while stamped.contains(path) {
+            stamped = RESTORED.wait(stamped).unwrap();
+        }
+        stamped.insert(path.to_path_buf());
+        Self(path.to_path_buf())
+    }
+}
+
+impl Drop for ConfigLock {
+    fn drop(&mut self) {
+        STAMPED.lock().unwrap().remove(&self.0);
+        RESTORED.notify_all();
+    }
+}
+
+/// A stamped `DefaultGame.ini`, with what is needed to put it back.
+pub struct StampedConfig {
+    path: PathBuf,
+    /// Contents before stamping, unless the stamp is kept. Also kept in
+    /// the backup file until restored.
+    original: Option<String>,
+    /// Keys and values written, in order.
+    values: Vec<(&'static str, String)>,
+    /// The locked backup, unless the stamp is kept.
+    _backup: Option<File>,
+    _lock: ConfigLock,
+}
+
+impl StampedConfig {
+    /// `ProjectVersion` written, if any.
+    pub fn project_version(&self) -> Option<String> {
+        self.values.iter().find(|(key, _)| *key == "ProjectVersion").map(|(_, value)| value.clone())
+    }
+
+    /// Writes the original contents back and removes the backup, unless the
+    /// stamp is kept. The backup stays if the config cannot be written.
+    pub fn restore(self, report: &mut dyn FnMut(ProgressUpdate)) {
+        let Some(original) = &self.original else {
+            return;
+        };
+        let restored = write_atomic(&self.path, original.as_bytes())
+            .and_then(|()| fs::remove_file(backup_path(&self.path)).map_err(Into::into));
+        match restored {
+            Ok(()) => report(ProgressUpdate::Log(format!("Restored {}", self.path.display()))),
+            Err(e) => {
+                eprintln!("Failed to restore {:?}: {}", self.path, e);
+                report(ProgressUpdate::Log(format!("Failed to restore {}: {}", self.path.display(), e)));
+            }
+        }
+    }
+}
+
+/// Increments and returns the `{counter}` of `uproject` kept in `data_dir`.
+/// Without a data directory every package is number 1.
+fn next_counter(data_dir: Option<&Path>, uproject: &Path) -> Result<u64, Box<dyn std::error::Error>> {
+    let Some(dir) = data_dir else {
+        return Ok(1);
+    };
+    let path = dir.join(COUNTERS_FILE);
+    let mut counters: BTreeMap<String, u64> = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    let counter = counters.entry(uproject.to_string_lossy().into_owned()).or_default();
+    *counter += 1;
+    let value = *counter;
+    fs::create_dir_all(dir)?;
+    write_atomic(&path, serde_json::to_string_pretty(&counters)?.as_bytes())?;
+    Ok(value)
+}
+
+/// Writes the versions of `stamp`, expanded for package `spec`, into the
+/// project's `DefaultGame.ini`, after waiting for any other package of the
+/// project to restore it and backing it up. The counter, if used, is kept
+/// in `data_dir`.
+pub fn stamp_version(
+    spec: &JobSpec,
+    stamp: &VersionStamp,
+    data_dir: Option<&Path>,
+) -> Result<StampedConfig, Box<dyn std::error::Error>> {
+    let path = game_config_path(&spec.uproject);
+    let lock = ConfigLock::acquire(&path);
+    match restore_backup(&path).map_err(|e| format!("cannot restore the backup of {}: {}", path.display(), e))? {
+        Backup::None => {}
+        Backup::InUse => return Err(format!("another process is packaging with a stamped {}", path.display()).into()),
+        Backup::Restored => println!("Restored {:?} left stamped by an interrupted package", path),
+    }
+    let original = fs::read_to_string(&path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    if fs::metadata(&path)?.permissions().readonly() {
+        return Err(format!("{} is read-only; check it out before packaging", path.display()).into());
+    }
+
+    let templates = [
+        (PROJECT_SETTINGS, "ProjectVersion", Some(&stamp.project_version)),
+        (PROJECT_SETTINGS, "BuildVersion", stamp.build_version.as_ref()),
+        (PACKAGING_SETTINGS, "BuildConfiguration", stamp.build_configuration.as_ref()),
+    ];
+    let templates: Vec<(&str, &'static str, &String)> = templates
+        .into_iter()
+        .filter_map(|(section, key, template)| Some((section, key, template.filter(|template| !template.is_empty())?)))
+        .collect();
+    if templates.is_empty() {
+        return Err("the version stamp sets no value".into());
+    }
+    let uses = |placeholder: &str| templates.iter().any(|(_, _, template)| template.contains(placeholder));
+
+    let counter = if uses("{counter}") { Some(next_counter(data_dir, &spec.uproject)?) } else { None };
+    let changelist = uses("{changelist}")
+        .then(|| detect_revision(spec.uproject.parent().unwrap_or(Path::new(""))))
+        .flatten();
+    let time = UtcDateTime::from_system_time(SystemTime::now());
+    let values = TemplateValues {
+        project: spec.project.clone(),
+        platform: spec.platform.name().to_owned(),
+        config: spec.mode.as_str().to_owned(),
+        date: time.date(),
+        time: time.time(),
+        changelist: changelist.unwrap_or_else(|| UNKNOWN_REVISION.to_owned()),
+    };
+
+    let mut content = original.clone();
+    let mut stamped = Vec::new();
+    for (section, key, template) in templates {
+        let template = match counter {
+            Some(counter) => template.replace("{counter}", &counter.to_string()),
+            None => template.clone(),
+        };
+        let value = expand_template(&template, &values);
+        content = set_value(&content, section, key, &value);
+        stamped.push((key, value));
+    }
+    let backup = if stamp.keep {
+        None
+    } else {
+        let backup = write_locked(&backup_path(&path), original.as_bytes())
+            .map_err(|e| format!("cannot back up {}: {}", path.display(), e))?;
+        Some(backup)
+    };
+    write_atomic(&path, content.as_bytes())?;
+    Ok(StampedConfig {
+        path,
+        original: (!stamp.keep).then_some(original),
+        values: stamped,
+        _backup: backup,
+        _lock: lock,
+    })
+}
+
+/// Restores a config left stamped by an interrupted package, then stamps
+/// the version of package `spec`, if it has one, and logs the values
+/// written.
+pub fn stamp_package(
+    spec: &JobSpec,
+    data_dir: Option<&Path>,
+    report: &mut dyn FnMut(ProgressUpdate),
+) -> Result<Option<StampedConfig>, Box<dyn std::error::Error>> {
+    if let Some(path) = restore_leftover_stamp(&spec.uproject)? {
+        report(ProgressUpdate::Log(format!("Restored {} left stamped by an interrupted package", path.display())));
+    }
+    let Some(stamp) = &spec.version_stamp else {
+        return Ok(None);
+    };
+    report(ProgressUpdate::Stage("Stamping project version...".to_owned()));
+    let stamped = stamp_version(spec, stamp, data_dir)?;
+    for (key, value) in &stamped.values {
+        report(ProgressUpdate::Log(format!("Set {}={} in {}", key, value, stamped.path.display())));
+    }
+    Ok(Some(stamped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::BuildMode;
+    use crate::ini::get_value;
+    use crate::jobs::JobKind;
+    use crate::platform::Platform;
+    use crate::test_support::fixture;
+
+    #[test]
+    fn stamps_and_restores_the_game_config() {
+        let (root, project, engine) = fixture("version");
+        let config = root.join("Game").join("Config");
+        fs::create_dir_all(&config).unwrap();
+        let original = "[/Script/EngineSettings.GeneralProjectSettings]\r\nProjectID=ABC\r\nProjectVersion=1.0.0\r\n";
+        fs::write(config.join("DefaultGame.ini"), original).unwrap();
+
+        let spec = JobSpec::new(JobKind::Package, &project, &engine, Platform::Linux, BuildMode::Shipping);
+        let stamp = VersionStamp {
+            project_version: "1.4.{counter}".to_owned(),
+            build_version: Some("{project}-{platform}".to_owned()),
+            build_configuration: Some("PPBC_{config}".to_owned()),
+            keep: false,
+        };
+        let data_dir = root.join("data");
+        let path = game_config_path(&spec.uproject);
+        for expected in ["1.4.1", "1.4.2"] {
+            let stamped = stamp_version(&spec, &stamp, Some(&data_dir)).unwrap();
+            let content = fs::read_to_string(&path).unwrap();
+            assert_eq!(get_value(&content, PROJECT_SETTINGS, "ProjectVersion").as_deref(), Some(expected));
+            assert_eq!(get_value(&content, PROJECT_SETTINGS, "BuildVersion").as_deref(), Some("Game-Linux"));
+            assert_eq!(
+                get_value(&content, PACKAGING_SETTINGS, "BuildConfiguration").as_deref(),
+                Some("PPBC_Shipping")
+            );
+            assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), original);
+            stamped.restore(&mut |_| {});
+            assert_eq!(fs::read_to_string(&path).unwrap(), original);
+            assert!(!backup_path(&path).exists());
+        }
+
+        let kept = VersionStamp { keep: true, ..stamp };
+        stamp_version(&spec, &kept, Some(&data_dir)).unwrap().restore(&mut |_| {});
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(get_value(&content, PROJECT_SETTINGS, "ProjectVersion").as_deref(), Some("1.4.3"));
+    }
+
+    #[test]
+    fn restores_a_leftover_stamp() {
+        let (root, project, engine) = fixture("version");
+        let config = root.join("Game").join("Config");
+        fs::create_dir_all(&config).unwrap();
+        let path = config.join("DefaultGame.ini");
+        fs::write(&path, "ProjectVersion=1.0.0\n").unwrap();
+        let spec = JobSpec::new(JobKind::Package, &project, &engine, Platform::Linux, BuildMode::Shipping);
+        let stamp = VersionStamp { project_version: "2.0.0".to_owned(), ..Default::default() };
+
+        // An interrupted package never restores its config, and its process
+        // no longer holds the backup.
+        drop(stamp_version(&spec, &stamp, None).unwrap());
+        assert!(fs::read_to_string(&path).unwrap().contains("2.0.0"));
+
+        assert_eq!(restore_leftover_stamp(&project.location).unwrap(), Some(path.clone()));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "ProjectVersion=1.0.0\n");
+        assert!(!backup_path(&path).exists());
+        assert_eq!(restore_leftover_stamp(&project.location).unwrap(), None);
+    }
+
+    #[test]
+    fn keeps_a_backup_another_process_still_holds() {
+        let (root, project, engine) = fixture("version");
+        let config = root.join("Game").join("Config");
+        fs::create_dir_all(&config).unwrap();
+        let path = config.join("DefaultGame.ini");
+        fs::write(&path, "ProjectVersion=1.0.0\n").unwrap();
+        let spec = JobSpec::new(JobKind::Package, &project, &engine, Platform::Linux, BuildMode::Shipping);
+        let stamp = VersionStamp { project_version: "2.0.0".to_owned(), ..Default::default() };
+
+        let stamped = stamp_version(&spec, &stamp, None).unwrap();
+        // Another process does not know about this one's packages.
+        STAMPED.lock().unwrap().remove(&path);
+        assert_eq!(restore_leftover_stamp(&project.location).unwrap(), None);
+        assert!(fs::read_to_string(&path).unwrap().contains("2.0.0"));
+        assert!(backup_path(&path).exists());
+        assert!(stamp_version(&spec, &stamp, None).is_err());
+
+        stamped.restore(&mut |_| {});
+        assert_eq!(fs::read_to_string(&path).unwrap(), "ProjectVersion=1.0.0\n");
+        assert!(!backup_path(&path).exists());
+    }
+
+    #[test]
+    fn stamps_one_package_of_a_project_at_a_time() {
+        let (root, project, engine) = fixture("version");
+        let config = root.join("Game").join("Config");
+        fs::create_dir_all(&config).unwrap();
+        let path = config.join("DefaultGame.ini");
+        fs::write(&path, "ProjectVersion=1.0.0\n").unwrap();
+        let spec = JobSpec::new(JobKind::Package, &project, &engine, Platform::Linux, BuildMode::Shipping);
+        let stamp = VersionStamp { project_version: "2.0.0".to_owned(), ..Default::default() };
+
+        let first = stamp_version(&spec, &stamp, None).unwrap();
+        let second = std::thread::spawn({
+            let (spec, stamp) = (spec.clone(), stamp.clone());
+            move || stamp_version(&spec, &stamp, None).unwrap().original
+        });
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert!(!second.is_finished());
+        first.restore(&mut |_| {});
+        // The second package saw the restored config, not the first stamp.
+        assert_eq!(second.join().unwrap().as_deref(), Some("ProjectVersion=1.0.0\n"));
+    }
+}
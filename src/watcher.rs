@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often watched files are polled for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Watches a set of files by polling their modification time on a
+/// background thread. Changed (or removed) files are reported on `changes`.
+pub struct FileWatcher {
+    paths: Arc<Mutex<Vec<PathBuf>>>,
+    stop: Arc<AtomicBool>,
+    changes: Receiver<PathBuf>,
+}
+
+impl FileWatcher {
+    /// Starts watching `paths`.
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        Self::with_interval(paths, POLL_INTERVAL)
+    }
+
+    /// Starts watching `paths`, polling them every `interval`.
+    fn with_interval(paths: Vec<PathBuf>, interval: Duration) -> Self {
+        let (tx, rx) = mpsc::channel::<PathBuf>();
+        let paths = Arc::new(Mutex::new(paths));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_paths = Arc::clone(&paths);
+        let thread_stop = Arc::clone(&stop);
+        thread::spawn(move || {
+            let mut last_seen: HashMap<PathBuf, Option<SystemTime>> = HashMap::new();
+            while !thread_stop.load(Ordering::Relaxed) {
+                let paths = thread_paths.lock().unwrap().clone();
+                last_seen.retain(|path, _| paths.contains(path));
+                for path in paths {
+                    let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+                    let previous = last_seen.insert(path.clone(), modified);
+                    if previous.is_some_and(|previous| previous != modified) && tx.send(path).is_err() {
+                        return;
+                    }
+                }
+                thread::sleep(interval);
+            }
+        });
+
+        Self {
+            paths,
+            stop,
+            changes: rx,
+        }
+    }
+
+    /// Replaces the set of watched files.
+    pub fn set_paths(&self, paths: Vec<PathBuf>) {
+        *self.paths.lock().unwrap() = paths;
+    }
+
+    /// Returns every file that changed since the last call.
+    pub fn changed_paths(&self) -> Vec<PathBuf> {
+        self.changes.try_iter().collect()
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use std::path::Path;
+    use std::time::Instant;
+
+    const INTERVAL: Duration = Duration::from_millis(10);
+
+    /// Gives the watcher time to poll a few times.
+    fn let_poll() {
+        thread::sleep(INTERVAL * 5);
+    }
+
+    /// Collects changes until `expected` have all been reported.
+    fn wait_for_changes(watcher: &FileWatcher, expected: &[PathBuf]) -> Vec<PathBuf> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut changed = Vec::new();
+        while !expected.iter().all(|path| changed.contains(path)) {
+            assert!(Instant::now() < deadline, "only saw {:?}", changed);
+            changed.extend(watcher.changed_paths());
+            thread::sleep(INTERVAL);
+        }
+        changed
+    }
+
+    /// Moves the modification time of `path` back by an hour, so the change
+    /// shows however coarse the file system's timestamps are.
+    fn touch(path: &Path) {
+        let modified = fs::metadata(path).unwrap().modified().unwrap();
+        let file = fs::OpenOptions::new().write(true).open(path).unwrap();
+        file.set_modified(modified - Duration::from_secs(3600)).unwrap();
+    }
+
+    #[test]
+    fn reports_modified_and_removed_files() {
+        let dir = TempDir::new("watcher");
+        let [modified, removed, untouched] = ["A.uproject", "B.uproject", "C.uproject"].map(|name| dir.join(name));
+        for path in [&modified, &removed, &untouched] {
+            fs::write(path, "{}").unwrap();
+        }
+        let watcher = FileWatcher::with_interval(vec![modified.clone(), removed.clone(), untouched.clone()], INTERVAL);
+        let_poll();
+        assert!(watcher.changed_paths().is_empty());
+
+        touch(&modified);
+        fs::remove_file(&removed).unwrap();
+        let changed = wait_for_changes(&watcher, &[modified.clone(), removed.clone()]);
+        assert!(!changed.contains(&untouched), "{:?}", changed);
+    }
+
+    #[test]
+    fn set_paths_retargets_the_watcher() {
+        let dir = TempDir::new("watcher");
+        let [old, new] = ["Old.uproject", "New.uproject"].map(|name| dir.join(name));
+        for path in [&old, &new] {
+            fs::write(path, "{}").unwrap();
+        }
+        let watcher = FileWatcher::with_interval(vec![old.clone()], INTERVAL);
+        let_poll();
+        watcher.set_paths(vec![new.clone()]);
+        let_poll();
+
+        touch(&old);
+        touch(&new);
+        let changed = wait_for_changes(&watcher, std::slice::from_ref(&new));
+        let_poll();
+        assert!(!changed.contains(&old) && !watcher.changed_paths().contains(&old));
+    }
+}